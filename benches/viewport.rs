@@ -0,0 +1,52 @@
+//! Render the viewport of a window holding many lines, with and without the
+//! cache of formatted lines.
+//!
+//! `cargo bench --bench viewport`
+
+use std::{collections::HashSet, hint::black_box};
+
+use cabin::ui::Window;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/// The number of lines held by the window.
+const LINES: u64 = 10_000;
+
+/// The number of lines which fit in the window.
+const HEIGHT: usize = 50;
+
+fn window() -> Window {
+    let mut window = Window::new(vec![0; 32], "default".to_string());
+    for i in 0..LINES {
+        let author = [(i % 7) as u8; 32];
+        let mut hash = [0; 32];
+        hash[..8].copy_from_slice(&i.to_be_bytes());
+        window.insert(
+            1_700_000_000_000 + i * 1_000,
+            Some(author),
+            Some(format!("peer{}", i % 7)),
+            &format!("message number {} of the benchmark", i),
+            Some(hash),
+        );
+    }
+    window
+}
+
+fn viewport(c: &mut Criterion) {
+    let blocked = HashSet::new();
+
+    let mut window = window();
+    c.bench_function("viewport (cached)", |b| {
+        b.iter(|| black_box(window.viewport(HEIGHT, &blocked, None, None)))
+    });
+
+    let mut window = self::window();
+    c.bench_function("viewport (uncached)", |b| {
+        b.iter(|| {
+            window.invalidate();
+            black_box(window.viewport(HEIGHT, &blocked, None, None))
+        })
+    });
+}
+
+criterion_group!(benches, viewport);
+criterion_main!(benches);
@@ -0,0 +1,60 @@
+//! A minimal bot which joins a channel and responds to every `!ping` posted
+//! in it with `pong`.
+//!
+//! Usage: `cargo run --example bot -- CABAL_ADDR HOST:PORT CHANNEL`
+
+use std::{
+    env,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use async_std::{prelude::*, task};
+use cable::post::PostBody;
+use cable_core::MemoryStore;
+
+use cabin::client::Client;
+
+type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+fn main() -> Result<(), Error> {
+    let args: Vec<String> = env::args().collect();
+    let (address, peer, channel) = match args.as_slice() {
+        [_, address, peer, channel] => (address, peer, channel),
+        _ => {
+            eprintln!("usage: bot CABAL_ADDR HOST:PORT CHANNEL");
+            return Ok(());
+        }
+    };
+    let address = (0..address.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&address[i..(i + 2).min(address.len())], 16))
+        .collect::<Result<Vec<u8>, _>>()?;
+
+    // Only posts made after the bot started are answered, rather than those
+    // synced from the history of the channel.
+    let started = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+
+    task::block_on(async move {
+        // Create a client with an in-memory store for each cabal, add the
+        // cabal and connect to a peer of it.
+        let mut client = Client::new(Box::new(|_address| Box::<MemoryStore>::default()));
+        client.add_cabal(&address);
+        client.set_nick(&address, "ping-bot").await?;
+        client.connect(&address, peer).await?;
+
+        // Join the channel and respond to each new ping as it arrives.
+        let mut posts = client.join(&address, channel).await?;
+        while let Some(Ok(post)) = posts.next().await {
+            if post.header.timestamp < started {
+                continue;
+            }
+            if let PostBody::Text { text, .. } = post.body {
+                if text.trim() == "!ping" {
+                    client.post(&address, channel, "pong").await?;
+                }
+            }
+        }
+
+        Ok(())
+    })
+}
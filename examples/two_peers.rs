@@ -0,0 +1,80 @@
+//! Two peers of the same cabal, linked by an in-memory stream within a single
+//! process: one posts to a channel and the other renders the post as the
+//! terminal UI would. No sockets are opened, so this also serves as a
+//! template for integration tests.
+//!
+//! Usage: `cargo run --example two_peers`
+
+use std::{collections::HashSet, time::Duration};
+
+use async_std::{future, prelude::*, task};
+use cable::post::PostBody;
+use cable_core::{MemoryStore, Store};
+
+use cabin::{client::Client, transport, ui::Window};
+
+type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// The channel in which the peers meet.
+const CHANNEL: &str = "default";
+
+/// The time to wait for the post to be synced before giving up.
+const SYNC_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn main() -> Result<(), Error> {
+    // Any 32 bytes serve as the address (aka. key) of the cabal.
+    let address = vec![0x5e; 32];
+
+    task::block_on(async move {
+        // Create a client for each peer, each with its own in-memory store.
+        let mut alice = Client::new(Box::new(|_address| Box::<MemoryStore>::default()));
+        let mut bob = Client::new(Box::new(|_address| Box::<MemoryStore>::default()));
+        alice.add_cabal(&address);
+        bob.add_cabal(&address);
+
+        // Link the peers, as a TCP connection between two processes would.
+        let (alice_end, bob_end) = transport::memory_pair();
+        alice.connect_stream(&address, alice_end)?;
+        bob.connect_stream(&address, bob_end)?;
+
+        // Alice introduces herself and posts to the channel.
+        alice.set_nick(&address, "alice").await?;
+        let _alice_posts = alice.join(&address, CHANNEL).await?;
+        alice.post(&address, CHANNEL, "hello from alice").await?;
+
+        // Bob joins the channel and renders the text posts he receives in a
+        // channel window, until the post of alice arrives.
+        let mut posts = bob.join(&address, CHANNEL).await?;
+        let mut window = Window::new(address.clone(), CHANNEL.to_string());
+        let blocked = HashSet::new();
+        loop {
+            let post = match future::timeout(SYNC_TIMEOUT, posts.next()).await {
+                Ok(Some(post)) => post?,
+                Ok(None) | Err(_) => return Err("the post of alice was not received".into()),
+            };
+            let PostBody::Text { text, .. } = post.body else {
+                continue;
+            };
+
+            let public_key = post.header.public_key;
+            let nick = match bob.cable(&address) {
+                Some(cable) => cable
+                    .store
+                    .get_peer_name_and_hash(&public_key)
+                    .await
+                    .map(|(nick, _hash)| nick),
+                None => None,
+            };
+            window.insert(post.header.timestamp, Some(public_key), nick, &text, None);
+            for line in window.take_unprinted(&blocked, None) {
+                println!("{}", line);
+            }
+
+            if text == "hello from alice" {
+                break;
+            }
+        }
+
+        Ok(())
+    })
+}
@@ -1,49 +1,1131 @@
 use std::{
-    collections::{HashMap, HashSet},
-    io::Read,
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    env, fs,
+    io::{self, BufRead, IsTerminal, Read},
+    net::{IpAddr, SocketAddr},
+    path::Path,
+    process::ChildStderr,
+    time::{Duration, Instant},
 };
 
+use argon2::{
+    password_hash::{PasswordHash, PasswordVerifier},
+    Argon2,
+};
 use async_std::{
-    net,
+    channel, future, net,
     prelude::*,
     sync::{Arc, Mutex},
     task,
 };
-use cable::{error::Error, post::PostBody, Channel, ChannelOptions};
-use cable_core::{CableManager, Store};
-use futures::{channel::mpsc, future::AbortHandle, stream::Abortable, SinkExt};
-use log::{debug, error};
-use terminal_keycode::KeyCode;
+use cable::{error::Error, post::PostBody, Channel, ChannelOptions, Hash, ToBytes};
+use cable_core::{CableManager, MemoryStore, Store};
+use futures::{
+    channel::mpsc,
+    future::{AbortHandle, Either},
+    io::{AsyncRead, AsyncWrite},
+    stream::Abortable,
+    SinkExt,
+};
+use log::{debug, error, warn};
+use signal_hook::{consts::SIGTSTP, low_level};
 
+#[cfg(feature = "websocket")]
+use crate::websocket;
 use crate::{
-    hex,
-    input::InputEvent,
-    time,
-    ui::{Addr, TermSize, Ui},
+    awaylog,
+    bridge::{self, BridgeConfig},
+    client::{self, Client, StorageFn, MAX_TEXT_LEN},
+    config::Config,
+    control, daemon, dial, exec,
+    feeds::{self, FeedConfig},
+    hex, history,
+    i18n::{self, tr, tr_args, usage},
+    input::{InputEvent, Key},
+    keys::{Action, Keybindings},
+    metrics::{self, Metered},
+    migrate,
+    nicks::NickCache,
+    notes,
+    offenders::{self, Offenders, Sniffed},
+    paths,
+    publish::{self, Entry},
+    redact::Redaction,
+    search::{Document, SearchIndex},
+    session::{CabalEntry, Session, WindowEntry},
+    settings::{Autofocus, HistoryMode},
+    store, stun,
+    time::{self, Clock, SystemClock},
+    transport,
+    ui::{Addr, Ignore, Level, Line, PublicKey, TermSize, Ui, MENTIONS_WINDOW, SYNC_INTERVAL},
+    utils,
+    webhooks::{self, WebhookConfig},
 };
 
-type StorageFn<S> = Box<dyn Fn(&str) -> Box<S>>;
-
 type CloseChannelSender = mpsc::UnboundedSender<Channel>;
 type CloseChannelReceiver = mpsc::UnboundedReceiver<Channel>;
 
-/// A TCP connection and associated address (host:post).
-#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+/// How the arguments of a command are split from a line of input.
+#[derive(Clone, Copy)]
+enum Arity {
+    /// The whole line is split into arguments (see `utils::parse_args`).
+    Args,
+    /// The given number of words (including the name of the command) are
+    /// split into arguments, followed by the rest of the line as free text
+    /// (see `utils::parse_args_then_text`).
+    ArgsThenText(usize),
+}
+
+/// The names of all commands (including aliases) handled by `App::handle`,
+/// along with how their arguments are split.
+const COMMANDS: [(&str, Arity); 53] = [
+    ("/awaylog", Arity::Args),
+    ("/block", Arity::Args),
+    ("/unblock", Arity::Args),
+    ("/cabal", Arity::Args),
+    ("/channels", Arity::Args),
+    ("/chanstats", Arity::Args),
+    ("/clear", Arity::Args),
+    ("/connect", Arity::ArgsThenText(1)),
+    ("/connections", Arity::Args),
+    ("/create", Arity::ArgsThenText(2)),
+    ("/delete", Arity::Args),
+    ("/disconnect", Arity::ArgsThenText(1)),
+    ("/doctor", Arity::Args),
+    ("/help", Arity::Args),
+    ("/info", Arity::Args),
+    ("/history", Arity::Args),
+    ("/ignore", Arity::Args),
+    ("/unignore", Arity::Args),
+    ("/invite", Arity::Args),
+    ("/join", Arity::Args),
+    ("/j", Arity::Args),
+    ("/leave", Arity::Args),
+    ("/listen", Arity::Args),
+    ("/lock", Arity::Args),
+    ("/members", Arity::Args),
+    ("/nick", Arity::Args),
+    ("/note", Arity::ArgsThenText(2)),
+    ("/unnote", Arity::Args),
+    ("/pin", Arity::Args),
+    ("/unpin", Arity::Args),
+    ("/pins", Arity::Args),
+    ("/publish", Arity::Args),
+    ("/redraw", Arity::Args),
+    ("/reply", Arity::ArgsThenText(2)),
+    ("/retry", Arity::Args),
+    ("/session", Arity::Args),
+    ("/set", Arity::Args),
+    ("/split", Arity::Args),
+    ("/unsplit", Arity::Args),
+    ("/sync", Arity::Args),
+    ("/toggle", Arity::Args),
+    ("/topic", Arity::ArgsThenText(1)),
+    ("/quit", Arity::Args),
+    ("/exit", Arity::Args),
+    ("/q", Arity::Args),
+    ("/whoami", Arity::Args),
+    ("/schedule", Arity::ArgsThenText(2)),
+    ("/search", Arity::ArgsThenText(1)),
+    ("/seen", Arity::Args),
+    ("/store", Arity::Args),
+    ("/whois", Arity::Args),
+    ("/win", Arity::ArgsThenText(3)),
+    ("/w", Arity::ArgsThenText(3)),
+];
+
+/// The syntax and description of each command, as listed by `/help`.
+const HELP: [(&str, &str); 71] = [
+    (
+        "/awaylog [clear]",
+        "list (or clear) mentions received while running as a daemon",
+    ),
+    ("/block PEER", "hide all posts from a peer"),
+    (
+        "/cabal add ADDR [ALIAS]",
+        "add a cabal with an optional local alias",
+    ),
+    ("/cabal set ADDR|ALIAS", "set the active cabal"),
+    ("/cabal list", "list all known cabals"),
+    (
+        "/cabal backfill [all|DURATION]",
+        "show or set how far back the channels of the active cabal are requested (e.g. 1d)",
+    ),
+    ("/channels", "list all known channels which are not hidden"),
+    (
+        "/channels --all",
+        "list all known channels, including hidden ones",
+    ),
+    (
+        "/channels hide|unhide CHANNEL",
+        "hide (or stop hiding) a channel in the channel list",
+    ),
+    (
+        "/chanstats [CHANNEL] [--days N]",
+        "count the posts of a channel by author and hour of the day",
+    ),
+    (
+        "/clear",
+        "clear the lines of the active window (posts remain stored)",
+    ),
+    ("/connections", "list all known network connections"),
+    ("/connect HOST:PORT", "connect to a peer over tcp"),
+    (
+        "/connect exec:COMMAND",
+        "connect to a peer through the input and output of a command",
+    ),
+    (
+        "/connect loopback",
+        "connect to a new in-process peer, for demos and testing",
+    ),
+    (
+        "/create CHANNEL [TOPIC]",
+        "join a new channel, set its topic and print an invite",
+    ),
+    ("/delete nick", "delete the most recent nick"),
+    (
+        "/delete N",
+        "delete the Nth most recent post in the active channel (if yours)",
+    ),
+    (
+        "/disconnect HOST:PORT|ADDR",
+        "close a connection or stop a listener",
+    ),
+    (
+        "/doctor [HOST:PORT]",
+        "check the terminal, storage, clock and connections (and a peer)",
+    ),
+    ("/history clear", "clear the input history"),
+    ("/ignore", "list ignored peers and post types"),
+    (
+        "/ignore PEER topics|joins|nicks",
+        "ignore topic changes, joins / leaves or nick changes of a peer",
+    ),
+    (
+        "/info N",
+        "show the metadata of the Nth most recent post in the active channel",
+    ),
+    (
+        "/invite [CHANNEL]",
+        "print an invite to the active cabal and channel",
+    ),
+    ("/join", "list known channels, most recently active first"),
+    ("/join CHANNEL", "join a channel (shorthand: /j CHANNEL)"),
+    (
+        "/listen PORT",
+        "listen for incoming tcp connections on all interfaces",
+    ),
+    ("/listen HOST:PORT", "listen for incoming tcp connections"),
+    (
+        "/listen ws://(HOST:)PORT",
+        "listen for incoming websocket connections",
+    ),
+    (
+        "/listen auto",
+        "listen on any free port and add its public address to invites",
+    ),
+    ("/lock", "hide all windows until the passphrase is entered"),
+    ("/members CHANNEL", "list all known members of the channel"),
+    (
+        "/note PEER [TEXT]",
+        "set (or show) a private local note about a peer",
+    ),
+    (
+        "/pin N",
+        "pin the Nth most recent post in the active channel",
+    ),
+    ("/pins", "list the pinned posts of the active channel"),
+    (
+        "/publish CHANNEL DIR",
+        "write the history of a channel to DIR as static html pages",
+    ),
+    ("/redraw", "repaint the entire screen"),
+    (
+        "/reply N TEXT",
+        "reply to the Nth most recent post in the active channel",
+    ),
+    (
+        "/retry [N]",
+        "resend the posts which failed to send (or only post N)",
+    ),
+    (
+        "/session save FILE",
+        "write cabals, aliases, channels and peers to a file",
+    ),
+    (
+        "/session load FILE",
+        "restore cabals, aliases, channels and peers from a file",
+    ),
+    ("/set", "list all settings and their values"),
+    (
+        "/set KEY VALUE",
+        "change a setting (e.g. /set bell on, /set autofocus never)",
+    ),
+    (
+        "/schedule WHEN TEXT",
+        "send a post to the active channel later (e.g. /schedule \"in 2h\" hi)",
+    ),
+    (
+        "/schedule list|cancel N",
+        "list the scheduled posts or cancel one",
+    ),
+    (
+        "/search WORD...",
+        "list the most recent posts containing every word",
+    ),
+    ("/seen PEER", "show when a peer last posted, and where"),
+    (
+        "/split [INDEX] INDEX",
+        "display a second window below the active window",
+    ),
+    (
+        "/store reindex",
+        "rebuild the search index from all stored posts",
+    ),
+    (
+        "/sync status",
+        "show the local posts, requests and recent arrivals of each channel",
+    ),
+    (
+        "/toggle hidden",
+        "reveal (dimmed) or hide posts from blocked peers",
+    ),
+    ("/topic", "list the topic of the active channel"),
+    ("/topic TOPIC", "set the topic of the active channel"),
+    (
+        "/topic history",
+        "list the previous topics of the active channel and who set them",
+    ),
+    ("/unblock PEER", "stop hiding posts from a peer"),
+    ("/unnote PEER", "remove the local note about a peer"),
+    (
+        "/unignore PEER [TYPE...]",
+        "stop ignoring the given (or all) post types of a peer",
+    ),
+    (
+        "/unpin N",
+        "unpin the Nth pinned post of the active channel",
+    ),
+    ("/unsplit", "display only the active window"),
+    ("/whoami", "list the local public key as a hex string"),
+    (
+        "/whois PEER",
+        "list the nickname, public key, channels and last post of a peer",
+    ),
+    (
+        "/win INDEX",
+        "change the active window (shorthand: /w INDEX)",
+    ),
+    (
+        "/win CHANNEL",
+        "change the active window to the best match for a channel name",
+    ),
+    (
+        "/win mentions",
+        "list posts mentioning you across all channels",
+    ),
+    (
+        "/win activity",
+        "summarise the recent activity of all channels",
+    ),
+    (
+        "/win network",
+        "list the opening and closing of peer connections",
+    ),
+    (
+        "/win move SRC DST",
+        "move a window to another position in the window list",
+    ),
+    (
+        "/win rename INDEX [NAME]",
+        "set (or remove) a local display name for a window",
+    ),
+    ("/exit", "exit the cabal process"),
+    ("/quit", "exit the cabal process (shorthand: /q)"),
+];
+
+/// The time allowed for each connection attempted by `/doctor`.
+const DOCTOR_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Timestamps (in milliseconds) earlier than this (2023-01-01) indicate that
+/// the system clock is wrong.
+const MIN_PLAUSIBLE_TIME: u64 = 1_672_531_200_000;
+
+/// The width (in characters) of the longest bar drawn by `/chanstats`.
+const CHANSTATS_BAR_WIDTH: usize = 30;
+
+/// The maximum number of authors listed by `/chanstats`.
+const CHANSTATS_AUTHORS: usize = 10;
+
+/// The maximum number of posts listed by `/search`.
+const SEARCH_RESULTS: usize = 20;
+
+/// The maximum interval between checks of the system clock while waiting to
+/// send a scheduled post, so that posts are sent on time after the system
+/// has been suspended.
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The period over which sent posts are counted for the `flood` setting.
+const FLOOD_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The time without further posts after which held posts are confirmed,
+/// allowing a paste or script to finish before the prompt is displayed.
+const FLOOD_SETTLE: Duration = Duration::from_millis(500);
+
+/// The interval at which the refresh setting is checked while channel
+/// request renewal is disabled.
+const REFRESH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The interval at which the store of each cabal is saved, if stores are
+/// persistent.
+const STORE_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The number of consecutive failures to read input after which the
+/// application exits, rather than retrying indefinitely.
+const MAX_INPUT_ERRORS: usize = 10;
+
+/// The maximum number of bytes of input read at once. An escape sequence
+/// sent by the terminal for a single key arrives within one read.
+const INPUT_BUFFER_LEN: usize = 1024;
+
+/// Determine whether the given passphrase matches the given Argon2 hash (a
+/// PHC string, as printed by `argon2 SALT -e`).
+///
+/// The parameters of the hash function are read from the hash, and the
+/// comparison takes constant time.
+fn passphrase_matches(passphrase: &str, hash: &str) -> bool {
+    PasswordHash::new(hash.trim()).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(passphrase.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+/// Return the public key and nickname (if one has been set) of the local
+/// peer.
+async fn local_peer<S: Store>(
+    address: &Addr,
+    store: &S,
+    nicks: &NickCache,
+) -> Option<(PublicKey, Option<String>)> {
+    let (public_key, _private_key) = store.get_keypair().await?;
+    let nickname = nicks.get(address, store, &public_key).await;
+
+    Some((public_key, nickname))
+}
+
+/// A TCP connection and associated address (host:post), along with the
+/// address of the cabal served over it.
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
 enum Connection {
-    Connected(String),
-    Listening(String),
+    Connected(Addr, String),
+    Listening(Addr, String),
+}
+
+/// Restrictions on the connections which are accepted or opened, protecting
+/// semi-private and public relays.
+#[derive(Debug, Default, Clone)]
+struct Admission {
+    /// IP addresses from which incoming connections are accepted, if
+    /// restricted.
+    allow: Option<HashSet<IpAddr>>,
+    /// The maximum number of incoming connections of each listener.
+    max_incoming: Option<usize>,
+    /// The maximum number of open connections, incoming and outgoing.
+    max_connections: Option<usize>,
+}
+
+impl Admission {
+    /// Describe the limit reached by a listener with the given number of
+    /// incoming connections, if any.
+    fn incoming_reached(&self, incoming: usize) -> Option<String> {
+        match self.max_incoming {
+            Some(max) if incoming >= max => {
+                Some(tr_args("listener limit of {} connections reached", &[&max]))
+            }
+            _ => self.total_reached(),
+        }
+    }
+
+    /// Describe the limit on the number of open connections, incoming and
+    /// outgoing, if it has been reached.
+    fn total_reached(&self) -> Option<String> {
+        match self.max_connections {
+            Some(max) if metrics::ACTIVE_CONNECTIONS.get() >= max as u64 => {
+                Some(tr_args("limit of {} connections reached", &[&max]))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The tasks spawned for a connection or listener, which are cancelled
+/// together by `/disconnect` or on exit.
+#[derive(Clone)]
+struct ConnectionTasks {
+    /// Closed to cancel the tasks.
+    cancel: channel::Sender<()>,
+    cancelled: channel::Receiver<()>,
+    /// The streams opened by the tasks.
+    streams: Arc<std::sync::Mutex<OpenStreams>>,
+}
+
+/// The open streams of a connection or listener, as listed by
+/// `/connections`.
+#[derive(Default)]
+struct OpenStreams {
+    /// The identifier of the next stream to be opened.
+    next: u64,
+    /// The address of the peer of each stream and the time at which it was
+    /// opened, keyed by identifier.
+    open: BTreeMap<u64, (String, Instant)>,
+    /// The identifiers of the open streams which were accepted from peers,
+    /// rather than opened to them.
+    incoming: BTreeSet<u64>,
+}
+
+/// A stream recorded by `ConnectionTasks::open_stream`, which is forgotten
+/// when dropped.
+struct StreamGuard {
+    streams: Arc<std::sync::Mutex<OpenStreams>>,
+    id: u64,
+}
+
+impl Drop for StreamGuard {
+    fn drop(&mut self) {
+        if let Ok(mut streams) = self.streams.lock() {
+            streams.open.remove(&self.id);
+            streams.incoming.remove(&self.id);
+        }
+    }
+}
+
+impl ConnectionTasks {
+    fn new() -> Self {
+        let (cancel, cancelled) = channel::bounded(1);
+
+        Self {
+            cancel,
+            cancelled,
+            streams: Arc::default(),
+        }
+    }
+
+    /// Record a stream opened with the peer with the given address, until
+    /// the returned guard is dropped (i.e. once the stream closes or the
+    /// task holding it is cancelled).
+    fn open_stream(&self, peer: String) -> StreamGuard {
+        self.record_stream(peer, false)
+    }
+
+    /// Record a stream accepted from the peer with the given address, as
+    /// `open_stream`.
+    fn accept_stream(&self, peer: String) -> StreamGuard {
+        self.record_stream(peer, true)
+    }
+
+    fn record_stream(&self, peer: String, incoming: bool) -> StreamGuard {
+        let mut streams = self.streams.lock().unwrap_or_else(|err| err.into_inner());
+        let id = streams.next;
+        streams.next += 1;
+        streams.open.insert(id, (peer, Instant::now()));
+        if incoming {
+            streams.incoming.insert(id);
+        }
+
+        StreamGuard {
+            streams: self.streams.clone(),
+            id,
+        }
+    }
+
+    /// Return the number of open streams which were accepted from peers.
+    fn incoming_streams(&self) -> usize {
+        let streams = self.streams.lock().unwrap_or_else(|err| err.into_inner());
+        streams.incoming.len()
+    }
+
+    /// Return the address of the peer of each open stream and the time for
+    /// which it has been open, oldest first.
+    fn open_streams(&self) -> Vec<(String, Duration)> {
+        let streams = self.streams.lock().unwrap_or_else(|err| err.into_inner());
+        streams
+            .open
+            .values()
+            .map(|(peer, opened)| (peer.to_owned(), opened.elapsed()))
+            .collect()
+    }
+
+    /// Determine whether the given tasks are the same as these, as for an
+    /// IPv4 address served by a dual-stack IPv6 listener.
+    fn is_shared_with(&self, other: &ConnectionTasks) -> bool {
+        Arc::ptr_eq(&self.streams, &other.streams)
+    }
+
+    /// Spawn a task which runs until it completes or the tasks are
+    /// cancelled.
+    fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let cancelled = self.cancelled.clone();
+        task::spawn(async move {
+            let cancelled = cancelled.recv();
+            futures::pin_mut!(future, cancelled);
+            futures::future::select(future, cancelled).await;
+        });
+    }
+
+    /// Cancel all tasks, including those still to be spawned.
+    fn cancel(&self) {
+        self.cancel.close();
+    }
+}
+
+/// Text posts which failed to send, kept to be resent by `/retry`.
+struct FailedPosts {
+    /// The posts, keyed by their number.
+    posts: BTreeMap<usize, FailedPost>,
+    /// The number of the next post which fails to send.
+    next: usize,
+}
+
+impl Default for FailedPosts {
+    fn default() -> Self {
+        Self {
+            posts: BTreeMap::new(),
+            next: 1,
+        }
+    }
+}
+
+/// A text post which failed to send, kept to be resent by `/retry`.
+struct FailedPost {
+    address: Addr,
+    channel: Channel,
+    text: String,
+    /// The index of the line marking the post as failed in its window, if
+    /// the window was open.
+    line: Option<u64>,
+}
+
+/// A post queued by `/schedule`, to be sent to a channel at a later time.
+struct ScheduledPost {
+    /// The time at which the post is sent, in milliseconds since the Unix
+    /// epoch.
+    due: u64,
+    channel: Channel,
+    text: String,
+    abort_handle: AbortHandle,
+}
+
+/// Posts held back because they were sent in rapid succession, awaiting
+/// confirmation.
+struct HeldPosts {
+    /// The cabal address and channel of each post, along with its text.
+    posts: Vec<(Addr, Channel, String)>,
+    /// Whether confirmation has been requested.
+    prompted: bool,
+}
+
+/// Protection against accidentally sending a flood of posts, such as when a
+/// multi-line paste is submitted line by line.
+#[derive(Default)]
+struct Flood {
+    /// The times at which recent posts were sent.
+    sent: VecDeque<Instant>,
+    /// Posts held back awaiting confirmation, if any.
+    held: Arc<Mutex<Option<HeldPosts>>>,
+}
+
+impl Flood {
+    /// Hold back the given post if posts are being sent in rapid succession,
+    /// returning `true` if the post was held.
+    ///
+    /// Once more than the given number of posts have been sent within
+    /// `FLOOD_INTERVAL`, further posts are held until no post has been held
+    /// for `FLOOD_SETTLE`, at which point confirmation to send them is
+    /// requested.
+    async fn hold(
+        &mut self,
+        address: &Addr,
+        channel: &Channel,
+        msg: &str,
+        limit: usize,
+        ui: Arc<Mutex<Ui>>,
+    ) -> bool {
+        let mut held = self.held.lock().await;
+        if let Some(held) = held.as_mut() {
+            held.posts
+                .push((address.to_owned(), channel.to_owned(), msg.to_owned()));
+            return true;
+        }
+        if limit == 0 {
+            return false;
+        }
+
+        let now = Instant::now();
+        while let Some(sent) = self.sent.front() {
+            if now.duration_since(*sent) < FLOOD_INTERVAL {
+                break;
+            }
+            self.sent.pop_front();
+        }
+        if self.sent.len() < limit {
+            self.sent.push_back(now);
+            return false;
+        }
+
+        *held = Some(HeldPosts {
+            posts: vec![(address.to_owned(), channel.to_owned(), msg.to_owned())],
+            prompted: false,
+        });
+        let held = self.held.clone();
+        task::spawn(async move {
+            // Wait for the paste (or script) to finish.
+            let mut seen = 0;
+            let count = loop {
+                task::sleep(FLOOD_SETTLE).await;
+                let mut held = held.lock().await;
+                let Some(held) = held.as_mut() else {
+                    return;
+                };
+                if held.posts.len() == seen {
+                    held.prompted = true;
+                    break seen;
+                }
+                seen = held.posts.len();
+            };
+
+            let mut ui = ui.lock().await;
+            let msg = tr_args("about to send {} messages, continue? (y/n)", &[&count]);
+            ui.get_active_window().write(&msg);
+            ui.update();
+        });
+
+        true
+    }
 }
 
 pub struct App<S: Store> {
     abort_handles: Arc<Mutex<HashMap<Channel, AbortHandle>>>,
-    cables: HashMap<Addr, CableManager<S>>,
+    aliases: HashMap<Addr, String>,
+    /// How far back the channels of each cabal are requested when joined, if
+    /// other than the default of two weeks.
+    backfill: HashMap<Addr, time::Backfill>,
+    /// Restrictions on the connections which are accepted or opened.
+    admission: Admission,
+    client: Client<S>,
     connections: HashSet<Connection>,
+    /// The tasks of each connection and listener.
+    connection_tasks: HashMap<Connection, ConnectionTasks>,
     close_channel_sender: CloseChannelSender,
-    storage_fn: StorageFn<S>,
+    /// The source of the current time, shared with the UI.
+    clock: Arc<dyn Clock>,
+    keys: Keybindings,
+    nicks: NickCache,
+    /// The full-text index of the text posts of all cabals.
+    index: SearchIndex,
+    /// An ambiguous channel name given to `/join`, which is joined verbatim
+    /// if the command is repeated.
+    pending_join: Option<String>,
+    /// A post awaiting confirmation (window index and text), held back
+    /// because the active window was recently changed.
+    pending_post: Option<(usize, String)>,
+    /// A post awaiting confirmation (window index and text) because it
+    /// exceeds the maximum length of a post and will be split.
+    pending_split: Option<(usize, String)>,
+    /// Hashes of locally pinned posts, keyed by cabal address and channel.
+    pins: HashMap<(Addr, Channel), Vec<Hash>>,
+    /// Channels omitted from `/channels` unless `--all` is given, keyed by
+    /// cabal address.
+    hidden: HashMap<Addr, BTreeSet<Channel>>,
+    /// Addresses from which incoming connections have recently failed.
+    offenders: Offenders,
+    /// The STUN server (host:port) queried for the public IP address of the
+    /// local peer by `/listen auto`, if any.
+    stun: Option<String>,
+    /// The addresses at which listeners started by `/listen auto` are
+    /// reachable, keyed by cabal address. They are included in invites.
+    advertised: HashMap<Addr, Vec<String>>,
+    /// The Argon2 hash (as a PHC string) of the passphrase which unlocks
+    /// the UI after `/lock`, if any.
+    lock: Option<String>,
+    /// The patterns of sensitive lines of input, which are not recorded in
+    /// the input history or echoed in full.
+    redaction: Redaction,
+    /// Protection against floods of posts, configured by the `flood`
+    /// setting.
+    flood: Flood,
+    /// Whether the store of each cabal is saved with `store::save()`.
+    persistent: bool,
+    /// Posts queued by `/schedule`, keyed by their number.
+    scheduled: Arc<Mutex<BTreeMap<usize, ScheduledPost>>>,
+    /// The number of the next post queued by `/schedule`.
+    next_scheduled: usize,
+    /// Posts which failed to send, shared with the tasks sending scheduled
+    /// posts.
+    failed: Arc<Mutex<FailedPosts>>,
     pub ui: Arc<Mutex<Ui>>,
     exit: bool,
 }
 
+/// Report the failure of the connection with the given peer.
+///
+/// Cable does not negotiate a protocol version when a connection is opened,
+/// so a peer speaking an incompatible version is only noticed once its
+/// messages fail to decode. This is suggested unless the connection itself
+/// failed (`io_failed`), e.g. by being reset or timing out.
+async fn report_connection_error(ui: &Arc<Mutex<Ui>>, peer: &str, err: Error, io_failed: bool) {
+    warn!("Cable stream listener error ({}): {}", peer, err);
+    let hint = if io_failed {
+        ""
+    } else {
+        tr(" (the peer may speak an incompatible protocol version)")
+    };
+    let mut ui = ui.lock().await;
+    ui.write_network_alert(&tr_args(
+        "connection with {} failed: {}{}",
+        &[&peer, &err, &hint],
+    ));
+    ui.update();
+}
+
+/// Accept incoming connections on the given listener, passing each stream
+/// to the given cable manager (after a WebSocket handshake, if `websocket` is
+/// set) in a task spawned with the given tasks of the listener.
+#[cfg_attr(not(feature = "websocket"), allow(unused_variables))]
+async fn accept_connections<S: Store>(
+    listener: net::TcpListener,
+    websocket: bool,
+    cable: CableManager<S>,
+    ui: Arc<Mutex<Ui>>,
+    admission: Admission,
+    offenders: Offenders,
+    tasks: ConnectionTasks,
+) {
+    debug!("Listening for incoming TCP connections...");
+
+    // Listen for incoming TCP connections and spawn a
+    // cable listener for each stream.
+    let mut incoming = listener.incoming();
+    while let Some(stream) = incoming.next().await {
+        debug!("Received an incoming TCP connection");
+        if let Ok(stream) = stream {
+            // Drop connections from peers which are not in the
+            // allowlist, if one is configured.
+            if let Some(allow) = &admission.allow {
+                let peer = stream.peer_addr().ok().map(|addr| addr.ip());
+                if !peer.is_some_and(|ip| allow.contains(&ip)) {
+                    let peer = peer
+                        .map(|ip| ip.to_string())
+                        .unwrap_or_else(|| tr("unknown address").to_string());
+                    warn!("Dropped connection from {}: not in allowlist", peer);
+                    let mut ui = ui.lock().await;
+                    ui.write_network(&tr_args(
+                        "dropped connection from {}: not in allowlist",
+                        &[&peer],
+                    ));
+                    ui.update();
+                    continue;
+                }
+            }
+
+            // Connections from addresses which have repeatedly
+            // failed are dropped without notice.
+            let peer = stream.peer_addr().ok();
+            if let Some(peer) = peer {
+                if offenders.is_ignored(&peer.ip()).await {
+                    debug!("Ignored connection from {}", peer);
+                    continue;
+                }
+            }
+
+            // Connections beyond the limits are closed before any messages
+            // are exchanged, rather than being left to time out.
+            if let Some(limit) = admission.incoming_reached(tasks.incoming_streams()) {
+                debug!("Closed connection from {}: {}", display_peer(peer), limit);
+                let _ = stream.shutdown(std::net::Shutdown::Both);
+                let mut ui = ui.lock().await;
+                ui.write_network(&tr_args(
+                    "closed connection from {}: {}",
+                    &[&display_peer(peer), &limit],
+                ));
+                ui.update();
+                continue;
+            }
+
+            let cable = cable.clone();
+            let ui = ui.clone();
+            let offenders = offenders.clone();
+            // The stream is listed by `/connections` until the task ends.
+            let guard = tasks.accept_stream(display_peer(peer));
+            tasks.spawn(async move {
+                let _guard = guard;
+                let accepted = Instant::now();
+                {
+                    let mut ui = ui.lock().await;
+                    ui.write_network(&tr_args(
+                        "accepted connection from {}",
+                        &[&display_peer(peer)],
+                    ));
+                    ui.update();
+                }
+
+                // Posts are relayed between all peers of the
+                // cabal, whichever transport they connected with.
+                #[cfg(feature = "websocket")]
+                if websocket {
+                    let stream = match websocket::accept(stream).await {
+                        Ok(stream) => stream,
+                        Err(err) => {
+                            debug!("WebSocket handshake failed: {}", err);
+                            let client = "a client which did not open a websocket";
+                            report_offence(&ui, &offenders, peer, client).await;
+                            return;
+                        }
+                    };
+                    {
+                        let mut ui = ui.lock().await;
+                        ui.write_network(&tr_args(
+                            "websocket handshake with {} completed",
+                            &[&display_peer(peer)],
+                        ));
+                        ui.update();
+                    }
+                    let stream = Sniffed::new(stream);
+                    let metered = Metered::new(stream.clone());
+                    let result = cable.listen(metered.clone()).await;
+                    let io_failed = metered.io_failed();
+                    report_incoming_close(
+                        &ui, &offenders, peer, accepted, &stream, io_failed, result,
+                    )
+                    .await;
+                    return;
+                }
+
+                let stream = Sniffed::new(stream);
+                let metered = Metered::new(stream.clone());
+                let result = cable.listen(metered.clone()).await;
+                let io_failed = metered.io_failed();
+                report_incoming_close(&ui, &offenders, peer, accepted, &stream, io_failed, result)
+                    .await;
+            });
+        }
+    }
+}
+
+/// Replace each pair of IPv6 and IPv4 wildcard listener addresses with the
+/// same port by the bare port, so that both are listened on again according
+/// to the `listen` setting.
+fn collapse_wildcards(listen: Vec<String>) -> Vec<String> {
+    listen
+        .iter()
+        .filter_map(|tcp_addr| {
+            let (scheme, host_port) = match tcp_addr.strip_prefix("ws://") {
+                Some(host_port) => ("ws://", host_port),
+                None => ("", tcp_addr.as_str()),
+            };
+            if let Some(port) = host_port.strip_prefix("[::]:") {
+                if listen.contains(&format!("{}0.0.0.0:{}", scheme, port)) {
+                    return Some(format!("{}{}", scheme, port));
+                }
+            } else if let Some(port) = host_port.strip_prefix("0.0.0.0:") {
+                if listen.contains(&format!("{}[::]:{}", scheme, port)) {
+                    return None;
+                }
+            }
+            Some(tcp_addr.to_owned())
+        })
+        .collect()
+}
+
+/// Report the failure to open the outgoing connection with the given
+/// address.
+async fn report_connect_error(ui: &Arc<Mutex<Ui>>, tcp_addr: &str, err: io::Error) {
+    let msg = tr_args("failed to connect to {}: {}", &[&tcp_addr, &err]);
+    let mut ui = ui.lock().await;
+    ui.write_network(&msg);
+    ui.write_alert(Level::Error, &msg);
+    ui.update();
+}
+
+/// Report the failure to open (or renew) the time range request of the
+/// given channel, after which posts are no longer received for it.
+async fn report_channel_error(ui: &Arc<Mutex<Ui>>, address: &Addr, channel: &Channel, err: Error) {
+    warn!("Failed to open channel {}: {}", channel, err);
+    let msg = tr_args(
+        "failed to request posts for channel {}: {}",
+        &[&channel, &err],
+    );
+    let mut ui = ui.lock().await;
+    match ui.get_window(address, channel) {
+        Some(window) => window.write_as(Level::Error, &msg),
+        None => ui.write_status(Level::Error, &msg),
+    }
+    ui.update();
+}
+
+/// Speak cable over the given outgoing connection (with the given address)
+/// until it closes, writing the given message once connected.
+async fn run_connection<S, T>(
+    ui: &Arc<Mutex<Ui>>,
+    cable: CableManager<S>,
+    tcp_addr: &str,
+    msg: String,
+    stream: T,
+) where
+    S: Store,
+    T: AsyncRead + AsyncWrite + Clone + Unpin + Send + Sync + 'static,
+{
+    // This block expression is needed to drop the lock and prevent
+    // blocking of the UI.
+    {
+        let mut ui = ui.lock().await;
+        ui.write_network(&msg);
+        ui.write_status(Level::Network, &msg);
+        ui.update();
+    }
+
+    let stream = Metered::new(stream);
+    match cable.listen(stream.clone()).await {
+        Ok(()) => {
+            let mut ui = ui.lock().await;
+            ui.write_network_alert(&tr_args("disconnected from {}", &[&tcp_addr]));
+            ui.update();
+        }
+        Err(err) => report_connection_error(ui, tcp_addr, err, stream.io_failed()).await,
+    }
+}
+
+/// Write each line of the standard error of the command of the connection
+/// with the given address to the network window.
+fn forward_stderr(ui: Arc<Mutex<Ui>>, tcp_addr: String, stderr: ChildStderr) {
+    task::spawn_blocking(move || {
+        for line in io::BufReader::new(stderr).lines().map_while(Result::ok) {
+            task::block_on(async {
+                let mut ui = ui.lock().await;
+                ui.write_network(&format!("{}: {}", tcp_addr, line));
+                ui.update();
+            });
+        }
+    });
+}
+
+/// Format the address of a peer, which may be unknown.
+fn display_peer(peer: Option<SocketAddr>) -> String {
+    peer.map(|peer| peer.to_string())
+        .unwrap_or_else(|| tr("unknown address").to_string())
+}
+
+/// Report the closing of the incoming connection from the given peer,
+/// accepted at the given time, with the given result (and whether reading
+/// from or writing to the connection failed).
+///
+/// Connections which fail as soon as they are accepted are taken to be from
+/// clients which do not speak cable (such as HTTP scanners), identified from
+/// the first bytes they sent.
+async fn report_incoming_close<T>(
+    ui: &Arc<Mutex<Ui>>,
+    offenders: &Offenders,
+    peer: Option<SocketAddr>,
+    accepted: Instant,
+    stream: &Sniffed<T>,
+    io_failed: bool,
+    result: Result<(), Error>,
+) {
+    match result {
+        Ok(()) => {
+            let mut ui = ui.lock().await;
+            ui.write_network(&tr_args(
+                "connection from {} closed",
+                &[&display_peer(peer)],
+            ));
+            ui.update();
+        }
+        Err(err) if accepted.elapsed() < offenders::INSTANT_FAILURE => {
+            debug!("Cable stream listener error: {}", err);
+            report_offence(ui, offenders, peer, offenders::classify(&stream.prefix())).await;
+        }
+        Err(err) => report_connection_error(ui, &display_peer(peer), err, io_failed).await,
+    }
+}
+
+/// Report an incoming connection from the given peer which was closed
+/// because the given client does not speak cable, ignoring further
+/// connections from its address if it has failed repeatedly.
+async fn report_offence(
+    ui: &Arc<Mutex<Ui>>,
+    offenders: &Offenders,
+    peer: Option<SocketAddr>,
+    client: &'static str,
+) {
+    let ignored = match peer {
+        Some(peer) => offenders.record(peer.ip()).await,
+        None => false,
+    };
+    let mut ui = ui.lock().await;
+    ui.write_network(&tr_args(
+        "closed connection from {}: {} rather than a cable peer",
+        &[&display_peer(peer), &tr(client)],
+    ));
+    if let Some(peer) = peer.filter(|_| ignored) {
+        ui.write_network(&tr_args(
+            "ignoring connections from {} for {} minutes after repeated failures",
+            &[&peer.ip(), &(offenders::OFFENCE_COOLDOWN.as_secs() / 60)],
+        ));
+    }
+    ui.update();
+}
+
+/// Keep a text post which failed to send with the given error, to be resent
+/// by `/retry`, and mark it as failed in the window of its channel.
+async fn keep_failed(
+    failed: &Mutex<FailedPosts>,
+    ui: &Mutex<Ui>,
+    address: &Addr,
+    channel: &Channel,
+    text: &str,
+    err: &Error,
+) {
+    let n = {
+        let mut failed = failed.lock().await;
+        failed.next += 1;
+        failed.next - 1
+    };
+
+    let mut ui = ui.lock().await;
+    let line = ui
+        .get_window(address, channel)
+        .map(|window| window.write_failed(n, text));
+    ui.write_alert(
+        Level::Error,
+        &tr_args(
+            "failed to send post {} to #{}: {} (resend it with /retry {})",
+            &[&n, &channel, &err, &n],
+        ),
+    );
+    ui.update();
+    drop(ui);
+
+    failed.lock().await.posts.insert(
+        n,
+        FailedPost {
+            address: address.clone(),
+            channel: channel.clone(),
+            text: text.to_owned(),
+            line,
+        },
+    );
+}
+
+/// Draw a bar representing the given count, scaled such that the maximum
+/// count is `CHANSTATS_BAR_WIDTH` characters wide.
+fn bar(count: usize, max: usize) -> String {
+    "#".repeat(count * CHANSTATS_BAR_WIDTH / max.max(1))
+}
+
 impl<S> App<S>
 where
     S: Store,
@@ -55,15 +1137,52 @@ where
     ) -> Self {
         Self {
             abort_handles: Arc::new(Mutex::new(HashMap::new())),
-            cables: HashMap::new(),
+            aliases: HashMap::new(),
+            backfill: HashMap::new(),
+            admission: Admission::default(),
+            client: Client::new(storage_fn),
             connections: HashSet::new(),
+            connection_tasks: HashMap::new(),
             close_channel_sender,
-            storage_fn,
-            ui: Arc::new(Mutex::new(Ui::new(size))),
+            clock: Arc::new(SystemClock),
+            keys: Keybindings::default(),
+            nicks: NickCache::default(),
+            index: SearchIndex::default(),
+            pending_join: None,
+            pending_post: None,
+            pending_split: None,
+            pins: HashMap::new(),
+            hidden: HashMap::new(),
+            offenders: Offenders::default(),
+            stun: None,
+            advertised: HashMap::new(),
+            lock: None,
+            redaction: Redaction::default(),
+            flood: Flood::default(),
+            persistent: false,
+            scheduled: Arc::new(Mutex::new(BTreeMap::new())),
+            next_scheduled: 1,
+            failed: Arc::new(Mutex::new(FailedPosts::default())),
+            ui: Arc::new(Mutex::new(
+                Ui::new(size).with_commands(COMMANDS.map(|(name, _arity)| name)),
+            )),
             exit: false,
         }
     }
 
+    /// Read the current time from the given clock rather than the system
+    /// clock, in the application, its client and its UI.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        // The UI is not yet shared with any task.
+        Arc::get_mut(&mut self.ui)
+            .expect("clock set before the UI is shared")
+            .get_mut()
+            .set_clock(clock.clone());
+        self.client = self.client.with_clock(clock.clone());
+        self.clock = clock;
+        self
+    }
+
     /// Listen for "close channel" messages and abort the associated task
     /// responsible for updating the UI with posts from the given channel.
     /// This prevents double-posting to the UI if a channel is left and then
@@ -85,22 +1204,159 @@ where
         });
     }
 
+    /// Save the store of each cabal with `store::save()` periodically and
+    /// on exit, e.g. for stores opened with `store::open()`.
+    pub fn with_persistent_stores(mut self) -> Self {
+        self.persistent = true;
+        self
+    }
+
     /// Add the given cabal address (key) to the cable manager.
+    ///
+    /// If stores are persistent, the store of a newly added cabal is saved
+    /// every `STORE_SAVE_INTERVAL`.
     pub fn add_cable(&mut self, addr: &Addr) {
-        let s_addr = hex::to(addr);
-        self.cables.insert(
-            addr.to_vec(),
-            CableManager::new(*(self.storage_fn)(&s_addr)),
-        );
+        let added = !self.client.has_cabal(addr);
+        self.client.add_cabal(addr);
+
+        if let Some(cable) = self.client.cable(addr).filter(|_| added && self.persistent) {
+            let address = addr.clone();
+            let cable_store = cable.store.clone();
+            task::spawn(async move {
+                loop {
+                    task::sleep(STORE_SAVE_INTERVAL).await;
+                    if let Err(err) = store::save(&address, &cable_store).await {
+                        warn!("Failed to save store of {}: {}", hex::to(&address), err);
+                    }
+                }
+            });
+        }
+    }
+
+    /// Save the store of each cabal, if stores are persistent.
+    async fn save_stores(&self) {
+        if !self.persistent {
+            return;
+        }
+        for address in self.client.cabals() {
+            if let Some(cable) = self.client.cable(address) {
+                if let Err(err) = store::save(address, &cable.store).await {
+                    warn!("Failed to save store of {}: {}", hex::to(address), err);
+                }
+            }
+        }
+    }
+
+    /// Resolve the given alias or hex-encoded string to a cabal address.
+    fn resolve_address(&self, s: &str) -> Option<Addr> {
+        self.aliases
+            .iter()
+            .find(|(_addr, alias)| alias.as_str() == s)
+            .map(|(addr, _alias)| addr.clone())
+            .or_else(|| hex::from(s))
+    }
+
+    /// Return the alias of the given cabal, or its hex-encoded address if it
+    /// has no alias.
+    fn cabal_name(&self, addr: &Addr) -> String {
+        self.aliases
+            .get(addr)
+            .cloned()
+            .unwrap_or_else(|| hex::to(addr))
+    }
+
+    /// Return the public key and nickname of all peers known to the active
+    /// cabal: members of any known channel and authors of displayed posts.
+    async fn known_peers(&self) -> Vec<(PublicKey, Option<String>)> {
+        let (address, cable) = match self.get_active_cable().await {
+            Some(active) => active,
+            None => return Vec::new(),
+        };
+
+        let mut public_keys = HashSet::new();
+        for channel in cable.store.get_channels().await.unwrap_or_default() {
+            if let Some(members) = cable.store.get_channel_members(&channel).await {
+                public_keys.extend(members);
+            }
+        }
+        for window in self.ui.lock().await.windows.iter() {
+            if window.address == address {
+                public_keys.extend(window.latest_posts.keys());
+            }
+        }
+
+        let mut peers = Vec::with_capacity(public_keys.len());
+        for public_key in public_keys {
+            let nick = self.nicks.get(&address, &cable.store, &public_key).await;
+            peers.push((public_key, nick));
+        }
+
+        peers
+    }
+
+    /// Resolve a peer argument to a public key.
+    ///
+    /// The argument may be a full hex-encoded public key, a nickname, a
+    /// unique nickname prefix or a unique public key prefix of a peer known
+    /// to the active cabal. Returns a description of the problem if the
+    /// argument matches no peer or is ambiguous.
+    async fn resolve_peer(&self, arg: &str) -> Result<PublicKey, String> {
+        if arg.len() == 64 {
+            if let Some(public_key) = hex::from(arg).and_then(|key| PublicKey::try_from(key).ok()) {
+                return Ok(public_key);
+            }
+        }
+
+        let peers = self.known_peers().await;
+        let arg_lower = arg.to_lowercase();
+
+        // Exact nickname matches take precedence over prefix matches.
+        let mut matches = peers
+            .iter()
+            .filter(|(_key, nick)| {
+                nick.as_ref().map(|n| n.to_lowercase()) == Some(arg_lower.clone())
+            })
+            .collect::<Vec<_>>();
+        if matches.is_empty() {
+            matches = peers
+                .iter()
+                .filter(|(key, nick)| {
+                    hex::to(key).starts_with(&arg_lower)
+                        || nick
+                            .as_ref()
+                            .map(|n| n.to_lowercase().starts_with(&arg_lower))
+                            .unwrap_or(false)
+                })
+                .collect();
+        }
+
+        match matches.as_slice() {
+            [] => Err(tr_args("no known peer matches {}", &[&arg])),
+            [(public_key, _nick)] => Ok(*public_key),
+            _ => {
+                let peers = matches
+                    .iter()
+                    .map(|(key, nick)| match nick {
+                        Some(nick) => format!("{} ({})", nick, hex::to(&key[..4])),
+                        None => hex::to(&key[..4]),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                Err(tr_args(
+                    "{} is ambiguous; matching peers: {}",
+                    &[&arg, &peers],
+                ))
+            }
+        }
     }
 
     /// Return the address and manager for the active cable.
-    pub async fn get_active_cable(&mut self) -> Option<(Addr, CableManager<S>)> {
+    pub async fn get_active_cable(&self) -> Option<(Addr, CableManager<S>)> {
         self.ui
             .lock()
             .await
             .get_active_address()
-            .and_then(|addr| self.cables.get(addr).map(|c| (addr.clone(), c.clone())))
+            .and_then(|addr| self.client.cable(addr).map(|c| (addr.clone(), c.clone())))
     }
 
     /// Set the address (key) of the active cabal.
@@ -122,610 +1378,2816 @@ where
             (Some("add"), Some(hex_addr)) => {
                 if let Some(addr) = hex::from(hex_addr) {
                     self.add_cable(&addr);
-                    self.write_status(&format!("added cabal: {}", hex_addr))
+                    self.write_status(Level::Info, &tr_args("added cabal: {}", &[&hex_addr]))
                         .await;
-                    self.set_active_address(&addr).await;
-                    self.write_status(&format!("set active cabal to {}", hex_addr))
+                    if let Some(alias) = args.get(3) {
+                        self.aliases.insert(addr.clone(), alias.to_owned());
+                        let mut ui = self.ui.lock().await;
+                        ui.aliases.insert(addr.clone(), alias.to_owned());
+                        drop(ui);
+                        self.write_status(
+                            Level::Info,
+                            &tr_args("set alias for {} to {}", &[&hex_addr, &alias]),
+                        )
                         .await;
+                    }
+                    self.set_active_address(&addr).await;
+                    self.write_status(
+                        Level::Info,
+                        &tr_args("set active cabal to {}", &[&hex_addr]),
+                    )
+                    .await;
                 } else {
-                    self.write_status(&format!("invalid cabal address: {}", hex_addr))
-                        .await;
+                    self.write_status(
+                        Level::Error,
+                        &tr_args("invalid cabal address: {}", &[&hex_addr]),
+                    )
+                    .await;
                 }
             }
             (Some("add"), None) => {
-                self.write_status("usage: /cabal add ADDR").await;
+                self.write_status(Level::Error, &usage("/cabal add ADDR [ALIAS]"))
+                    .await;
             }
             (Some("set"), Some(s_addr)) => {
-                if let Some(addr) = hex::from(s_addr) {
+                if let Some(addr) = self.resolve_address(s_addr) {
                     self.set_active_address(&addr).await;
-                    self.write_status(&format!("set active cabal to {}", s_addr))
+                    self.write_status(Level::Info, &tr_args("set active cabal to {}", &[&s_addr]))
                         .await;
                 } else {
-                    self.write_status(&format!("invalid cabal address: {}", s_addr))
-                        .await;
+                    self.write_status(
+                        Level::Error,
+                        &tr_args("invalid cabal address: {}", &[&s_addr]),
+                    )
+                    .await;
                 }
             }
             (Some("set"), None) => {
-                self.write_status("usage: /cabal set ADDR").await;
+                self.write_status(Level::Error, &usage("/cabal set ADDR|ALIAS"))
+                    .await;
             }
+            (Some("backfill"), value) => self.backfill_handler(value).await,
             (Some("list"), _) => {
-                for addr in self.cables.keys() {
+                for addr in self.client.cabals() {
                     let is_active = self
                         .get_active_address()
                         .await
                         .map(|x| &x == addr)
                         .unwrap_or(false);
                     let star = if is_active { "*" } else { "" };
-                    self.write_status(&format!("{}{}", hex::to(addr), star))
+                    let alias = self
+                        .aliases
+                        .get(addr)
+                        .map(|alias| format!(" ({})", alias))
+                        .unwrap_or_default();
+                    self.write_status(Level::Info, &format!("{}{}{}", hex::to(addr), alias, star))
                         .await;
                 }
-                if self.cables.is_empty() {
-                    self.write_status("{ no cabals in list }").await;
+                if self.client.cabals().next().is_none() {
+                    self.write_status(Level::Info, tr("{ no cabals in list }"))
+                        .await;
                 }
             }
             _ => {}
         }
     }
 
-    /// Handle the `/channels` command.
+    /// Handle the `/cabal backfill` command.
     ///
-    /// Prints a list of known channels for the active cable instance.
-    async fn channels_handler(&mut self) {
-        if let Some((_address, cable)) = self.get_active_cable().await {
-            let mut ui = self.ui.lock().await;
-            if let Some(channels) = cable.store.get_channels().await {
-                for channel in channels {
-                    ui.write_status(&format!("- {}", channel));
+    /// Sets how far back the channels of the active cabal are requested when
+    /// joined (from the next join or renewal of the request), or reports the
+    /// current horizon if none is given.
+    async fn backfill_handler(&mut self, value: Option<&String>) {
+        let Some(address) = self.get_active_address().await else {
+            self.write_status(
+                Level::Error,
+                tr("no active cabal. add one with \"/cabal add\" first"),
+            )
+            .await;
+            return;
+        };
+
+        match value.map(|value| time::Backfill::parse(value)) {
+            None => {
+                let backfill = self.backfill.get(&address).copied().unwrap_or_default();
+                self.write_status(Level::Info, &tr_args("backfill: {}", &[&backfill]))
+                    .await;
+            }
+            Some(Some(backfill)) => {
+                if backfill == time::Backfill::default() {
+                    self.backfill.remove(&address);
+                } else {
+                    self.backfill.insert(address, backfill);
                 }
-            } else {
-                ui.write_status("{ no known channels for the active cabal }");
+                self.write_status(Level::Info, &tr_args("set backfill to {}", &[&backfill]))
+                    .await;
+            }
+            Some(None) => {
+                self.write_status(Level::Error, &usage("/cabal backfill [all|DURATION]"))
+                    .await;
             }
-            ui.update();
-        } else {
-            let mut ui = self.ui.lock().await;
-            ui.write_status(&format!(
-                "{}{}",
-                "cannot list channels with no active cabal set.",
-                " add a cabal with \"/cabal add\" first",
-            ));
-            ui.update();
         }
     }
 
-    /// Handle the `/connect` command.
+    /// Handle the `/awaylog` command.
     ///
-    /// Attempts a TCP connection to the given host:port.
-    async fn connect_handler(&mut self, args: Vec<String>) {
-        if self.get_active_address().await.is_none() {
-            self.write_status(r#"no active cabal to bind this connection. use "/cabal add" first"#)
-                .await;
-        } else if let Some(tcp_addr) = args.get(1).cloned() {
+    /// Lists the mentions received while running as a daemon, or removes
+    /// them with `/awaylog clear`.
+    async fn awaylog_handler(&mut self, args: Vec<String>) {
+        let mut ui = self.ui.lock().await;
+        match args.get(1).map(String::as_str) {
+            None => match awaylog::load() {
+                Ok(entries) if entries.is_empty() => {
+                    ui.write_status(Level::Info, tr("{ no mentions while you were away }"))
+                }
+                Ok(entries) => {
+                    for entry in entries {
+                        ui.write_status(
+                            Level::Info,
+                            &format!(
+                                "  [{}] #{} <{}> {}",
+                                time::format_date(entry.timestamp),
+                                entry.channel,
+                                entry.name,
+                                entry.text
+                            ),
+                        );
+                    }
+                    ui.write_status(Level::Info, tr("clear the away log with /awaylog clear"));
+                }
+                Err(err) => ui.write_status(
+                    Level::Error,
+                    &tr_args("failed to load away log: {}", &[&err]),
+                ),
+            },
+            Some("clear") => match awaylog::clear() {
+                Ok(()) => ui.write_status(Level::Info, tr("cleared away log")),
+                Err(err) => ui.write_status(
+                    Level::Error,
+                    &tr_args("failed to clear away log: {}", &[&err]),
+                ),
+            },
+            Some(_) => ui.write_status(Level::Error, &usage("/awaylog [clear]")),
+        }
+        ui.update();
+    }
+
+    /// Handle the `/block` and `/unblock` commands.
+    ///
+    /// Adds the given peer to (or removes the given peer from) the local
+    /// block list. Posts authored by blocked peers are hidden in all windows.
+    async fn block_handler(&mut self, args: Vec<String>) {
+        let block = args[0] == "/block";
+
+        if let Some(peer) = args.get(1) {
+            let resolved = self.resolve_peer(peer).await;
+            let mut ui = self.ui.lock().await;
+            match resolved {
+                Ok(public_key) if block => {
+                    ui.blocked.insert(public_key);
+                    ui.write_status(
+                        Level::Info,
+                        &tr_args("blocked {}", &[&hex::to(&public_key)]),
+                    );
+                }
+                Ok(public_key) => {
+                    if ui.blocked.remove(&public_key) {
+                        ui.write_status(
+                            Level::Info,
+                            &tr_args("unblocked {}", &[&hex::to(&public_key)]),
+                        );
+                    } else {
+                        ui.write_status(Level::Error, &tr_args("{} is not blocked", &[&peer]));
+                    }
+                }
+                Err(err) => ui.write_status(Level::Error, &err),
+            }
+            ui.update();
+        } else if block {
+            self.write_status(Level::Error, &usage("/block PEER")).await;
+        } else {
+            self.write_status(Level::Error, &usage("/unblock PEER"))
+                .await;
+        }
+    }
+
+    /// Handle the `/note` and `/unnote` commands.
+    ///
+    /// `/note PEER TEXT` sets a private local note about a peer, `/note PEER`
+    /// shows it and `/unnote PEER` removes it. Notes are persisted locally
+    /// and never published.
+    async fn note_handler(&mut self, args: Vec<String>) {
+        let note = args[0] == "/note";
+
+        let Some(peer) = args.get(1) else {
+            if note {
+                self.write_status(Level::Error, &usage("/note PEER [TEXT]"))
+                    .await;
+            } else {
+                self.write_status(Level::Error, &usage("/unnote PEER"))
+                    .await;
+            }
+            return;
+        };
+        let public_key = match self.resolve_peer(peer).await {
+            Ok(public_key) => public_key,
+            Err(err) => {
+                self.write_status(Level::Error, &err).await;
+                return;
+            }
+        };
+
+        let mut ui = self.ui.lock().await;
+        let text = args[2..].join(" ");
+        if note && text.is_empty() {
+            match ui.notes.get(&public_key) {
+                Some(text) => {
+                    let msg = tr_args("note about {}: {}", &[&peer, &text]);
+                    ui.write_status(Level::Info, &msg);
+                }
+                None => ui.write_status(Level::Info, &tr_args("no note about {}", &[&peer])),
+            }
+            ui.update();
+            return;
+        }
+
+        if note {
+            ui.set_note(public_key, Some(text));
+            ui.write_status(Level::Info, &tr_args("set note about {}", &[&peer]));
+        } else if ui.notes.contains_key(&public_key) {
+            ui.set_note(public_key, None);
+            ui.write_status(Level::Info, &tr_args("removed note about {}", &[&peer]));
+        } else {
+            ui.write_status(Level::Info, &tr_args("no note about {}", &[&peer]));
+        }
+        if let Err(err) = notes::save(ui.notes.iter()) {
+            ui.write_status(Level::Error, &tr_args("failed to save notes: {}", &[&err]));
+        }
+        ui.update();
+    }
+
+    /// Handle the `/ignore` and `/unignore` commands.
+    ///
+    /// `/ignore PEER TYPE...` ignores the given types of post (`topics`,
+    /// `joins` or `nicks`) from a peer without blocking their text posts;
+    /// `/unignore PEER [TYPE...]` stops ignoring the given (or all) types.
+    /// `/ignore` with no arguments lists all ignored peers.
+    async fn ignore_handler(&mut self, args: Vec<String>) {
+        let ignore = args[0] == "/ignore";
+
+        let Some(peer) = args.get(1) else {
+            let mut ui = self.ui.lock().await;
+            if !ignore {
+                ui.write_status(Level::Error, &usage("/unignore PEER [TYPE...]"));
+            } else if ui.ignored.is_empty() {
+                ui.write_status(Level::Info, tr("{ no ignored peers }"));
+            } else {
+                let mut lines = ui
+                    .ignored
+                    .iter()
+                    .map(|(public_key, types)| {
+                        let types: Vec<&str> = types.iter().map(|kind| kind.as_str()).collect();
+                        format!("- {}: {}", hex::to(public_key), types.join(", "))
+                    })
+                    .collect::<Vec<String>>();
+                lines.sort();
+                for line in lines {
+                    ui.write_status(Level::Info, &line);
+                }
+            }
+            ui.update();
+            return;
+        };
+
+        let types: Result<Vec<Ignore>, String> = args[2..]
+            .iter()
+            .map(|arg| {
+                Ignore::parse(arg).ok_or_else(|| {
+                    tr_args(
+                        "unknown post type {}; expected topics, joins or nicks",
+                        &[&arg],
+                    )
+                })
+            })
+            .collect();
+        let types = match types {
+            Ok(types) if ignore && types.is_empty() => {
+                self.write_status(Level::Error, &usage("/ignore PEER topics|joins|nicks"))
+                    .await;
+                return;
+            }
+            Ok(types) => types,
+            Err(err) => {
+                self.write_status(Level::Error, &err).await;
+                return;
+            }
+        };
+
+        let resolved = self.resolve_peer(peer).await;
+        let mut ui = self.ui.lock().await;
+        match resolved {
+            Ok(public_key) => {
+                let ignored = ui.ignored.entry(public_key).or_default();
+                if ignore {
+                    ignored.extend(types);
+                } else if types.is_empty() {
+                    ignored.clear();
+                } else {
+                    ignored.retain(|kind| !types.contains(kind));
+                }
+                let remaining: Vec<&str> = ignored.iter().map(|kind| kind.as_str()).collect();
+                let msg = if remaining.is_empty() {
+                    tr_args(
+                        "no longer ignoring any posts from {}",
+                        &[&hex::to(&public_key)],
+                    )
+                } else {
+                    tr_args(
+                        "ignoring {} from {}",
+                        &[&remaining.join(", "), &hex::to(&public_key)],
+                    )
+                };
+                if remaining.is_empty() {
+                    ui.ignored.remove(&public_key);
+                }
+                ui.write_status(Level::Info, &msg);
+            }
+            Err(err) => ui.write_status(Level::Error, &err),
+        }
+        ui.update();
+    }
+
+    /// Handle the `/channels` command.
+    ///
+    /// Prints a list of known channels for the active cable instance,
+    /// omitting hidden channels unless `--all` is given. `/channels hide
+    /// CHANNEL` and `/channels unhide CHANNEL` change which channels are
+    /// hidden.
+    async fn channels_handler(&mut self, args: Vec<String>) {
+        if let Some((address, cable)) = self.get_active_cable().await {
+            match (args.get(1).map(String::as_str), args.get(2)) {
+                (Some(action @ ("hide" | "unhide")), Some(channel)) => {
+                    let channel = channel.strip_prefix('#').unwrap_or(channel).to_owned();
+                    let hidden = self.hidden.entry(address).or_default();
+                    let msg = if action == "hide" {
+                        hidden.insert(channel.clone());
+                        tr_args("hid #{}; /channels --all to list it", &[&channel])
+                    } else if hidden.remove(&channel) {
+                        tr_args("unhid #{}", &[&channel])
+                    } else {
+                        tr_args("#{} is not hidden", &[&channel])
+                    };
+                    self.write_status(Level::Info, &msg).await;
+                    return;
+                }
+                (None, _) | (Some("--all"), None) => {}
+                _ => {
+                    self.write_status(
+                        Level::Error,
+                        &usage("/channels [--all|hide|unhide CHANNEL]"),
+                    )
+                    .await;
+                    return;
+                }
+            }
+
+            let all = args.get(1).is_some();
+            let hidden = self.hidden.get(&address).cloned().unwrap_or_default();
+            let mut ui = self.ui.lock().await;
+            if let Some(channels) = cable.store.get_channels().await {
+                let mut omitted = 0;
+                for channel in channels {
+                    if !hidden.contains(&channel) {
+                        ui.write_status(Level::Info, &format!("- {}", channel));
+                    } else if all {
+                        ui.write_status(Level::Info, &tr_args("- {} (hidden)", &[&channel]));
+                    } else {
+                        omitted += 1;
+                    }
+                }
+                if omitted > 0 {
+                    ui.write_status(
+                        Level::Info,
+                        &tr_args(
+                            "{} hidden channels; /channels --all to list them",
+                            &[&omitted],
+                        ),
+                    );
+                }
+            } else {
+                ui.write_status(
+                    Level::Info,
+                    tr("{ no known channels for the active cabal }"),
+                );
+            }
+            ui.update();
+        } else {
+            let mut ui = self.ui.lock().await;
+            ui.write_status(
+                Level::Error,
+                tr("cannot list channels with no active cabal set. add a cabal with \"/cabal add\" first"),
+            );
+            ui.update();
+        }
+    }
+
+    /// Handle the `/chanstats` command.
+    ///
+    /// Aggregates the stored text posts of the given channel (or the active
+    /// channel), optionally limited to the last N days, into the total post
+    /// count, the post count of the most active authors and the number of
+    /// posts per hour of the day.
+    async fn chanstats_handler(&mut self, args: Vec<String>) {
+        let mut channel = None;
+        let mut days = None;
+        let mut args = args.iter().skip(1);
+        while let Some(arg) = args.next() {
+            if arg == "--days" {
+                match args.next().and_then(|n| n.parse::<u64>().ok()) {
+                    Some(n) if n > 0 => days = Some(n),
+                    _ => {
+                        self.write_status(Level::Error, &usage("/chanstats [CHANNEL] [--days N]"))
+                            .await;
+                        return;
+                    }
+                }
+            } else {
+                channel = Some(arg.strip_prefix('#').unwrap_or(arg).to_owned());
+            }
+        }
+
+        let (address, channel) = {
+            let mut ui = self.ui.lock().await;
+            let window = ui.get_active_window();
+            match channel {
+                Some(channel) => (ui.active_address.clone(), channel),
+                None if !window.is_virtual() => {
+                    (Some(window.address.clone()), window.channel.clone())
+                }
+                None => {
+                    ui.write_status(Level::Error, &usage("/chanstats [CHANNEL] [--days N]"));
+                    ui.update();
+                    return;
+                }
+            }
+        };
+        let Some(cable) = address
+            .as_ref()
+            .and_then(|address| self.client.cable(address))
+        else {
+            self.write_status(
+                Level::Error,
+                tr("no active cabal. add one with \"/cabal add\" first"),
+            )
+            .await;
+            return;
+        };
+        let address = address.unwrap();
+
+        let time_start = match days {
+            Some(days) => self
+                .clock
+                .now()
+                .unwrap_or(0)
+                .saturating_sub(days * 86_400_000),
+            None => 0,
+        };
+        let opts = ChannelOptions {
+            channel: channel.clone(),
+            time_start,
+            time_end: 0,
+            limit: 0,
+        };
+
+        let mut total = 0;
+        let mut authors: HashMap<PublicKey, usize> = HashMap::new();
+        let mut hours = [0; 24];
+        {
+            let mut store = cable.store.clone();
+            let mut posts = store.get_posts(&opts).await;
+            while let Some(Ok(post)) = posts.next().await {
+                if let PostBody::Text { .. } = post.body {
+                    total += 1;
+                    *authors.entry(post.header.public_key).or_default() += 1;
+                    if let Some(hour) = time::local_hour(post.header.timestamp) {
+                        hours[hour as usize] += 1;
+                    }
+                }
+            }
+        }
+
+        let period = match days {
+            Some(1) => tr(" in the last day").to_string(),
+            Some(days) => tr_args(" in the last {} days", &[&days]),
+            None => String::new(),
+        };
+        let mut lines = vec![tr_args("#{}: {} posts{}", &[&channel, &total, &period])];
+        if total > 0 {
+            let mut authors: Vec<(PublicKey, usize)> = authors.into_iter().collect();
+            authors.sort_by_key(|(_public_key, count)| Reverse(*count));
+            let author_count = authors.len();
+
+            let mut names = Vec::new();
+            for (public_key, count) in authors.into_iter().take(CHANSTATS_AUTHORS) {
+                let name = self
+                    .nicks
+                    .get(&address, &cable.store, &public_key)
+                    .await
+                    .unwrap_or_else(|| hex::to(&public_key[..4]));
+                names.push((name, count));
+            }
+            let width = names
+                .iter()
+                .map(|(name, _count)| name.chars().count())
+                .max()
+                .unwrap_or(0);
+            let max = names.first().map_or(1, |(_name, count)| *count);
+
+            lines.push(tr_args("top authors (of {}):", &[&author_count]));
+            for (name, count) in names {
+                lines.push(format!(
+                    "  {:<width$} {:>6} {}",
+                    name,
+                    count,
+                    bar(count, max),
+                    width = width
+                ));
+            }
+
+            lines.push(tr("posts by hour:").to_string());
+            let max = hours.iter().copied().max().unwrap_or(1);
+            for (hour, count) in hours.iter().enumerate() {
+                lines.push(format!(
+                    "  {:02}:00 {:>6} {}",
+                    hour,
+                    count,
+                    bar(*count, max)
+                ));
+            }
+        }
+
+        let mut ui = self.ui.lock().await;
+        for line in lines {
+            ui.write_status(Level::Info, &line);
+        }
+        ui.update();
+    }
+
+    /// Handle the `/connect` command.
+    ///
+    /// Attempts a TCP connection to the given host:port, trying each of the
+    /// addresses to which the host resolves, or runs the given `exec:`
+    /// command and connects through its standard input and output.
+    ///
+    /// `/connect loopback` creates a separate in-process peer of the active
+    /// cabal and connects to it in memory.
+    async fn connect_handler(&mut self, args: Vec<String>) {
+        if self.get_active_address().await.is_none() {
+            self.write_status(
+                Level::Error,
+                tr(r#"no active cabal to bind this connection. use "/cabal add" first"#),
+            )
+            .await;
+        } else if args.len() > 1 {
+            // Commands may contain spaces; host:port addresses do not.
+            let mut tcp_addr = args[1..].join(" ");
+            if let Some(command) = exec::command(&tcp_addr) {
+                tcp_addr = format!("{}{}", exec::PREFIX, command);
+            }
+
+            if let Some(limit) = self.admission.total_reached() {
+                self.write_status(
+                    Level::Error,
+                    &tr_args("cannot connect to {}: {}", &[&tcp_addr, &limit]),
+                )
+                .await;
+                return;
+            }
+
             // Retrieve the active cable manager.
-            let (_, cable) = self.get_active_cable().await.unwrap();
+            let (address, cable) = self.get_active_cable().await.unwrap();
+
+            let ui = self.ui.clone();
+
+            // Register the connection, replacing any previous connection
+            // to the same address.
+            let connection = Connection::Connected(address.clone(), tcp_addr.clone());
+            let tasks = ConnectionTasks::new();
+            self.register_connection(connection, tasks.clone());
+
+            if tcp_addr == transport::LOOPBACK {
+                // The loopback peer keeps its posts in memory, separately
+                // from the store of the cabal.
+                let peer = CableManager::new(MemoryStore::default());
+                let peer_tasks = tasks.clone();
+                let guard = tasks.open_stream(tcp_addr.clone());
+                tasks.spawn(async move {
+                    let _guard = guard;
+                    let (local, remote) = transport::memory_pair();
+                    peer_tasks.spawn(async move {
+                        if let Err(err) = peer.listen(remote).await {
+                            debug!("Loopback peer error: {}", err);
+                        }
+                    });
+                    let msg = tr("connected to an in-process loopback peer").to_string();
+                    run_connection(&ui, cable, &tcp_addr, msg, local).await;
+                });
+                return;
+            }
+
+            // Attempt a connection to the peer and invoke the cable
+            // listener.
+            let stream_tasks = tasks.clone();
+            tasks.spawn(async move {
+                if let Some(command) = exec::command(&tcp_addr) {
+                    // The command is killed once the connection closes or
+                    // is cancelled.
+                    let (mut process, stream) = match exec::spawn(command) {
+                        Ok(spawned) => spawned,
+                        Err(err) => {
+                            report_connect_error(&ui, &tcp_addr, err).await;
+                            return;
+                        }
+                    };
+                    if let Some(stderr) = process.take_stderr() {
+                        forward_stderr(ui.clone(), tcp_addr.clone(), stderr);
+                    }
+
+                    let _guard = stream_tasks.open_stream(tcp_addr.clone());
+                    let msg = tr_args("connected to {}", &[&tcp_addr]);
+                    run_connection(&ui, cable, &tcp_addr, msg, stream).await;
+                    return;
+                }
+
+                let (stream, peer) = match dial::connect(&tcp_addr).await {
+                    Ok(connected) => connected,
+                    Err(err) => {
+                        report_connect_error(&ui, &tcp_addr, err).await;
+                        return;
+                    }
+                };
+
+                let _guard = stream_tasks.open_stream(peer.to_string());
+                // Name the address connected to if a host name was given.
+                let msg = if peer.to_string() == tcp_addr {
+                    tr_args("connected to {}", &[&tcp_addr])
+                } else {
+                    tr_args("connected to {} via {}", &[&tcp_addr, &peer])
+                };
+                run_connection(&ui, cable, &tcp_addr, msg, stream).await;
+            });
+        } else {
+            // Print usage example for the connect command.
+            let mut ui = self.ui.lock().await;
+            ui.write_status(
+                Level::Error,
+                &usage("/connect HOST:PORT|exec:COMMAND|loopback"),
+            );
+            ui.update();
+        }
+    }
+
+    /// Register the given connection or listener along with its tasks,
+    /// cancelling the tasks of any connection it replaces.
+    fn register_connection(&mut self, connection: Connection, tasks: ConnectionTasks) {
+        self.connections.insert(connection.clone());
+        if let Some(replaced) = self.connection_tasks.insert(connection, tasks) {
+            replaced.cancel();
+        }
+    }
+
+    /// Cancel the tasks of all connections and listeners.
+    fn cancel_connections(&mut self) {
+        for tasks in self.connection_tasks.values() {
+            tasks.cancel();
+        }
+    }
+
+    /// Handle the `/disconnect` command.
+    ///
+    /// Closes the connection to, or stops the listener on, the given
+    /// address, as listed by `/connections`.
+    async fn disconnect_handler(&mut self, args: Vec<String>) {
+        if args.len() < 2 {
+            self.write_status(Level::Error, &usage("/disconnect HOST:PORT|ADDR"))
+                .await;
+            return;
+        }
+        let mut addr = args[1..].join(" ");
+        if let Some(command) = exec::command(&addr) {
+            addr = format!("{}{}", exec::PREFIX, command);
+        }
+
+        let closed: Vec<Connection> = self
+            .connections
+            .iter()
+            .filter(|connection| match connection {
+                Connection::Connected(_cabal, tcp_addr) => tcp_addr == &addr,
+                Connection::Listening(_cabal, tcp_addr) => tcp_addr == &addr,
+            })
+            .cloned()
+            .collect();
+        if closed.is_empty() {
+            self.write_status(
+                Level::Error,
+                &tr_args("no connection or listener for {}", &[&addr]),
+            )
+            .await;
+            return;
+        }
+
+        let mut ui = self.ui.lock().await;
+        for connection in closed {
+            self.connections.remove(&connection);
+            if let Some(tasks) = self.connection_tasks.remove(&connection) {
+                tasks.cancel();
+            }
+            let msg = match &connection {
+                Connection::Connected(_cabal, tcp_addr) => {
+                    tr_args("disconnected from {}", &[&tcp_addr])
+                }
+                Connection::Listening(_cabal, tcp_addr) => {
+                    tr_args("stopped listening on {}", &[&tcp_addr])
+                }
+            };
+            ui.write_network(&msg);
+            ui.write_status(Level::Network, &msg);
+        }
+        ui.update();
+    }
+
+    /// Handle the `/connections` command.
+    ///
+    /// Prints a list of active TCP connections.
+    async fn connections_handler(&mut self) {
+        let mut ui = self.ui.lock().await;
+        if self.connections.is_empty() {
+            ui.write_status(Level::Info, tr("{ no connections in list }"));
+            ui.update();
+            return;
+        }
+
+        let mut cabals: BTreeMap<String, Vec<&Connection>> = BTreeMap::new();
+        for connection in self.connections.iter() {
+            let (Connection::Connected(cabal, _) | Connection::Listening(cabal, _)) = connection;
+            let short = hex::to(cabal.get(..4).unwrap_or(cabal));
+            let name = match self.aliases.get(cabal) {
+                Some(alias) => format!("{} ({})", alias, short),
+                None => short,
+            };
+            cabals.entry(name).or_default().push(connection);
+        }
+
+        // The incoming connections of listeners which share their tasks
+        // (dual-stack listeners) are only listed once.
+        let mut listed: Vec<&ConnectionTasks> = Vec::new();
+        for (name, mut connections) in cabals {
+            connections.sort();
+            ui.write_status(Level::Info, &tr_args("cabal {}:", &[&name]));
+            for connection in connections {
+                let tasks = self
+                    .connection_tasks
+                    .get(connection)
+                    .filter(|tasks| !listed.iter().any(|other| tasks.is_shared_with(other)));
+                let streams = tasks.map(|tasks| tasks.open_streams()).unwrap_or_default();
+                listed.extend(tasks);
+
+                match connection {
+                    Connection::Connected(_, addr) => {
+                        let msg = match streams.first() {
+                            Some((peer, open)) if peer != addr => tr_args(
+                                "  outgoing to {} via {}, connected for {}",
+                                &[&addr, &peer, &time::format_span(open.as_secs())],
+                            ),
+                            Some((_peer, open)) => tr_args(
+                                "  outgoing to {}, connected for {}",
+                                &[&addr, &time::format_span(open.as_secs())],
+                            ),
+                            None => tr_args("  outgoing to {} (not connected)", &[&addr]),
+                        };
+                        ui.write_status(Level::Info, &msg);
+                    }
+                    Connection::Listening(_, addr) => {
+                        ui.write_status(
+                            Level::Info,
+                            &tr_args("  listening on {} ({} incoming)", &[&addr, &streams.len()]),
+                        );
+                        for (peer, open) in streams {
+                            ui.write_status(
+                                Level::Info,
+                                &tr_args(
+                                    "    incoming from {}, connected for {}",
+                                    &[&peer, &time::format_span(open.as_secs())],
+                                ),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        ui.update();
+    }
+
+    /// Handle the `/delete` command.
+    ///
+    /// Deletes the most recently set nickname for the local peer.
+    async fn delete_handler(&mut self, args: Vec<String>) -> Result<(), Error> {
+        if let Some(n) = args.get(1).and_then(|n| n.parse::<usize>().ok()) {
+            return self.delete_post(n).await;
+        }
+
+        if let Some((address, mut cable)) = self.get_active_cable().await {
+            if let Some("nick") = args.get(1).map(|arg| arg.as_str()) {
+                if let Some((public_key, _private_key)) = cable.store.get_keypair().await {
+                    if let Some((_name, hash)) =
+                        cable.store.get_peer_name_and_hash(&public_key).await
+                    {
+                        cable.post_delete(vec![hash]).await?;
+                        self.nicks.invalidate(&address, &public_key).await;
+                        let mut ui = self.ui.lock().await;
+                        ui.write_status(Level::Info, tr("deleted most recent nickname"));
+                        ui.update();
+                    } else {
+                        let mut ui = self.ui.lock().await;
+                        ui.write_status(Level::Error, tr("no nickname found for the local peer"));
+                        ui.update();
+                    }
+                }
+            } else {
+                self.write_status(Level::Error, &usage("/delete nick"))
+                    .await;
+            }
+        } else {
+            let mut ui = self.ui.lock().await;
+            ui.write_status(
+                Level::Error,
+                tr("cannot delete nickname with no active cabal set. add a cabal with \"/cabal add\" first"),
+            );
+            ui.update();
+        }
+        Ok(())
+    }
+
+    /// Delete the Nth most recent post in the active window, provided that
+    /// it was authored by the local peer.
+    async fn delete_post(&mut self, n: usize) -> Result<(), Error> {
+        let (address, line) = {
+            let mut ui = self.ui.lock().await;
+            let window = ui.get_active_window();
+            (window.address.clone(), window.nth_recent_post(n).cloned())
+        };
+        let Some(mut cable) = self.client.cable(&address).cloned() else {
+            self.write_status(
+                Level::Error,
+                tr("posts can only be deleted from a channel window"),
+            )
+            .await;
+            return Ok(());
+        };
+        let local_public_key = cable
+            .store
+            .get_keypair()
+            .await
+            .map(|(public_key, _private_key)| public_key);
+
+        match line {
+            Some(Line {
+                hash: Some(hash),
+                author: Some(author),
+                ..
+            }) if Some(author) == local_public_key => {
+                cable.post_delete(vec![hash]).await?;
+                self.index.remove(&address, &hash).await;
+                let mut ui = self.ui.lock().await;
+                ui.get_active_window().remove_post(&hash);
+                ui.write_status(Level::Info, tr("deleted post"));
+                ui.update();
+            }
+            Some(_) => {
+                self.write_status(Level::Error, tr("only your own posts can be deleted"))
+                    .await
+            }
+            None => {
+                self.write_status(
+                    Level::Error,
+                    &tr_args("no post found at position {}", &[&n]),
+                )
+                .await
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle the `/doctor` command.
+    ///
+    /// Checks the terminal, local storage, system clock, connections and
+    /// listeners, printing the result of each check along with a suggestion
+    /// for any problem found. If a peer address is given, connecting to the
+    /// peer is checked as well.
+    async fn doctor_handler(&mut self, args: Vec<String>) {
+        // Each result is `Ok` with a description, or `Err` with a
+        // description of the problem and a suggestion.
+        let mut results: Vec<Result<String, String>> = Vec::new();
+
+        // Terminal.
+        {
+            let ui = self.ui.lock().await;
+            if ui.headless {
+                results.push(Ok(tr("running as a daemon without a terminal").to_string()));
+            } else if !io::stdout().is_terminal() {
+                results.push(Err(tr(
+                    "output is not a terminal; run cabin in a terminal or with --daemon",
+                )
+                .to_string()));
+            }
+            match env::var("TERM") {
+                Ok(term) if !term.is_empty() && term != "dumb" => {
+                    results.push(Ok(tr_args("terminal type is {}", &[&term])))
+                }
+                _ => results.push(Err(
+                    tr("TERM is unset or dumb, so the screen may not update correctly; try --accessible")
+                        .to_string(),
+                )),
+            }
+            let (width, height) = ui.size;
+            if width < 40 || height < 10 {
+                results.push(Err(tr_args(
+                    "the terminal is only {}x{}; enlarge it to display more of each window",
+                    &[&width, &height],
+                )));
+            }
+        }
+
+        // Local storage.
+        for (dir, purpose) in [
+            (paths::data_dir(), tr("input history")),
+            (paths::state_dir(), tr("daemon session")),
+        ] {
+            let probe = dir.join(".doctor");
+            let result = fs::create_dir_all(&dir)
+                .and_then(|()| fs::write(&probe, b"cabin"))
+                .and_then(|()| fs::remove_file(&probe));
+            results.push(match result {
+                Ok(()) => Ok(tr_args("{} is writable ({})", &[&dir.display(), &purpose])),
+                Err(err) => Err(tr_args(
+                    "{} is not writable ({}): {}; check its permissions",
+                    &[&dir.display(), &purpose, &err],
+                )),
+            });
+        }
+        match self.get_active_cable().await {
+            Some((_address, cable)) => match cable.store.get_keypair().await {
+                Some(_keypair) => results
+                    .push(Ok(
+                        tr("the store of the active cabal holds a keypair").to_string()
+                    )),
+                None => results.push(Err(tr(
+                    "the store of the active cabal has no keypair, so posts cannot be signed",
+                )
+                .to_string())),
+            },
+            None => results.push(Err(
+                tr("no active cabal; add one with /cabal add ADDR").to_string()
+            )),
+        }
+
+        // System clock.
+        match self.clock.now() {
+            Ok(now) if now < MIN_PLAUSIBLE_TIME => results.push(Err(tr_args("the system clock reads {}, which is likely wrong; posts will be misdated and may not be requested by peers", &[&time::format_iso(now)]))),
+            Ok(now) => {
+                // Posts from peers far in the future suggest that the local
+                // clock is behind.
+                let latest = self
+                    .ui
+                    .lock()
+                    .await
+                    .windows
+                    .iter()
+                    .flat_map(|window| {
+                        window
+                            .lines
+                            .iter()
+                            .filter(|line| line.author.is_some() && line.author != window.local_public_key)
+                            .map(|line| line.timestamp)
+                    })
+                    .max()
+                    .unwrap_or(0);
+                if latest > now + 600_000 {
+                    results.push(Err(tr_args("posts were received from up to {} minutes in the future; check that the system clock is correct", &[&((latest - now) / 60_000)])));
+                } else {
+                    results.push(Ok(tr_args("the system clock reads {}", &[&time::format_iso(now)])));
+                }
+            }
+            Err(err) => results.push(Err(tr_args("failed to read the system clock: {}", &[&err]))),
+        }
+
+        // Connections and listeners.
+        if self.connections.is_empty() {
+            results.push(Err(tr(
+                "no connections, so nothing can sync; use /connect HOST:PORT or /listen PORT",
+            )
+            .to_string()));
+        }
+        for connection in self.connections.iter() {
+            match connection {
+                Connection::Connected(cabal, addr) => results.push(Ok(tr_args(
+                    "connected to {} (cabal {})",
+                    &[&addr, &self.cabal_name(cabal)],
+                ))),
+                Connection::Listening(_cabal, addr) => {
+                    // Connecting to the listener would be accepted as a peer,
+                    // so the listener is instead checked by binding its
+                    // address, which fails while the listener holds it.
+                    let tcp_addr = addr.strip_prefix("ws://").unwrap_or(addr);
+                    match net::TcpListener::bind(tcp_addr).await {
+                        Err(err) if err.kind() == io::ErrorKind::AddrInUse => results.push(Ok(tr_args("listener on {} holds its port; peers on other networks may also require the port to be forwarded", &[&addr]))),
+                        Ok(_listener) => results.push(Err(tr_args("listener on {} no longer holds its port; restart it with /listen", &[&addr]))),
+                        Err(err) => results.push(Err(tr_args("failed to check the listener on {}: {}", &[&addr, &err]))),
+                    }
+                }
+            }
+        }
+        if let Some(peer) = args.get(1) {
+            match future::timeout(DOCTOR_TIMEOUT, net::TcpStream::connect(peer)).await {
+                Ok(Ok(_stream)) => results.push(Ok(tr_args("peer {} is reachable", &[&peer]))),
+                Ok(Err(err)) => results.push(Err(tr_args("could not connect to peer {}: {}; check the address and that the peer is listening", &[&peer, &err]))),
+                Err(_) => results.push(Err(tr_args("peer {} did not respond; check the address and any firewalls in between", &[&peer]))),
+            }
+        }
+
+        let mut ui = self.ui.lock().await;
+        let problems = results.iter().filter(|result| result.is_err()).count();
+        for result in results {
+            match result {
+                Ok(msg) => ui.write_status(Level::Info, &format!("{} {}", tr("ok:"), msg)),
+                Err(msg) => ui.write_status(Level::Error, &format!("{} {}", tr("problem:"), msg)),
+            }
+        }
+        ui.write_status(Level::Info, &tr_args("{} problems found", &[&problems]));
+        ui.update();
+    }
+
+    /// Handle the `/help` command.
+    ///
+    /// Prints a description and usage example for all commands.
+    async fn help_handler(&mut self) {
+        let mut ui = self.ui.lock().await;
+        for (syntax, description) in HELP {
+            ui.write_status(Level::Info, syntax);
+            ui.write_status(Level::Info, &format!("  {}", tr(description)));
+        }
+        ui.update();
+    }
+
+    /// Handle the `/history` command.
+    ///
+    /// Clears the input history, both in memory and on disk.
+    async fn history_handler(&mut self, args: Vec<String>) {
+        let mut ui = self.ui.lock().await;
+        if let Some("clear") = args.get(1).map(|arg| arg.as_str()) {
+            ui.input.history.clear();
+            match history::clear() {
+                Ok(()) => ui.write_status(Level::Info, tr("cleared input history")),
+                Err(err) => ui.write_status(
+                    Level::Error,
+                    &tr_args("failed to clear input history: {}", &[&err]),
+                ),
+            }
+        } else {
+            ui.write_status(Level::Error, &usage("/history clear"));
+        }
+        ui.update();
+    }
+
+    /// Handle the `/info` command.
+    ///
+    /// Displays the metadata of the Nth most recent post in the active
+    /// window: its hash, the full public key of its author, the hashes it
+    /// references, its timestamp and its encoded length.
+    async fn info_handler(&mut self, args: Vec<String>) {
+        let Some(n) = args.get(1).and_then(|n| n.parse::<usize>().ok()) else {
+            self.write_status(Level::Error, &usage("/info N")).await;
+            return;
+        };
+
+        let (address, channel, line) = {
+            let mut ui = self.ui.lock().await;
+            let window = ui.get_active_window();
+            (
+                window.address.clone(),
+                window.channel.clone(),
+                window.nth_recent_post(n).cloned(),
+            )
+        };
+        let Some(Line {
+            hash: Some(hash),
+            timestamp,
+            author,
+            nickname,
+            text,
+            ..
+        }) = line
+        else {
+            self.write_status(
+                Level::Error,
+                &tr_args("no post found at position {}", &[&n]),
+            )
+            .await;
+            return;
+        };
+
+        // The links and encoded length are only known from the stored post,
+        // which is found among the posts of the channel with the same
+        // timestamp.
+        let mut post = None;
+        if let Some(cable) = self.client.cable(&address) {
+            let opts = ChannelOptions {
+                channel,
+                time_start: timestamp,
+                time_end: timestamp + 1,
+                limit: 4096,
+            };
+            let mut store = cable.store.clone();
+            let mut posts = store.get_posts(&opts).await;
+            while let Some(Ok(candidate)) = posts.next().await {
+                if candidate.hash().ok() == Some(hash) {
+                    post = Some(candidate);
+                    break;
+                }
+            }
+        }
+
+        let mut lines = vec![tr_args("hash: {}", &[&hex::to(&hash)])];
+        if let Some(public_key) = author {
+            lines.push(tr_args("public key: {}", &[&hex::to(&public_key)]));
+        }
+        if let Some(nickname) = nickname {
+            lines.push(tr_args("author: {}", &[&nickname]));
+        }
+        lines.push(tr_args("timestamp: {}", &[&time::format_iso(timestamp)]));
+        if let Some((reply_hash, _text)) = utils::parse_reply(&text) {
+            lines.push(tr_args("reply to: {}", &[&hex::to(&reply_hash)]));
+        }
+        match post {
+            Some(post) => {
+                let links = post
+                    .header
+                    .links
+                    .iter()
+                    .map(|link| hex::to(link))
+                    .collect::<Vec<String>>();
+                if links.is_empty() {
+                    lines.push(tr("links: { none }").to_string());
+                } else {
+                    lines.push(tr_args("links: {}", &[&links.join(", ")]));
+                }
+                match post.to_bytes() {
+                    Ok(bytes) => lines.push(tr_args("length: {} bytes", &[&bytes.len()])),
+                    Err(err) => lines.push(tr_args("length: { unknown: {} }", &[&err])),
+                }
+            }
+            None => lines.push(tr("{ post not found in the local store }").to_string()),
+        }
+
+        let mut ui = self.ui.lock().await;
+        let window = ui.get_active_window();
+        for line in lines {
+            window.write(&line);
+        }
+        ui.update();
+    }
+
+    /// Handle the `/create` command.
+    ///
+    /// Joins the given channel (verbatim, without matching known channels),
+    /// sets its topic if one is given and prints an invite which can be
+    /// pasted to other peers.
+    async fn create_handler(&mut self, args: Vec<String>) -> Result<(), Error> {
+        let Some(channel) = args
+            .get(1)
+            .map(|arg| arg.strip_prefix('#').unwrap_or(arg).to_owned())
+            .filter(|channel| !channel.is_empty())
+        else {
+            self.write_status(Level::Error, &usage("/create CHANNEL [TOPIC]"))
+                .await;
+            return Ok(());
+        };
+        let Some((address, mut cable)) = self.get_active_cable().await else {
+            self.write_status(
+                Level::Error,
+                tr(r#"no active cabal to create a channel in. use "/cabal add" first"#),
+            )
+            .await;
+            return Ok(());
+        };
+
+        // The channel is created with the exact name given, rather than
+        // fuzzily matching a known channel.
+        self.join_channel(address.clone(), cable.clone(), &channel, true)
+            .await?;
+
+        if args.len() > 2 {
+            let topic = args[2..].join(" ");
+            cable.post_topic(&channel, &topic).await?;
+            let mut ui = self.ui.lock().await;
+            if let Some(window) = ui.get_window(&address, &channel) {
+                window.update_topic(topic);
+            }
+        }
+
+        let invite = self.invite(&address, Some(&channel));
+        let mut ui = self.ui.lock().await;
+        ui.write_status(
+            Level::Info,
+            &tr_args("created #{}. invite others with:", &[&channel]),
+        );
+        for line in invite {
+            ui.write_status(Level::Info, &line);
+        }
+        ui.update();
+
+        Ok(())
+    }
+
+    /// Handle the `/invite` command.
+    ///
+    /// Prints an invite to the active cabal and the given channel (or the
+    /// channel of the active window), which can be pasted to other peers.
+    async fn invite_handler(&mut self, args: Vec<String>) {
+        let Some(address) = self.get_active_address().await else {
+            self.write_status(Level::Error, tr("no active cabal to invite others to"))
+                .await;
+            return;
+        };
+        let channel = match args.get(1) {
+            Some(arg) => Some(arg.strip_prefix('#').unwrap_or(arg).to_owned()),
+            None => {
+                let mut ui = self.ui.lock().await;
+                let window = ui.get_active_window();
+                (!window.is_virtual()).then(|| window.channel.clone())
+            }
+        };
+
+        let invite = self.invite(&address, channel.as_deref());
+        let mut ui = self.ui.lock().await;
+        ui.write_status(Level::Info, tr("invite others with:"));
+        for line in invite {
+            ui.write_status(Level::Info, &line);
+        }
+        ui.update();
+    }
+
+    /// Return the lines of an invite to the given cabal and, optionally,
+    /// channel, including the addresses at which the local peer is
+    /// reachable.
+    fn invite(&self, address: &Addr, channel: Option<&str>) -> Vec<String> {
+        let key = hex::to(address);
+        let mut lines = vec![match channel {
+            Some(channel) => tr_args(
+                "  join #{} on cabal://{} (in cabin: /cabal add {} then /join {})",
+                &[&channel, &key, &key, &channel],
+            ),
+            None => tr_args("  join cabal://{} (in cabin: /cabal add {})", &[&key, &key]),
+        }];
+        for reachable in self.advertised.get(address).into_iter().flatten() {
+            lines.push(if reachable.starts_with("ws://") {
+                tr_args("  connect to {} from a browser peer", &[&reachable])
+            } else {
+                tr_args(
+                    "  connect to {} (in cabin: /connect {})",
+                    &[&reachable, &reachable],
+                )
+            });
+        }
+        lines
+    }
+
+    /// Handle the `/join` and `/j` commands.
+    ///
+    /// Sets the active window of the UI, publishes a `post/join` if the local
+    /// peer is not already a channel member, creates a channel time range
+    /// request and updates the UI with stored and received posts.
+    ///
+    /// The `interactive` flag distinguishes joins entered as a command from
+    /// those triggered by restoring a session; it determines whether a newly
+    /// created window becomes active, according to the `autofocus` setting.
+    /// Interactive joins fuzzily match the given name against known channels.
+    async fn join_handler(&mut self, args: Vec<String>, interactive: bool) -> Result<(), Error> {
+        let pending_join = self.pending_join.take();
+        let Some((address, cable)) = self.get_active_cable().await else {
+            let mut ui = self.ui.lock().await;
+            ui.write_status(
+                Level::Error,
+                tr("cannot join channel with no active cabal set. add a cabal with \"/cabal add\" first"),
+            );
+            ui.update();
+            return Ok(());
+        };
+        let Some(arg) = args.get(1) else {
+            return self.list_joinable_channels(&address, &cable).await;
+        };
+
+        // Resolve the channel name, fuzzily matching known channels if the
+        // name was typed by the user.
+        let channel = if interactive && pending_join.as_ref() != Some(arg) {
+            match self.match_channel(&address, &cable, arg).await {
+                Some(channel) => channel,
+                None => return Ok(()),
+            }
+        } else {
+            arg.to_owned()
+        };
+
+        self.join_channel(address, cable, &channel, interactive)
+            .await
+    }
+
+    /// Join the given channel of the given cabal by its exact name.
+    ///
+    /// The `interactive` flag is as for `join_handler`.
+    async fn join_channel(
+        &mut self,
+        address: Addr,
+        mut cable: CableManager<S>,
+        channel: &Channel,
+        interactive: bool,
+    ) -> Result<(), Error> {
+        // Check if the local peer is already a member of this channel.
+        // If not, publish a `post/join` post.
+        if let Some((public_key, _private_key)) = cable.store.get_keypair().await {
+            if !cable.store.is_channel_member(channel, &public_key).await {
+                // TODO: Match on validation error and display to user.
+                cable.post_join(channel).await?;
+            }
+        }
+
+        let local_public_key = cable.store.get_keypair().await.map(|(key, _)| key);
+
+        let mut ui = self.ui.lock().await;
+        let channel_window_index = ui.get_window_index(&address, channel);
+
+        // Define the window index.
+        //
+        // First check if a window has previously been created for the
+        // given address / channel combination. If so, return the
+        // index. Otherwise, add a new window and return the index.
+        let index =
+            channel_window_index.unwrap_or_else(|| ui.add_window(address.clone(), channel.clone()));
+
+        let ch = channel.clone();
+
+        ui.windows[index].local_public_key = local_public_key;
+
+        // Joining an existing window always switches to it; newly
+        // created windows are focused according to the setting.
+        let focus = match ui.settings.autofocus {
+            Autofocus::Always => true,
+            Autofocus::Command => interactive,
+            Autofocus::Never => interactive && channel_window_index.is_some(),
+        };
+        if focus {
+            ui.set_active_index(index);
+        }
+        ui.update();
+        // The UI remains locked if not explicitly dropped here.
+        drop(ui);
+
+        // Define the channel options, requesting posts as far back
+        // as the backfill horizon of the cabal.
+        let backfill = self.backfill.get(&address).copied().unwrap_or_default();
+        let opts = ChannelOptions {
+            channel: ch.clone(),
+            time_start: backfill.start(self.clock.as_ref())?,
+            time_end: 0,
+            limit: 4096,
+        };
+
+        let store = cable.store.clone();
+        let nicks = self.nicks.clone();
+        let index = self.index.clone();
+        let ui = self.ui.clone();
+        let mut ui = ui.lock().await;
+
+        // Open the channel and update the UI with stored and received
+        // text posts; only if this action has not been performed
+        // previously.
+        //
+        // The window index is used as a proxy for "channel has been
+        // initialised".
+        if channel_window_index.is_none() {
+            ui.write_status(Level::Info, &tr_args("joined channel {}", &[&channel]));
+            if let Some(window) = ui.get_window(&address, channel) {
+                window.request_start = Some(opts.time_start);
+            }
+            ui.update();
+
+            let mut stored_posts_stream = cable.store.get_posts(&opts).await;
+            while let Some(post_stream) = stored_posts_stream.next().await {
+                if let Ok(post) = post_stream {
+                    let timestamp = post.header.timestamp;
+                    let public_key = post.header.public_key;
+                    let hash = post.hash().ok();
+                    ui.record_seen(&address, public_key, timestamp, channel);
+                    let ignored = ui.ignored_types(&public_key);
+                    let nickname = nicks
+                        .get(&address, &store, &public_key)
+                        .await
+                        .filter(|_| !ignored.contains(&Ignore::Nicks));
+
+                    if let PostBody::Text { channel, text } = post.body {
+                        if let Some(hash) = hash {
+                            let document = Document {
+                                channel: channel.clone(),
+                                timestamp,
+                                author: public_key,
+                                text: text.clone(),
+                            };
+                            index.insert(&address, hash, document).await;
+                        }
+                        if let Some(window) = ui.get_window(&address, &channel) {
+                            window.insert(timestamp, Some(public_key), nickname, &text, hash);
+                            ui.update();
+                        }
+                    } else if let PostBody::Topic { channel, topic } = post.body {
+                        if ignored.contains(&Ignore::Topics) {
+                            continue;
+                        }
+                        if let Some(window) = ui.get_window(&address, &channel) {
+                            window.update_topic(topic);
+                            ui.update();
+                        }
+                    }
+                }
+            }
+            drop(stored_posts_stream);
+
+            // Create an abort handle and add it to the local map.
+            //
+            // This allows the `display_posts` task to be aborted
+            // when the channel is left, thereby preventing double
+            // posting to the UI if the channel is later rejoined.
+            let (abort_handle, abort_registration) = AbortHandle::new_pair();
+            self.abort_handles
+                .lock()
+                .await
+                .insert(channel.to_owned(), abort_handle);
+
+            let store = cable.store.clone();
+            let nicks = self.nicks.clone();
+            let index = self.index.clone();
+            let clock = self.clock.clone();
 
             let ui = self.ui.clone();
+            let display_posts = async move {
+                let mut opts = opts;
+                let mut stream = match cable.open_channel(&opts).await {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        report_channel_error(&ui, &address, &opts.channel, err).await;
+                        return;
+                    }
+                };
+                let mut requested = Instant::now();
 
-            // Register the connection.
-            self.connections
-                .insert(Connection::Connected(tcp_addr.clone()));
+                loop {
+                    // Wait for the next post, renewing the time range
+                    // request when the refresh interval elapses. The
+                    // setting is checked periodically while disabled.
+                    let refresh = ui.lock().await.settings.refresh;
+                    let wait = match refresh {
+                        0 => REFRESH_CHECK_INTERVAL,
+                        secs => Duration::from_secs(secs).saturating_sub(requested.elapsed()),
+                    };
+                    let post_stream = match future::timeout(wait, stream.next()).await {
+                        Ok(Some(post_stream)) => post_stream,
+                        Ok(None) => break,
+                        Err(_) => {
+                            if refresh > 0 {
+                                debug!("Renewing request for channel {}", opts.channel);
+                                drop(stream);
+                                let _ = cable.close_channel(&opts.channel).await;
+                                opts.time_start = backfill.start(clock.as_ref()).unwrap_or(0);
+                                stream = match cable.open_channel(&opts).await {
+                                    Ok(stream) => stream,
+                                    Err(err) => {
+                                        report_channel_error(&ui, &address, &opts.channel, err)
+                                            .await;
+                                        break;
+                                    }
+                                };
+                                requested = Instant::now();
+                                if let Some(window) =
+                                    ui.lock().await.get_window(&address, &opts.channel)
+                                {
+                                    window.request_start = Some(opts.time_start);
+                                }
+                            }
+                            continue;
+                        }
+                    };
+                    if let Ok(post) = post_stream {
+                        metrics::POSTS_RECEIVED.inc();
+                        let timestamp = post.header.timestamp;
+                        let public_key = post.header.public_key;
+                        let hash = post.hash().ok();
 
-            // Attempt a TCP connection to the peer and invoke the
-            // cable listener.
-            task::spawn(async move {
-                let stream = net::TcpStream::connect(tcp_addr.clone()).await?;
+                        // TODO: Render moderation posts (role
+                        // assignments, blocks) as status lines once
+                        // they are supported by cable_core.
+
+                        // A `post/info` may change the nickname of
+                        // the author.
+                        if let PostBody::Info { .. } = post.body {
+                            nicks.invalidate(&address, &public_key).await;
+                        }
+                        // The member count and local nickname shown
+                        // in the header of the window may have
+                        // changed.
+                        let members = store
+                            .get_channel_members(&opts.channel)
+                            .await
+                            .map(|members| members.len());
+                        let local = local_peer(&address, &store, &nicks).await;
+                        // Nicknames of peers whose nicks are ignored
+                        // are not displayed.
+                        let ignored = {
+                            let mut ui = ui.lock().await;
+                            if let Some(window) = ui.get_window(&address, &opts.channel) {
+                                window.record_arrival();
+                                window.members = members;
+                            }
+                            if let Some((_, Some(nick))) = &local {
+                                ui.local_nicks.insert(address.clone(), nick.clone());
+                            }
+                            ui.record_seen(&address, public_key, timestamp, &opts.channel);
+                            ui.ignored_types(&public_key)
+                        };
+                        let nickname = nicks
+                            .get(&address, &store, &public_key)
+                            .await
+                            .filter(|_| !ignored.contains(&Ignore::Nicks));
+
+                        if let PostBody::Text { channel, text } = post.body {
+                            if let Some(hash) = hash {
+                                let document = Document {
+                                    channel: channel.clone(),
+                                    timestamp,
+                                    author: public_key,
+                                    text: text.clone(),
+                                };
+                                index.insert(&address, hash, document).await;
+                            }
+
+                            // Posts authored by the local peer are
+                            // never counted as unread activity.
+                            let is_local = local
+                                .as_ref()
+                                .map(|(local_key, _)| local_key == &public_key)
+                                .unwrap_or(false);
+                            let mention = local
+                                .and_then(|(_, nick)| nick)
+                                .map(|nick| utils::is_mention(&text, &nick))
+                                .unwrap_or(false);
+
+                            let name = nickname
+                                .clone()
+                                .unwrap_or_else(|| hex::to(&public_key[..4]));
+                            let mut ui = ui.lock().await;
+                            if !is_local {
+                                ui.record_timestamp(public_key, &name, timestamp);
+                            }
+                            // Posts of blocked peers are hidden, so
+                            // they neither count as activity nor
+                            // notify.
+                            let is_blocked = ui.blocked.contains(&public_key);
+                            if let Some(index) = ui.get_window_index(&address, &channel) {
+                                let inserted = ui.windows[index].insert(
+                                    timestamp,
+                                    Some(public_key),
+                                    nickname,
+                                    &text,
+                                    hash,
+                                );
+                                if inserted && !is_local && !is_blocked {
+                                    ui.mark_activity(index, mention);
+                                    if let (true, Some(hash)) = (mention, &hash) {
+                                        ui.record_mention(index, hash);
+                                    }
+                                }
+                                // Mentions received without a
+                                // terminal are kept for review on
+                                // the next interactive start,
+                                // unless their author is blocked.
+                                if inserted && !is_local && !is_blocked && mention && ui.headless {
+                                    let entry = awaylog::Entry {
+                                        timestamp,
+                                        channel: channel.clone(),
+                                        name,
+                                        text: text.clone(),
+                                    };
+                                    if let Err(err) = awaylog::append(&entry) {
+                                        warn!("Failed to write away log: {}", err);
+                                    }
+                                }
+                                ui.update();
+                            }
+                        } else if let PostBody::Topic { channel, topic } = post.body {
+                            if ignored.contains(&Ignore::Topics) {
+                                continue;
+                            }
+                            let mut ui = ui.lock().await;
+                            if let Some(window) = ui.get_window(&address, &channel) {
+                                window.update_topic(topic);
+                                ui.update();
+                            }
+                        } else if !ignored.contains(&Ignore::Joins) {
+                            let (channel, template) = match post.body {
+                                PostBody::Join { channel } => (channel, "{} joined the channel"),
+                                PostBody::Leave { channel } => (channel, "{} left the channel"),
+                                _ => continue,
+                            };
+                            let name = nickname.unwrap_or_else(|| hex::to(&public_key[..4]));
+                            let mut ui = ui.lock().await;
+                            if let Some(window) = ui.get_window(&address, &channel) {
+                                window.insert(
+                                    timestamp,
+                                    None,
+                                    None,
+                                    &tr_args(template, &[&name]),
+                                    None,
+                                );
+                                ui.update();
+                            }
+                        }
+                    }
+                }
+            };
+
+            task::spawn(Abortable::new(display_posts, abort_registration));
+        }
+
+        Ok(())
+    }
+
+    /// Match the given channel name against the known channels of the given
+    /// cabal.
+    ///
+    /// Returns the name of the channel to be joined: either an exact or
+    /// unique fuzzy match, or the given name if no known channel matches.
+    /// Returns `None` if the name is ambiguous, in which case the matching
+    /// channels are listed and repeating the command joins the channel
+    /// verbatim.
+    async fn match_channel(
+        &mut self,
+        address: &Addr,
+        cable: &CableManager<S>,
+        name: &str,
+    ) -> Option<String> {
+        let mut ui = self.ui.lock().await;
+        if ui.get_window_index(address, &name.to_owned()).is_some() {
+            return Some(name.to_owned());
+        }
+
+        let mut channels = cable.store.get_channels().await.unwrap_or_default();
+        if channels.iter().any(|channel| channel == name) {
+            return Some(name.to_owned());
+        }
+
+        // Hidden channels are only joined by their exact name.
+        if let Some(hidden) = self.hidden.get(address) {
+            channels.retain(|channel| !hidden.contains(channel));
+        }
+        match utils::match_channels(name, &channels).as_slice() {
+            [] => Some(name.to_owned()),
+            [channel] => {
+                ui.write_status(
+                    Level::Info,
+                    &tr_args("matched {} to channel {}", &[&name, &channel]),
+                );
+                Some(channel.to_string())
+            }
+            matches => {
+                let matches: Vec<&str> = matches.iter().map(|channel| channel.as_str()).collect();
+                ui.write_status(
+                    Level::Info,
+                    &tr_args(
+                        "{} matches several channels: {}",
+                        &[&name, &matches.join(", ")],
+                    ),
+                );
+                ui.write_status(
+                    Level::Info,
+                    &tr_args("refine the name or repeat the command to join {}", &[&name]),
+                );
+                ui.update();
+                self.pending_join = Some(name.to_owned());
+                None
+            }
+        }
+    }
+
+    /// List the known channels of the given cabal which are not hidden, most
+    /// recently active first.
+    async fn list_joinable_channels(
+        &mut self,
+        address: &Addr,
+        cable: &CableManager<S>,
+    ) -> Result<(), Error> {
+        let mut channels = cable.store.get_channels().await.unwrap_or_default();
+        if let Some(hidden) = self.hidden.get(address) {
+            channels.retain(|channel| !hidden.contains(channel));
+        }
+
+        // Determine the timestamp of the most recent post in each channel.
+        let mut store = cable.store.clone();
+        let mut activity = Vec::new();
+        for channel in channels {
+            let opts = ChannelOptions {
+                channel: channel.clone(),
+                time_start: 0,
+                time_end: 0,
+                limit: 1,
+            };
+            let mut latest = 0;
+            let mut posts = store.get_posts(&opts).await;
+            while let Some(Ok(post)) = posts.next().await {
+                latest = latest.max(post.header.timestamp);
+            }
+            activity.push((latest, channel));
+        }
+        activity.sort_by(|a, b| b.cmp(a));
+
+        let mut ui = self.ui.lock().await;
+        if activity.is_empty() {
+            ui.write_status(
+                Level::Info,
+                tr("{ no known channels for the active cabal }"),
+            );
+        } else {
+            ui.write_status(
+                Level::Info,
+                tr("known channels (most recently active first):"),
+            );
+            for (latest, channel) in activity {
+                let joined = if ui.get_window_index(address, &channel).is_some() {
+                    tr(" (joined)")
+                } else {
+                    ""
+                };
+                let active = if latest > 0 {
+                    tr_args(" - last post {}", &[&time::format(latest)])
+                } else {
+                    String::new()
+                };
+                ui.write_status(Level::Info, &format!("- {}{}{}", channel, joined, active));
+            }
+        }
+        ui.write_status(Level::Error, &usage("/join CHANNEL"));
+        ui.update();
+
+        Ok(())
+    }
+
+    /// Handle the `/leave` command.
+    ///
+    /// Cancels any active outbound channel time range requests for the
+    /// given channel and publishes a `post/leave`.
+    async fn leave_handler(&mut self, args: Vec<String>) -> Result<(), Error> {
+        if let Some((address, mut cable)) = self.get_active_cable().await {
+            if let Some(channel) = args.get(1) {
+                if let Some(channels) = cable.store.get_channels().await {
+                    // Avoid closing and leaving a channel that isn't known to the
+                    // local peer.
+                    if channels.contains(channel) {
+                        // Cancel any active outbound channel time range requests
+                        // for this channel.
+                        cable.close_channel(channel).await?;
+
+                        // Check if the local peer is a member of this channel.
+                        // If so, publish a `post/leave` post.
+                        if let Some((public_key, _private_key)) = cable.store.get_keypair().await {
+                            if cable.store.is_channel_member(channel, &public_key).await {
+                                // TODO: Match on validation error and display to user.
+                                cable.post_leave(channel).await?;
+                            }
+                        }
+
+                        self.close_channel_sender.send(channel.to_owned()).await?;
+
+                        let mut ui = self.ui.lock().await;
+                        // Remove the window associated with the given channel.
+                        if let Some(index) = ui.get_window_index(&address, channel) {
+                            ui.remove_window(index)
+                        }
+                        // Return to the home / status window.
+                        ui.set_active_index(0);
+                        ui.write_status(Level::Info, &tr_args("left channel {}", &[&channel]));
+                        ui.update();
+                    }
+                } else {
+                    let mut ui = self.ui.lock().await;
+                    ui.write_status(
+                        Level::Error,
+                        &tr_args(
+                            "not currently a member of channel {}; no action taken",
+                            &[&channel],
+                        ),
+                    );
+                    ui.update();
+                }
+            } else {
+                let mut ui = self.ui.lock().await;
+                ui.write_status(Level::Error, &usage("/leave CHANNEL"));
+                ui.update();
+            }
+        } else {
+            let mut ui = self.ui.lock().await;
+            ui.write_status(
+                Level::Error,
+                tr("cannot leave channel with no active cabal set. add a cabal with \"/cabal add\" first"),
+            );
+            ui.update();
+        }
+
+        Ok(())
+    }
+
+    /// Handle the `/listen` command.
+    ///
+    /// Deploys a TCP server on the given host:port, listens for incoming
+    /// connections and passes any resulting streams to the cable manager.
+    ///
+    /// `/listen auto` binds a port chosen by the operating system and
+    /// records the address at which it is reachable for inclusion in
+    /// invites.
+    async fn listen_handler(&mut self, args: Vec<String>) {
+        // Retrieve the active cable address (aka. key).
+        if self.get_active_address().await.is_none() {
+            self.write_status(
+                Level::Error,
+                tr(r#"no active cabal to bind this connection. use "/cabal add" first"#),
+            )
+            .await;
+        } else if let Some(mut tcp_addr) = args.get(1).cloned() {
+            // Listen for WebSocket connections rather than raw TCP
+            // connections if the address has a `ws://` scheme.
+            let websocket = match tcp_addr.strip_prefix("ws://") {
+                Some(addr) => {
+                    tcp_addr = addr.to_owned();
+                    true
+                }
+                None => false,
+            };
+            if websocket && !cfg!(feature = "websocket") {
+                self.write_status(
+                    Level::Error,
+                    tr("websocket listeners require cabin to be built with the `websocket` feature"),
+                )
+                .await;
+                return;
+            }
+
+            let auto = tcp_addr == "auto";
+            if auto {
+                tcp_addr = "0".to_string();
+            }
+
+            // Listen on the wildcard address of each enabled IP version if
+            // a host was not supplied.
+            let mut listeners = Vec::new();
+            if tcp_addr.contains(':') {
+                match net::TcpListener::bind(&tcp_addr).await {
+                    Ok(listener) => listeners.push((tcp_addr, Some(listener))),
+                    Err(err) => {
+                        self.write_status(
+                            Level::Error,
+                            &tr_args("failed to listen on {}: {}", &[&tcp_addr, &err]),
+                        )
+                        .await
+                    }
+                }
+            } else {
+                let mut port = tcp_addr;
+                let mode = self.ui.lock().await.settings.listen;
+                for host in mode.wildcards() {
+                    let tcp_addr = format!("{}:{}", host, port);
+                    match net::TcpListener::bind(&tcp_addr).await {
+                        Ok(listener) => {
+                            // A port chosen by the operating system is
+                            // reused for the remaining addresses.
+                            if let Ok(local_addr) = listener.local_addr() {
+                                port = local_addr.port().to_string();
+                            }
+                            listeners.push((format!("{}:{}", host, port), Some(listener)));
+                        }
+                        // The IPv6 listener also accepts IPv4 connections.
+                        Err(err)
+                            if err.kind() == io::ErrorKind::AddrInUse
+                                && *host == "0.0.0.0"
+                                && !listeners.is_empty() =>
+                        {
+                            debug!("IPv6 listener on port {} is dual-stack", port);
+                            listeners.push((tcp_addr, None));
+                        }
+                        Err(err) => {
+                            self.write_status(
+                                Level::Error,
+                                &tr_args("failed to listen on {}: {}", &[&tcp_addr, &err]),
+                            )
+                            .await
+                        }
+                    }
+                }
+            }
+            if listeners.is_empty() {
+                return;
+            }
+            // The port of an automatic listener is only known once bound.
+            let port = listeners
+                .iter()
+                .find_map(|(_, listener)| listener.as_ref()?.local_addr().ok())
+                .map(|local_addr| local_addr.port());
+
+            // Retrieve the active cable manager.
+            let (address, cable) = self.get_active_cable().await.unwrap();
+
+            let mut tasks = ConnectionTasks::new();
+            for (tcp_addr, listener) in listeners {
+                let listen_addr = if websocket {
+                    format!("ws://{}", tcp_addr)
+                } else {
+                    tcp_addr
+                };
+
+                // Register the listener. An IPv4 address served by a
+                // dual-stack IPv6 listener shares the tasks of the latter.
+                if listener.is_some() {
+                    tasks = ConnectionTasks::new();
+                }
+                let connection = Connection::Listening(address.clone(), listen_addr.clone());
+                self.register_connection(connection, tasks.clone());
 
-                // This block expression is needed to drop the lock and prevent
-                // blocking of the UI.
+                // Update the UI.
                 {
-                    // Update the UI.
-                    let mut ui = ui.lock().await;
-                    ui.write_status(&format!("connected to {}", tcp_addr));
+                    let msg = tr_args("listening on {}", &[&listen_addr]);
+                    let mut ui = self.ui.lock().await;
+                    ui.write_network(&msg);
+                    ui.write_status(Level::Network, &msg);
+                    ui.update();
+                }
+
+                if let Some(listener) = listener {
+                    tasks.spawn(accept_connections(
+                        listener,
+                        websocket,
+                        cable.clone(),
+                        self.ui.clone(),
+                        self.admission.clone(),
+                        self.offenders.clone(),
+                        tasks.clone(),
+                    ));
+                }
+            }
+
+            if let (true, Some(port)) = (auto, port) {
+                self.advertise_listener(&address, port, websocket).await;
+            }
+        } else {
+            // Print usage example for the listen command.
+            let mut ui = self.ui.lock().await;
+            ui.write_status(Level::Error, &usage("/listen (ws://)(ADDR:)PORT|auto"));
+            ui.update();
+        }
+    }
+
+    /// Detect the address at which the given listening port is reachable
+    /// and record it for inclusion in invites to the given cabal.
+    ///
+    /// The public IP address is queried from the configured STUN server,
+    /// falling back to the address of the local network interface.
+    async fn advertise_listener(&mut self, address: &Addr, port: u16, websocket: bool) {
+        let ip = match &self.stun {
+            Some(server) => match stun::public_ip(server).await {
+                Ok(ip) => Ok(ip),
+                Err(err) => {
+                    self.write_status(
+                        Level::Error,
+                        &tr_args("failed to query stun server {}: {}", &[&server, &err]),
+                    )
+                    .await;
+                    stun::local_ip().await
+                }
+            },
+            None => stun::local_ip().await,
+        };
+
+        match ip {
+            Ok(ip) => {
+                let reachable = SocketAddr::new(ip, port).to_string();
+                let reachable = if websocket {
+                    format!("ws://{}", reachable)
+                } else {
+                    reachable
+                };
+                self.write_status(
+                    Level::Network,
+                    &tr_args(
+                        "reachable at {} unless port {} is blocked by a firewall or nat. share it with /invite",
+                        &[&reachable, &port],
+                    ),
+                )
+                .await;
+                self.advertised
+                    .entry(address.clone())
+                    .or_default()
+                    .push(reachable);
+            }
+            Err(err) => {
+                self.write_status(
+                    Level::Error,
+                    &tr_args("failed to detect the address of this peer: {}", &[&err]),
+                )
+                .await
+            }
+        }
+    }
+
+    /// Handle the `/lock` command.
+    ///
+    /// Hides all windows until the passphrase whose hash is configured as
+    /// `lock` is entered.
+    async fn lock_handler(&mut self) {
+        let Some(lock) = &self.lock else {
+            self.write_status(Level::Error, tr(
+                "no passphrase is configured; set lock in the configuration file to the argon2 hash of a passphrase",
+            ))
+            .await;
+            return;
+        };
+        // A hash which cannot be parsed would never match, leaving the UI
+        // locked for good.
+        if PasswordHash::new(lock.trim()).is_err() {
+            self.write_status(Level::Error, tr(
+                "the configured lock is not an argon2 hash; see \"lock the screen\" in the readme",
+            ))
+            .await;
+            return;
+        }
+
+        let mut ui = self.ui.lock().await;
+        if ui.headless {
+            ui.write_status(
+                Level::Error,
+                tr("the ui cannot be locked while running as a daemon"),
+            );
+            return;
+        }
+        ui.start_lock();
+        ui.update();
+    }
+
+    /// Handle the `/members` command.
+    ///
+    /// Prints a list of known members of a channel. If this handler is invoked
+    /// from an active channel window, the members of that channel will be
+    /// printed. Otherwise, the handler can be invoked with a specific channel
+    /// name as an argument; this is useful for printing channel members when
+    /// the status window is active.
+    async fn members_handler(&mut self, args: Vec<String>) {
+        // TODO: Display admin / moderator badges next to each member once the
+        // cable moderation extension (roles and blocks) is supported by
+        // cable_core. A `/mod` command for assigning roles belongs here too.
+        if let Some((address, cable)) = self.get_active_cable().await {
+            if let Some(channel) = args.get(1) {
+                let mut ui = self.ui.lock().await;
+
+                if let Some(members) = cable.store.get_channel_members(channel).await {
+                    for member in members {
+                        // Retrieve and print the nick for each member's
+                        // public key.
+                        if let Some(name) = self.nicks.get(&address, &cable.store, &member).await {
+                            ui.write_status(Level::Info, &format!("  {}", name));
+                        } else {
+                            // Fall back to the public key (formatted as a
+                            // hex string) if no nick is known.
+                            ui.write_status(Level::Info, &format!("  {}", hex::to(&member)));
+                        }
+                    }
+                } else {
+                    ui.write_status(
+                        Level::Info,
+                        tr("{ no known channel members for the active cabal and channel }"),
+                    );
+                }
+                ui.update();
+            } else {
+                // No args were passed to the `/members` handler. Attempt to
+                // determine the channel for the active window and print the
+                // members.
+                let mut ui = self.ui.lock().await;
+                let index = ui.get_active_index();
+                // Don't attempt to retrieve and print channel members if the
+                // status window is active.
+                if index != 0 {
+                    let window = ui.get_active_window();
+                    if let Some(members) = cable.store.get_channel_members(&window.channel).await {
+                        for member in members {
+                            // Retrieve and print the nick for each member's
+                            // public key.
+                            if let Some(name) =
+                                self.nicks.get(&address, &cable.store, &member).await
+                            {
+                                ui.write_status(Level::Info, &format!("  {}", name));
+                            } else {
+                                // Fall back to the public key (formatted as a
+                                // hex string) if no nick is known.
+                                ui.write_status(Level::Info, &format!("  {}", hex::to(&member)));
+                            }
+                        }
+                    } else {
+                        ui.write_status(
+                            Level::Info,
+                            tr("{ no known channel members for the active cabal and channel }"),
+                        );
+                    }
                     ui.update();
                 }
-
-                cable.listen(stream).await?;
-
-                // Type inference fails without binding concretely to `Result`.
-                Result::<(), Error>::Ok(())
-            });
+            };
         } else {
-            // Print usage example for the connect command.
             let mut ui = self.ui.lock().await;
-            ui.write_status("usage: /connect HOST:PORT");
+            ui.write_status(
+                Level::Error,
+                tr("cannot list channel members with no active cabal set. add a cabal with \"/cabal add\" first"),
+            );
             ui.update();
         }
     }
 
-    /// Handle the `/connections` command.
-    ///
-    /// Prints a list of active TCP connections.
-    async fn connections_handler(&mut self) {
-        let mut ui = self.ui.lock().await;
-        for connection in self.connections.iter() {
-            ui.write_status(&match connection {
-                Connection::Connected(addr) => format!("connected to {}", addr),
-                Connection::Listening(addr) => format!("listening on {}", addr),
-            });
-        }
-        if self.connections.is_empty() {
-            ui.write_status("{ no connections in list }");
-        }
-        ui.update();
-    }
-
-    /// Handle the `/delete` command.
+    /// Handle the `/nick` command.
     ///
-    /// Deletes the most recently set nickname for the local peer.
-    async fn delete_handler(&mut self, args: Vec<String>) -> Result<(), Error> {
-        if let Some((_address, mut cable)) = self.get_active_cable().await {
-            if let Some("nick") = args.get(1).map(|arg| arg.as_str()) {
+    /// Set the nickname for the local peer.
+    async fn nick_handler(&mut self, args: Vec<String>) -> Result<(), Error> {
+        if let Some((address, mut cable)) = self.get_active_cable().await {
+            if let Some(nick) = args.get(1) {
+                let _hash = cable.post_info_name(nick).await?;
                 if let Some((public_key, _private_key)) = cable.store.get_keypair().await {
-                    if let Some((_name, hash)) =
-                        cable.store.get_peer_name_and_hash(&public_key).await
-                    {
-                        cable.post_delete(vec![hash]).await?;
-                        let mut ui = self.ui.lock().await;
-                        ui.write_status("deleted most recent nickname");
-                        ui.update();
-                    } else {
-                        let mut ui = self.ui.lock().await;
-                        ui.write_status("no nickname found for the local peer");
-                        ui.update();
-                    }
+                    self.nicks.invalidate(&address, &public_key).await;
                 }
+                let mut ui = self.ui.lock().await;
+                ui.local_nicks.insert(address.clone(), nick.clone());
+                ui.write_status(
+                    Level::Info,
+                    &tr_args("nickname set to {}", &[&format!("{:?}", nick)]),
+                );
+                ui.update();
             } else {
-                self.write_status("usage: /delete nick").await;
+                let mut ui = self.ui.lock().await;
+                ui.write_status(Level::Error, &usage("/nick NAME"));
+                ui.update();
             }
         } else {
             let mut ui = self.ui.lock().await;
-            ui.write_status(&format!(
-                "{}{}",
-                "cannot delete nickname with no active cabal set.",
-                " add a cabal with \"/cabal add\" first",
-            ));
+            ui.write_status(
+                Level::Error,
+                tr("cannot assign nickname with no active cabal set. add a cabal with \"/cabal add\" first"),
+            );
             ui.update();
         }
+
         Ok(())
     }
 
-    /// Handle the `/help` command.
+    /// Handle the `/pin` and `/unpin` commands.
     ///
-    /// Prints a description and usage example for all commands.
-    async fn help_handler(&mut self) {
+    /// Pins the Nth most recent post in the active channel window (where 1 is
+    /// the most recent) or unpins the Nth pinned post (as listed by `/pins`).
+    async fn pin_handler(&mut self, args: Vec<String>) {
+        let pin = args[0] == "/pin";
         let mut ui = self.ui.lock().await;
-        ui.write_status("/cabal add ADDR");
-        ui.write_status("  add a cabal");
-        ui.write_status("/cabal set ADDR");
-        ui.write_status("  set the active cabal");
-        ui.write_status("/cabal list");
-        ui.write_status("  list all known cabals");
-        ui.write_status("/channels");
-        ui.write_status("  list all known channels");
-        ui.write_status("/connections");
-        ui.write_status("  list all known network connections");
-        ui.write_status("/connect HOST:PORT");
-        ui.write_status("  connect to a peer over tcp");
-        ui.write_status("/delete nick");
-        ui.write_status("  delete the most recent nick");
-        ui.write_status("/join CHANNEL");
-        ui.write_status("  join a channel (shorthand: /j CHANNEL)");
-        ui.write_status("/listen PORT");
-        ui.write_status("  listen for incoming tcp connections on 0.0.0.0");
-        ui.write_status("/listen HOST:PORT");
-        ui.write_status("  listen for incoming tcp connections");
-        ui.write_status("/members CHANNEL");
-        ui.write_status("  list all known members of the channel");
-        ui.write_status("/topic");
-        ui.write_status("  list the topic of the active channel");
-        ui.write_status("/topic TOPIC");
-        ui.write_status("  set the topic of the active channel");
-        ui.write_status("/whoami");
-        ui.write_status("  list the local public key as a hex string");
-        ui.write_status("/win INDEX");
-        ui.write_status("  change the active window (shorthand: /w INDEX)");
-        ui.write_status("/exit");
-        ui.write_status("  exit the cabal process");
-        ui.write_status("/quit");
-        ui.write_status("  exit the cabal process (shorthand: /q)");
+
+        let n = match args.get(1).and_then(|n| n.parse::<usize>().ok()) {
+            Some(n) => n,
+            None => {
+                ui.write_status(
+                    Level::Error,
+                    &usage(if pin { "/pin N" } else { "/unpin N" }),
+                );
+                ui.update();
+                return;
+            }
+        };
+
+        let window = ui.get_active_window();
+        if window.is_virtual() {
+            ui.write_status(
+                Level::Error,
+                tr("posts can only be pinned or unpinned from a channel window"),
+            );
+            ui.update();
+            return;
+        }
+        let key = (window.address.to_owned(), window.channel.to_owned());
+
+        if pin {
+            if let Some(hash) = window.nth_recent_post(n).and_then(|line| line.hash) {
+                let pins = self.pins.entry(key.clone()).or_default();
+                if !pins.contains(&hash) {
+                    pins.push(hash);
+                }
+                ui.write_status(
+                    Level::Info,
+                    &tr_args("pinned post {} in #{}", &[&hex::to(&hash[..4]), &key.1]),
+                );
+            } else {
+                ui.write_status(
+                    Level::Error,
+                    &tr_args("no post found at position {}", &[&n]),
+                );
+            }
+        } else {
+            let pins = self.pins.entry(key.clone()).or_default();
+            if n > 0 && n <= pins.len() {
+                let hash = pins.remove(n - 1);
+                ui.write_status(
+                    Level::Info,
+                    &tr_args("unpinned post {} in #{}", &[&hex::to(&hash[..4]), &key.1]),
+                );
+            } else {
+                ui.write_status(
+                    Level::Error,
+                    &tr_args("no pinned post found at position {}", &[&n]),
+                );
+            }
+        }
         ui.update();
     }
 
-    /// Handle the `/join` and `/j` commands.
+    /// Handle the `/pins` command.
     ///
-    /// Sets the active window of the UI, publishes a `post/join` if the local
-    /// peer is not already a channel member, creates a channel time range
-    /// request and updates the UI with stored and received posts.
-    async fn join_handler(&mut self, args: Vec<String>) -> Result<(), Error> {
-        if let Some((address, mut cable)) = self.get_active_cable().await {
-            if let Some(channel) = args.get(1) {
-                // Check if the local peer is already a member of this channel.
-                // If not, publish a `post/join` post.
-                if let Some((public_key, _private_key)) = cable.store.get_keypair().await {
-                    if !cable.store.is_channel_member(channel, &public_key).await {
-                        // TODO: Match on validation error and display to user.
-                        cable.post_join(channel).await?;
-                    }
-                }
-
-                let mut ui = self.ui.lock().await;
-                let channel_window_index = ui.get_window_index(&address, channel);
+    /// Displays the pinned posts of the active channel in a dedicated
+    /// `!pins:CHANNEL` window.
+    async fn pins_handler(&mut self) {
+        let mut ui = self.ui.lock().await;
 
-                // Define the window index.
-                //
-                // First check if a window has previously been created for the
-                // given address / channel combination. If so, return the
-                // index. Otherwise, add a new window and return the index.
-                let index = channel_window_index
-                    .unwrap_or_else(|| ui.add_window(address.clone(), channel.clone()));
+        let window = ui.get_active_window();
+        if window.is_virtual() {
+            ui.write_status(
+                Level::Error,
+                tr("pinned posts can only be listed from a channel window"),
+            );
+            ui.update();
+            return;
+        }
+        let (address, channel) = (window.address.to_owned(), window.channel.to_owned());
 
-                let ch = channel.clone();
+        // Copy the pinned lines from the channel window.
+        let pins = self
+            .pins
+            .get(&(address.clone(), channel.clone()))
+            .cloned()
+            .unwrap_or_default();
+        let lines = pins
+            .iter()
+            .map(|hash| {
+                let line = window
+                    .lines
+                    .iter()
+                    .find(|line| line.hash.as_ref() == Some(hash))
+                    .cloned();
+                (hash, line)
+            })
+            .collect::<Vec<_>>();
 
-                ui.set_active_index(index);
-                ui.update();
-                // The UI remains locked if not explicitly dropped here.
-                drop(ui);
+        let pins_channel = format!("!pins:{}", channel);
+        let index = ui
+            .get_window_index(&address, &pins_channel)
+            .unwrap_or_else(|| ui.add_window(address.clone(), pins_channel));
 
-                // Define the channel options.
-                let opts = ChannelOptions {
-                    channel: ch.clone(),
-                    time_start: time::two_weeks_ago()?,
-                    time_end: 0,
-                    limit: 4096,
-                };
+        let pins_window = &mut ui.windows[index];
+        pins_window.clear();
+        pins_window.update_topic(tr_args("pinned posts of #{}", &[&channel]));
+        for (n, (hash, line)) in lines.into_iter().enumerate() {
+            if let Some(line) = line {
+                pins_window.insert(
+                    line.timestamp,
+                    line.author,
+                    line.nickname,
+                    &format!("{}. {}", n + 1, line.text),
+                    line.hash,
+                );
+            } else {
+                pins_window.write(&tr_args(
+                    "{}. post {} is not available locally",
+                    &[&(n + 1), &hex::to(hash)],
+                ));
+            }
+        }
+        if pins.is_empty() {
+            pins_window.write(tr("{ no pinned posts for this channel }"));
+        }
 
-                let store = cable.store.clone();
-                let ui = self.ui.clone();
-                let mut ui = ui.lock().await;
+        ui.set_active_index(index);
+        ui.update();
+    }
 
-                // Open the channel and update the UI with stored and received
-                // text posts; only if this action has not been performed
-                // previously.
-                //
-                // The window index is used as a proxy for "channel has been
-                // initialised".
-                if channel_window_index.is_none() {
-                    ui.write_status(&format!("joined channel {}", channel));
-                    ui.update();
+    /// Handle the `/reply` command.
+    ///
+    /// Posts a reply to the Nth most recent post in the active window (where
+    /// 1 is the most recent). The reply references the hash of the replied-to
+    /// post using the `[re:HASH]` client convention.
+    ///
+    /// The given line of input is put back in the input line if the reply
+    /// awaits confirmation, rather than the reply in that form.
+    async fn reply_handler(&mut self, args: Vec<String>, line: &str) -> Result<(), Error> {
+        let n = args.get(1).and_then(|n| n.parse::<usize>().ok());
+        if n.is_none() || args.len() < 3 {
+            self.write_status(Level::Error, &usage("/reply N TEXT"))
+                .await;
+            return Ok(());
+        }
 
-                    let mut stored_posts_stream = cable.store.get_posts(&opts).await;
-                    while let Some(post_stream) = stored_posts_stream.next().await {
-                        if let Ok(post) = post_stream {
-                            let timestamp = post.header.timestamp;
-                            let public_key = post.header.public_key;
-                            let nickname = store
-                                .get_peer_name_and_hash(&public_key)
-                                .await
-                                .map(|(nick, _hash)| nick);
-
-                            if let PostBody::Text { channel, text } = post.body {
-                                if let Some(window) = ui.get_window(&address, &channel) {
-                                    window.insert(timestamp, Some(public_key), nickname, &text);
-                                    ui.update();
-                                }
-                            } else if let PostBody::Topic { channel, topic } = post.body {
-                                if let Some(window) = ui.get_window(&address, &channel) {
-                                    window.update_topic(topic);
-                                    ui.update();
-                                }
-                            }
-                        }
-                    }
-                    drop(stored_posts_stream);
+        let hash = self
+            .ui
+            .lock()
+            .await
+            .get_active_window()
+            .nth_recent_post(n.unwrap())
+            .and_then(|line| line.hash);
 
-                    // Create an abort handle and add it to the local map.
-                    //
-                    // This allows the `display_posts` task to be aborted
-                    // when the channel is left, thereby preventing double
-                    // posting to the UI if the channel is later rejoined.
-                    let (abort_handle, abort_registration) = AbortHandle::new_pair();
-                    self.abort_handles
-                        .lock()
-                        .await
-                        .insert(channel.to_owned(), abort_handle);
+        if let Some(hash) = hash {
+            let text = args[2..].join(" ");
+            self.post_from(&utils::format_reply(&hash, &text), line)
+                .await?;
+        } else {
+            self.write_status(
+                Level::Error,
+                &tr_args("no post found at position {}", &[&args[1]]),
+            )
+            .await;
+        }
 
-                    let store = cable.store.clone();
+        Ok(())
+    }
 
-                    let ui = self.ui.clone();
-                    let display_posts = async move {
-                        let mut stream = cable
-                            .open_channel(&opts)
+    /// Handle the `/session` commands.
+    ///
+    /// Saves the current session state to a file or restores a previously
+    /// saved session state from a file.
+    async fn session_handler(&mut self, args: Vec<String>) -> Result<(), Error> {
+        match (args.get(1).map(|x| x.as_str()), args.get(2)) {
+            (Some("save"), Some(path)) => {
+                let session = self.export_session().await;
+                match session.save(path) {
+                    Ok(()) => {
+                        self.write_status(Level::Info, &tr_args("saved session to {}", &[&path]))
                             .await
-                            // TODO: Can we handle this unwrap another way?
-                            .unwrap();
-
-                        while let Some(post_stream) = stream.next().await {
-                            if let Ok(post) = post_stream {
-                                let timestamp = post.header.timestamp;
-                                let public_key = post.header.public_key;
-                                let nickname = store
-                                    .get_peer_name_and_hash(&public_key)
-                                    .await
-                                    .map(|(nick, _hash)| nick);
-
-                                if let PostBody::Text { channel, text } = post.body {
-                                    let mut ui = ui.lock().await;
-                                    if let Some(window) = ui.get_window(&address, &channel) {
-                                        window.insert(timestamp, Some(public_key), nickname, &text);
-                                        ui.update();
-                                    }
-                                } else if let PostBody::Topic { channel, topic } = post.body {
-                                    let mut ui = ui.lock().await;
-                                    if let Some(window) = ui.get_window(&address, &channel) {
-                                        window.update_topic(topic);
-                                        ui.update();
-                                    }
-                                }
-                            }
-                        }
-                    };
-
-                    task::spawn(Abortable::new(display_posts, abort_registration));
+                    }
+                    Err(err) => {
+                        self.write_status(
+                            Level::Error,
+                            &tr_args("failed to save session to {}: {}", &[&path, &err]),
+                        )
+                        .await
+                    }
                 }
-            } else {
-                let mut ui = self.ui.lock().await;
-                ui.write_status("usage: /join CHANNEL");
-                ui.update();
             }
-        } else {
-            let mut ui = self.ui.lock().await;
-            ui.write_status(&format!(
-                "{}{}",
-                "cannot join channel with no active cabal set.",
-                " add a cabal with \"/cabal add\" first",
-            ));
-            ui.update();
+            (Some("load"), Some(path)) => match Session::load(path) {
+                Ok(session) => {
+                    self.import_session(session).await?;
+                    self.write_status(Level::Info, &tr_args("loaded session from {}", &[&path]))
+                        .await;
+                }
+                Err(err) => {
+                    self.write_status(
+                        Level::Error,
+                        &tr_args("failed to load session from {}: {}", &[&path, &err]),
+                    )
+                    .await
+                }
+            },
+            _ => {
+                self.write_status(Level::Error, &usage("/session save|load FILE"))
+                    .await;
+            }
         }
 
         Ok(())
     }
 
-    /// Handle the `/leave` command.
-    ///
-    /// Cancels any active outbound channel time range requests for the
-    /// given channel and publishes a `post/leave`.
-    async fn leave_handler(&mut self, args: Vec<String>) -> Result<(), Error> {
-        if let Some((address, mut cable)) = self.get_active_cable().await {
-            if let Some(channel) = args.get(1) {
-                if let Some(channels) = cable.store.get_channels().await {
-                    // Avoid closing and leaving a channel that isn't known to the
-                    // local peer.
-                    if channels.contains(channel) {
-                        // Cancel any active outbound channel time range requests
-                        // for this channel.
-                        cable.close_channel(channel).await?;
+    /// Capture the known cabals, aliases, joined channels and connections as
+    /// a session document.
+    async fn export_session(&self) -> Session {
+        let ui = self.ui.lock().await;
 
-                        // Check if the local peer is a member of this channel.
-                        // If so, publish a `post/leave` post.
-                        if let Some((public_key, _private_key)) = cable.store.get_keypair().await {
-                            if cable.store.is_channel_member(channel, &public_key).await {
-                                // TODO: Match on validation error and display to user.
-                                cable.post_leave(channel).await?;
-                            }
+        let cabals = self
+            .client
+            .cabals()
+            .map(|addr| CabalEntry {
+                address: hex::to(addr),
+                alias: self.aliases.get(addr).cloned(),
+                backfill: self.backfill.get(addr).map(|backfill| backfill.to_string()),
+                channels: ui
+                    .windows
+                    .iter()
+                    .filter(|window| &window.address == addr && !window.is_virtual())
+                    .map(|window| window.channel.to_owned())
+                    .collect(),
+                pins: self
+                    .pins
+                    .iter()
+                    .filter(|((pin_addr, _channel), _hashes)| pin_addr == addr)
+                    .map(|((_addr, channel), hashes)| {
+                        (
+                            channel.to_owned(),
+                            hashes.iter().map(|hash| hex::to(hash)).collect(),
+                        )
+                    })
+                    .collect(),
+                hidden: self
+                    .hidden
+                    .get(addr)
+                    .map(|hidden| hidden.iter().cloned().collect())
+                    .unwrap_or_default(),
+                listen: self
+                    .connections
+                    .iter()
+                    .filter_map(|connection| match connection {
+                        Connection::Listening(cabal, tcp_addr) if cabal == addr => {
+                            Some(tcp_addr.to_owned())
+                        }
+                        _ => None,
+                    })
+                    .collect(),
+                // Commands are left out, so that loading a shared session
+                // does not run them.
+                connect: self
+                    .connections
+                    .iter()
+                    .filter_map(|connection| match connection {
+                        Connection::Connected(cabal, tcp_addr)
+                            if cabal == addr && exec::command(tcp_addr).is_none() =>
+                        {
+                            Some(tcp_addr.to_owned())
                         }
+                        _ => None,
+                    })
+                    .collect(),
+            })
+            .collect();
 
-                        self.close_channel_sender.send(channel.to_owned()).await?;
+        Session {
+            active: ui.get_active_address().map(|addr| hex::to(addr)),
+            blocked: ui.blocked.iter().map(|key| hex::to(key)).collect(),
+            ignored: ui
+                .ignored
+                .iter()
+                .map(|(key, types)| {
+                    let types = types.iter().map(|kind| kind.as_str().to_string());
+                    (hex::to(key), types.collect())
+                })
+                .collect(),
+            settings: ui.settings.clone(),
+            cabals,
+            windows: ui
+                .windows
+                .iter()
+                .filter(|window| !window.is_virtual())
+                .map(|window| WindowEntry {
+                    address: hex::to(&window.address),
+                    channel: window.channel.to_owned(),
+                })
+                .collect(),
+            ..Session::default()
+        }
+    }
 
-                        let mut ui = self.ui.lock().await;
-                        // Remove the window associated with the given channel.
-                        if let Some(index) = ui.get_window_index(&address, channel) {
-                            ui.remove_window(index)
-                        }
-                        // Return to the home / status window.
-                        ui.set_active_index(0);
-                        ui.write_status(&format!("left channel {}", channel));
-                        ui.update();
-                    }
-                } else {
-                    let mut ui = self.ui.lock().await;
-                    ui.write_status(&format!(
-                        "not currently a member of channel {}; no action taken",
-                        channel
-                    ));
-                    ui.update();
+    /// Restore the cabals, aliases, joined channels and connections described
+    /// by the given session document.
+    ///
+    /// Connections are bound to the active cabal of the restored session.
+    async fn import_session(&mut self, session: Session) -> Result<(), Error> {
+        {
+            let mut ui = self.ui.lock().await;
+            ui.settings = session.settings;
+            ui.blocked.extend(
+                session
+                    .blocked
+                    .iter()
+                    .filter_map(|key| hex::from(key))
+                    .filter_map(|key| PublicKey::try_from(key).ok()),
+            );
+            for (key, types) in &session.ignored {
+                if let Some(key) = hex::from(key).and_then(|key| PublicKey::try_from(key).ok()) {
+                    ui.ignored
+                        .entry(key)
+                        .or_default()
+                        .extend(types.iter().filter_map(|kind| Ignore::parse(kind)));
                 }
+            }
+        }
+
+        for entry in session.cabals {
+            let addr = if let Some(addr) = hex::from(&entry.address) {
+                addr
             } else {
+                self.write_status(
+                    Level::Error,
+                    &tr_args("invalid cabal address: {}", &[&entry.address]),
+                )
+                .await;
+                continue;
+            };
+
+            if !self.client.has_cabal(&addr) {
+                self.add_cable(&addr);
+                self.write_status(Level::Info, &tr_args("added cabal: {}", &[&entry.address]))
+                    .await;
+            }
+            if let Some(alias) = entry.alias {
                 let mut ui = self.ui.lock().await;
-                ui.write_status("usage: /leave CHANNEL");
-                ui.update();
+                ui.aliases.insert(addr.clone(), alias.clone());
+                drop(ui);
+                self.aliases.insert(addr.clone(), alias);
+            }
+            match entry.backfill.as_deref().map(time::Backfill::parse) {
+                Some(Some(backfill)) => {
+                    self.backfill.insert(addr.clone(), backfill);
+                }
+                Some(None) => {
+                    self.write_status(
+                        Level::Error,
+                        &tr_args(
+                            "invalid backfill for cabal {}: {}",
+                            &[&entry.address, &entry.backfill.unwrap_or_default()],
+                        ),
+                    )
+                    .await;
+                }
+                None => {}
+            }
+            for (channel, hashes) in entry.pins {
+                let pins = self.pins.entry((addr.clone(), channel)).or_default();
+                for hash in hashes
+                    .iter()
+                    .filter_map(|hash| hex::from(hash))
+                    .filter_map(|hash| Hash::try_from(hash).ok())
+                {
+                    if !pins.contains(&hash) {
+                        pins.push(hash);
+                    }
+                }
+            }
+            if !entry.hidden.is_empty() {
+                self.hidden
+                    .entry(addr.clone())
+                    .or_default()
+                    .extend(entry.hidden);
+            }
+
+            // Channels are joined and connections are bound via the active
+            // cabal.
+            self.set_active_address(&addr).await;
+            for channel in entry.channels {
+                self.join_handler(vec!["/join".to_string(), channel], false)
+                    .await?;
+            }
+            self.restore_connections(&addr, entry.listen, entry.connect)
+                .await;
+        }
+
+        if let Some(addr) = session.active.as_deref().and_then(hex::from) {
+            if self.client.has_cabal(&addr) {
+                self.set_active_address(&addr).await;
             }
-        } else {
-            let mut ui = self.ui.lock().await;
-            ui.write_status(&format!(
-                "{}{}",
-                "cannot leave channel with no active cabal set.",
-                " add a cabal with \"/cabal add\" first",
-            ));
-            ui.update();
         }
 
+        let order: Vec<(Addr, Channel)> = session
+            .windows
+            .into_iter()
+            .filter_map(|entry| Some((hex::from(&entry.address)?, entry.channel)))
+            .collect();
+        let mut ui = self.ui.lock().await;
+        ui.arrange_windows(&order);
+        ui.update();
+
         Ok(())
     }
 
-    /// Handle the `/listen` command.
+    /// Start the given listeners and connections for the active cabal (with
+    /// the given address), skipping any which already exist.
     ///
-    /// Deploys a TCP server on the given host:port, listens for incoming
-    /// connections and passes any resulting streams to the cable manager.
-    async fn listen_handler(&mut self, args: Vec<String>) {
-        // Retrieve the active cable address (aka. key).
-        if self.get_active_address().await.is_none() {
-            self.write_status(r#"no active cabal to bind this connection. use "/cabal add" first"#)
+    /// `exec:` connections are never started, since the session document
+    /// may come from someone else; they must be made with `/connect`.
+    async fn restore_connections(
+        &mut self,
+        addr: &Addr,
+        listen: Vec<String>,
+        connect: Vec<String>,
+    ) {
+        let listen = listen
+            .into_iter()
+            .filter(|tcp_addr| {
+                !self
+                    .connections
+                    .contains(&Connection::Listening(addr.clone(), tcp_addr.clone()))
+            })
+            .collect();
+        for tcp_addr in collapse_wildcards(listen) {
+            self.listen_handler(vec!["/listen".to_string(), tcp_addr])
                 .await;
-        } else if let Some(mut tcp_addr) = args.get(1).cloned() {
-            // Format the TCP address if a host was not supplied.
-            if !tcp_addr.contains(':') {
-                tcp_addr = format!("0.0.0.0:{}", tcp_addr);
+        }
+        for tcp_addr in connect {
+            if exec::command(&tcp_addr).is_some() {
+                self.write_status(
+                    Level::Error,
+                    &tr_args("skipped connection from session: {}", &[&tcp_addr]),
+                )
+                .await;
+                continue;
             }
+            if !self
+                .connections
+                .contains(&Connection::Connected(addr.clone(), tcp_addr.clone()))
+            {
+                self.connect_handler(vec!["/connect".to_string(), tcp_addr])
+                    .await;
+            }
+        }
+    }
 
-            // Retrieve the active cable manager.
-            let (_, cable) = self.get_active_cable().await.unwrap();
-
-            // Register the listener.
-            self.connections
-                .insert(Connection::Listening(tcp_addr.clone()));
-
-            let ui = self.ui.clone();
-
-            task::spawn(async move {
-                let listener = net::TcpListener::bind(tcp_addr.clone()).await.unwrap();
+    /// Handle the `/sync status` command.
+    ///
+    /// Prints, for each joined channel, the time range of the posts held
+    /// locally, the open channel time range request and the number of posts
+    /// which arrived within the last sync interval.
+    async fn sync_handler(&mut self, args: Vec<String>) {
+        if args.get(1).map(|arg| arg.as_str()) != Some("status") {
+            self.write_status(Level::Error, &usage("/sync status"))
+                .await;
+            return;
+        }
 
-                // Update the UI.
-                let mut ui = ui.lock().await;
-                ui.write_status(&format!("listening on {}", tcp_addr));
-                ui.update();
-                drop(ui);
+        let mut ui = self.ui.lock().await;
+        let mut lines = Vec::new();
+        for window in ui.windows.iter().filter(|window| !window.is_virtual()) {
+            let posts = match window.post_time_range() {
+                Some((oldest, newest)) => tr_args(
+                    "posts from {} to {}",
+                    &[&time::format_date(oldest), &time::format_date(newest)],
+                ),
+                None => tr("no posts").to_string(),
+            };
+            let request = match window.request_start {
+                Some(start) => tr_args("live request since {}", &[&time::format_date(start)]),
+                None => tr("no open request").to_string(),
+            };
+            lines.push(tr_args(
+                "{} ({}): {}; {}; {} arrived in the last {} minutes",
+                &[
+                    &window.channel,
+                    &self.cabal_name(&window.address),
+                    &posts,
+                    &request,
+                    &window.recent_arrivals(),
+                    &(SYNC_INTERVAL.as_secs() / 60),
+                ],
+            ));
+        }
+        if lines.is_empty() {
+            lines.push(tr("{ no joined channels }").to_string());
+        }
+        for line in lines {
+            ui.write_status(Level::Info, &line);
+        }
+        ui.update();
+    }
 
-                debug!("Listening for incoming TCP connections...");
-
-                // Listen for incoming TCP connections and spawn a
-                // cable listener for each stream.
-                let mut incoming = listener.incoming();
-                while let Some(stream) = incoming.next().await {
-                    debug!("Received an incoming TCP connection");
-                    if let Ok(stream) = stream {
-                        let cable = cable.clone();
-                        task::spawn(async move {
-                            if let Err(err) = cable.listen(stream).await {
-                                error!("Cable stream listener error: {}", err);
-                            }
-                        });
+    /// Handle the `/set` command.
+    ///
+    /// Lists all settings or updates the value of a single setting.
+    async fn set_handler(&mut self, args: Vec<String>) {
+        let mut ui = self.ui.lock().await;
+        match (args.get(1), args.get(2)) {
+            (Some(key), Some(value)) => match ui.settings.set(key, value) {
+                Ok(()) => {
+                    if key == "notes" {
+                        ui.invalidate_windows();
                     }
+                    ui.write_status(Level::Info, &tr_args("set {} to {}", &[&key, &value]))
                 }
-            });
-        } else {
-            // Print usage example for the listen command.
-            let mut ui = self.ui.lock().await;
-            ui.write_status("usage: /listen (ADDR:)PORT");
-            ui.update();
+                Err(err) => ui.write_status(Level::Error, &err),
+            },
+            (Some(_key), None) => ui.write_status(Level::Error, &usage("/set KEY VALUE")),
+            _ => {
+                for (key, value) in ui.settings.list() {
+                    ui.write_status(Level::Info, &format!("  {} = {}", key, value));
+                }
+            }
         }
+        ui.update();
     }
 
-    /// Handle the `/members` command.
+    /// Handle the `/split` and `/unsplit` commands.
     ///
-    /// Prints a list of known members of a channel. If this handler is invoked
-    /// from an active channel window, the members of that channel will be
-    /// printed. Otherwise, the handler can be invoked with a specific channel
-    /// name as an argument; this is useful for printing channel members when
-    /// the status window is active.
-    async fn members_handler(&mut self, args: Vec<String>) {
-        if let Some((_address, cable)) = self.get_active_cable().await {
-            if let Some(channel) = args.get(1) {
-                let mut ui = self.ui.lock().await;
+    /// `/split A B` makes window A active and displays window B below it;
+    /// `/split B` displays window B below the current active window.
+    async fn split_handler(&mut self, args: Vec<String>) {
+        let mut ui = self.ui.lock().await;
+        if args[0] == "/unsplit" {
+            ui.set_split(None);
+            ui.update();
+            return;
+        }
 
-                if let Some(members) = cable.store.get_channel_members(channel).await {
-                    for member in members {
-                        // Retrieve and print the nick for each member's
-                        // public key.
-                        if let Some((name, _hash)) =
-                            cable.store.get_peer_name_and_hash(&member).await
-                        {
-                            ui.write_status(&format!("  {}", name));
-                        } else {
-                            // Fall back to the public key (formatted as a
-                            // hex string) if no nick is known.
-                            ui.write_status(&format!("  {}", hex::to(&member)));
-                        }
-                    }
-                } else {
-                    ui.write_status(
-                        "{ no known channel members for the active cabal and channel }",
-                    );
-                }
+        let indexes: Result<Vec<usize>, _> = args[1..].iter().map(|arg| arg.parse()).collect();
+        let (active, watched) = match indexes.as_deref() {
+            Ok([watched]) => (ui.get_active_index(), *watched),
+            Ok([active, watched]) => (*active, *watched),
+            _ => {
+                ui.write_status(Level::Error, &usage("/split [INDEX] INDEX"));
                 ui.update();
-            } else {
-                // No args were passed to the `/members` handler. Attempt to
-                // determine the channel for the active window and print the
-                // members.
-                let mut ui = self.ui.lock().await;
-                let index = ui.get_active_index();
-                // Don't attempt to retrieve and print channel members if the
-                // status window is active.
-                if index != 0 {
-                    let window = ui.get_active_window();
-                    if let Some(members) = cable.store.get_channel_members(&window.channel).await {
-                        for member in members {
-                            // Retrieve and print the nick for each member's
-                            // public key.
-                            if let Some((name, _hash)) =
-                                cable.store.get_peer_name_and_hash(&member).await
-                            {
-                                ui.write_status(&format!("  {}", name));
-                            } else {
-                                // Fall back to the public key (formatted as a
-                                // hex string) if no nick is known.
-                                ui.write_status(&format!("  {}", hex::to(&member)));
-                            }
-                        }
-                    } else {
-                        ui.write_status(
-                            "{ no known channel members for the active cabal and channel }",
-                        );
-                    }
-                    ui.update();
-                }
-            };
+                return;
+            }
+        };
+
+        if active >= ui.windows.len() || watched >= ui.windows.len() {
+            ui.write_status(Level::Error, tr("window index out of range"));
+        } else if active == watched {
+            ui.write_status(Level::Error, tr("cannot split a window with itself"));
         } else {
-            let mut ui = self.ui.lock().await;
-            ui.write_status(&format!(
-                "{}{}",
-                "cannot list channel members with no active cabal set.",
-                " add a cabal with \"/cabal add\" first",
-            ));
-            ui.update();
+            ui.set_active_index(active);
+            ui.set_split(Some(watched));
         }
+        ui.update();
     }
 
-    /// Handle the `/nick` command.
+    /// Handle the `/clear` command.
     ///
-    /// Set the nickname for the local peer.
-    async fn nick_handler(&mut self, args: Vec<String>) -> Result<(), Error> {
-        if let Some((_address, mut cable)) = self.get_active_cable().await {
-            if let Some(nick) = args.get(1) {
-                let mut ui = self.ui.lock().await;
-                let _hash = cable.post_info_name(nick).await?;
-                ui.write_status(&format!("nickname set to {:?}", nick));
-                ui.update();
+    /// Removes the lines of the active window. The posts they displayed
+    /// remain in the store, but are not displayed again in the window.
+    async fn clear_handler(&mut self) {
+        let mut ui = self.ui.lock().await;
+        ui.get_active_window().clear_lines();
+        ui.update();
+    }
+
+    /// Handle the `/toggle` command.
+    ///
+    /// Toggles the display of posts from blocked peers in the active window.
+    /// Revealed posts are rendered dimmed.
+    async fn toggle_handler(&mut self, args: Vec<String>) {
+        let mut ui = self.ui.lock().await;
+        if let Some("hidden") = args.get(1).map(|arg| arg.as_str()) {
+            let window = ui.get_active_window();
+            window.show_hidden = !window.show_hidden;
+            let msg = if window.show_hidden {
+                tr("revealing hidden posts in the active window")
             } else {
-                let mut ui = self.ui.lock().await;
-                ui.write_status("usage: /nick NAME");
-                ui.update();
-            }
+                tr("hiding posts from blocked peers in the active window")
+            };
+            ui.write_status(Level::Info, msg);
         } else {
-            let mut ui = self.ui.lock().await;
-            ui.write_status(&format!(
-                "{}{}",
-                "cannot assign nickname with no active cabal set.",
-                " add a cabal with \"/cabal add\" first",
-            ));
-            ui.update();
+            ui.write_status(Level::Error, &usage("/toggle hidden"));
         }
-
-        Ok(())
+        ui.update();
     }
 
     /// Handle the `/topic` command.
     ///
     /// Sets the topic of the active channel.
     async fn topic_handler(&mut self, args: Vec<String>) -> Result<(), Error> {
+        if args.len() == 2 && args[1] == "history" {
+            self.topic_history_handler().await;
+            return Ok(());
+        }
+
         if let Some((_address, mut cable)) = self.get_active_cable().await {
             if args.get(1).is_some() {
                 // Get all arguments that come after the `/topic` argument.
                 let topic: String = args[1..].join(" ");
                 let mut ui = self.ui.lock().await;
                 let active_channel = ui.get_active_window().channel.to_owned();
-                if active_channel != "!status" {
+                if !ui.get_active_window().is_virtual() {
                     cable.post_topic(&active_channel, &topic).await?;
-                    ui.write_status(&format!(
-                        "topic set to {:?} for channel {:?}",
-                        topic, active_channel
-                    ));
+                    ui.write_status(
+                        Level::Info,
+                        &tr_args(
+                            "topic set to {} for channel {}",
+                            &[&format!("{:?}", topic), &format!("{:?}", active_channel)],
+                        ),
+                    );
                     ui.update();
                 } else {
-                    ui.write_status("topic cannot be set for !status window");
+                    ui.write_status(
+                        Level::Error,
+                        &tr_args("topic cannot be set for {} window", &[&active_channel]),
+                    );
                     ui.update();
                 }
             } else {
                 let mut ui = self.ui.lock().await;
-                ui.write_status("usage: /topic TOPIC");
+                ui.write_status(Level::Error, &usage("/topic TOPIC"));
                 ui.update();
             }
         }
@@ -733,6 +4195,83 @@ where
         Ok(())
     }
 
+    /// Handle the `/topic history` command.
+    ///
+    /// Lists every topic set for the active channel, oldest first, with the
+    /// time at which it was set and the name of the peer who set it. Topics
+    /// are read from the topic posts held by the store.
+    async fn topic_history_handler(&mut self) {
+        let (address, channel, is_virtual) = {
+            let mut ui = self.ui.lock().await;
+            let window = ui.get_active_window();
+            (
+                window.address.clone(),
+                window.channel.clone(),
+                window.is_virtual(),
+            )
+        };
+        if is_virtual {
+            self.write_status(
+                Level::Info,
+                &tr_args("{} window has no topic history", &[&channel]),
+            )
+            .await;
+            return;
+        }
+        let Some(cable) = self.client.cable(&address) else {
+            return;
+        };
+
+        // The entire history of the channel is searched (a limit of zero
+        // means no limit).
+        let opts = ChannelOptions {
+            channel: channel.clone(),
+            time_start: 0,
+            time_end: 0,
+            limit: 0,
+        };
+        let mut topics = Vec::new();
+        {
+            let mut store = cable.store.clone();
+            let mut posts = store.get_posts(&opts).await;
+            while let Some(Ok(post)) = posts.next().await {
+                if let PostBody::Topic { topic, .. } = post.body {
+                    topics.push((post.header.timestamp, post.header.public_key, topic));
+                }
+            }
+        }
+        topics.sort_by_key(|(timestamp, _public_key, _topic)| *timestamp);
+
+        let mut lines = Vec::with_capacity(topics.len());
+        for (timestamp, public_key, topic) in topics {
+            let name = self
+                .nicks
+                .get(&address, &cable.store, &public_key)
+                .await
+                .unwrap_or_else(|| hex::to(&public_key[..4]));
+            lines.push(format!(
+                "  [{}] {}: {:?}",
+                time::format_iso(timestamp),
+                name,
+                topic
+            ));
+        }
+
+        let mut ui = self.ui.lock().await;
+        if lines.is_empty() {
+            ui.write_status(
+                Level::Info,
+                &tr_args("{ no topics found for #{} }", &[&channel]),
+            );
+        } else {
+            ui.write_status(Level::Info, &tr_args("topic history of #{}:", &[&channel]));
+            for line in lines {
+                ui.write_status(Level::Info, &line);
+            }
+        }
+        ui.update();
+    }
+
     /// Handle the `/whoami` command.
     ///
     /// Prints the hex-encoded public key of the local peer.
@@ -740,165 +4279,1397 @@ where
         if let Some((_address, cable)) = self.get_active_cable().await {
             if let Some((public_key, _private_key)) = cable.store.get_keypair().await {
                 let mut ui = self.ui.lock().await;
-                ui.write_status(&format!("  {}", hex::to(&public_key)));
+                ui.write_status(Level::Info, &format!("  {}", hex::to(&public_key)));
                 ui.update();
             }
         } else {
             let mut ui = self.ui.lock().await;
-            ui.write_status(&format!(
-                "{}{}",
-                "cannot list the local public key with no active cabal set.",
-                " add a cabal with \"/cabal add\" first",
-            ));
+            ui.write_status(
+                Level::Error,
+                tr("cannot list the local public key with no active cabal set. add a cabal with \"/cabal add\" first"),
+            );
             ui.update();
         }
     }
 
+    /// Handle the `/publish` command.
+    ///
+    /// Renders the stored text posts of the given channel of the active
+    /// cabal as static HTML pages in the given directory. Posts from blocked
+    /// peers are omitted.
+    async fn publish_handler(&mut self, args: Vec<String>) {
+        let (Some(channel), Some(dir)) = (args.get(1), args.get(2)) else {
+            self.write_status(Level::Error, &usage("/publish CHANNEL DIR"))
+                .await;
+            return;
+        };
+        let channel = channel.strip_prefix('#').unwrap_or(channel).to_owned();
+        let Some((address, cable)) = self.get_active_cable().await else {
+            self.write_status(
+                Level::Error,
+                tr("no active cabal to publish from. add one with \"/cabal add\" first"),
+            )
+            .await;
+            return;
+        };
+
+        let (blocked, topic) = {
+            let mut ui = self.ui.lock().await;
+            let topic = ui
+                .get_window(&address, &channel)
+                .map(|window| window.topic.to_owned())
+                .unwrap_or_default();
+            (ui.blocked.clone(), topic)
+        };
+
+        let opts = ChannelOptions {
+            channel: channel.clone(),
+            time_start: 0,
+            time_end: 0,
+            limit: 0,
+        };
+        let mut posts = Vec::new();
+        {
+            let mut store = cable.store.clone();
+            let mut stream = store.get_posts(&opts).await;
+            while let Some(Ok(post)) = stream.next().await {
+                if blocked.contains(&post.header.public_key) {
+                    continue;
+                }
+                if let PostBody::Text { text, .. } = post.body {
+                    posts.push((post.header.timestamp, post.header.public_key, text));
+                }
+            }
+        }
+        posts.sort_by_key(|(timestamp, _public_key, _text)| *timestamp);
+
+        let mut entries = Vec::with_capacity(posts.len());
+        for (timestamp, author, text) in posts {
+            let name = self
+                .nicks
+                .get(&address, &cable.store, &author)
+                .await
+                .unwrap_or_else(|| hex::to(&author[..4]));
+            entries.push(Entry {
+                timestamp,
+                author,
+                name,
+                text,
+            });
+        }
+
+        match publish::publish(Path::new(dir), &channel, &topic, &entries) {
+            Ok(pages) => {
+                self.write_status(
+                    Level::Info,
+                    &tr_args(
+                        "published {} posts of #{} ({} days) to {}",
+                        &[&entries.len(), &channel, &pages, &dir],
+                    ),
+                )
+                .await
+            }
+            Err(err) => {
+                self.write_status(
+                    Level::Error,
+                    &tr_args("failed to publish #{} to {}: {}", &[&channel, &dir, &err]),
+                )
+                .await
+            }
+        }
+    }
+
+    /// Handle the `/schedule` command.
+    ///
+    /// Queues a post to the active channel, to be sent at the given time if
+    /// cabin is still running. `/schedule list` lists the queued posts and
+    /// `/schedule cancel N` removes one from the queue.
+    async fn schedule_handler(&mut self, args: Vec<String>) {
+        match args.get(1).map(String::as_str) {
+            Some("list") if args.len() == 2 => {
+                let scheduled = self.scheduled.lock().await;
+                let mut ui = self.ui.lock().await;
+                if scheduled.is_empty() {
+                    ui.write_status(Level::Info, tr("{ no scheduled posts }"));
+                }
+                for (id, post) in scheduled.iter() {
+                    ui.write_status(
+                        Level::Info,
+                        &format!(
+                            "  {}. [{}] #{}: {}",
+                            id,
+                            time::format_date(post.due),
+                            post.channel,
+                            utils::snippet(&post.text, 48)
+                        ),
+                    );
+                }
+                ui.update();
+            }
+            Some("cancel") => {
+                let Some(id) = args.get(2).and_then(|id| id.parse::<usize>().ok()) else {
+                    self.write_status(Level::Error, &usage("/schedule cancel N"))
+                        .await;
+                    return;
+                };
+                let msg = match self.scheduled.lock().await.remove(&id) {
+                    Some(post) => {
+                        post.abort_handle.abort();
+                        tr_args("cancelled scheduled post {}", &[&id])
+                    }
+                    None => tr_args("no scheduled post numbered {}", &[&id]),
+                };
+                self.write_status(Level::Info, &msg).await;
+            }
+            Some(when) if args.len() > 2 => {
+                let when = when.to_owned();
+                self.schedule_post(&when, args[2..].join(" ")).await;
+            }
+            _ => {
+                self.write_status(
+                    Level::Error,
+                    &usage("/schedule \"in 2h\"|HH:MM|\"YYYY-MM-DD HH:MM\" TEXT"),
+                )
+                .await;
+                self.write_status(Level::Error, &usage("/schedule list|cancel N"))
+                    .await;
+            }
+        }
+    }
+
+    /// Queue the given text to be posted to the active channel at the given
+    /// time.
+    async fn schedule_post(&mut self, when: &str, text: String) {
+        let Some(due) = time::parse_due(when, self.clock.as_ref()) else {
+            self.write_status(
+                Level::Error,
+                &tr_args(
+                    "invalid time {}; expected e.g. \"in 2h\", 18:30 or \"2024-05-01 09:00\"",
+                    &[&format!("{:?}", when)],
+                ),
+            )
+            .await;
+            return;
+        };
+        if due <= self.clock.now().unwrap_or_default() {
+            self.write_status(Level::Error, &tr_args("{} has already passed", &[&when]))
+                .await;
+            return;
+        }
+        if text.len() > MAX_TEXT_LEN {
+            self.write_status(
+                Level::Error,
+                &tr_args("scheduled posts are limited to {} bytes", &[&MAX_TEXT_LEN]),
+            )
+            .await;
+            return;
+        }
+
+        let (address, channel, is_virtual) = {
+            let mut ui = self.ui.lock().await;
+            let window = ui.get_active_window();
+            (
+                window.address.clone(),
+                window.channel.clone(),
+                window.is_virtual(),
+            )
+        };
+        let Some(mut cable) = self.client.cable(&address).cloned().filter(|_| !is_virtual) else {
+            self.write_status(
+                Level::Error,
+                tr("posts can only be scheduled from a channel window"),
+            )
+            .await;
+            return;
+        };
+
+        let id = self.next_scheduled;
+        self.next_scheduled += 1;
+
+        let (abort_handle, abort_registration) = AbortHandle::new_pair();
+        self.scheduled.lock().await.insert(
+            id,
+            ScheduledPost {
+                due,
+                channel: channel.clone(),
+                text: text.clone(),
+                abort_handle,
+            },
+        );
+
+        let msg = tr_args(
+            "scheduled post {} to #{} for {}",
+            &[&id, &channel, &time::format_date(due)],
+        );
+        let scheduled = self.scheduled.clone();
+        let failed = self.failed.clone();
+        let ui = self.ui.clone();
+        let clock = self.clock.clone();
+        let send = async move {
+            // The clock is checked periodically rather than sleeping for the
+            // entire delay, since monotonic time does not advance while the
+            // system is suspended.
+            loop {
+                let now = clock.now().unwrap_or(due);
+                if now >= due {
+                    break;
+                }
+                task::sleep(Duration::from_millis(due - now).min(SCHEDULE_CHECK_INTERVAL)).await;
+            }
+            scheduled.lock().await.remove(&id);
+
+            // A scheduled post which fails to send is kept to be resent by
+            // `/retry`, as any other post.
+            match client::post_text(&mut cable, &channel, &text).await {
+                Ok(_hash) => {
+                    let mut ui = ui.lock().await;
+                    ui.write_status(
+                        Level::Info,
+                        &tr_args("sent scheduled post {} to #{}", &[&id, &channel]),
+                    );
+                    ui.update();
+                }
+                Err(err) => keep_failed(&failed, &ui, &address, &channel, &text, &err).await,
+            }
+        };
+        task::spawn(Abortable::new(send, abort_registration));
+
+        self.write_status(Level::Info, &msg).await;
+    }
+
+    /// Handle the `/search` command.
+    ///
+    /// Lists the most recent text posts of the active cabal containing every
+    /// given word, using the full-text index.
+    async fn search_handler(&mut self, args: Vec<String>) {
+        if args.len() < 2 {
+            self.write_status(Level::Error, &usage("/search WORD..."))
+                .await;
+            return;
+        }
+        let Some((address, cable)) = self.get_active_cable().await else {
+            self.write_status(
+                Level::Error,
+                tr("no active cabal to search. add one with \"/cabal add\" first"),
+            )
+            .await;
+            return;
+        };
+
+        let query = args[1..].join(" ");
+        let documents = self.index.search(&address, &query, SEARCH_RESULTS).await;
+
+        let mut lines = Vec::with_capacity(documents.len());
+        for document in documents.iter() {
+            let name = self
+                .nicks
+                .get(&address, &cable.store, &document.author)
+                .await
+                .unwrap_or_else(|| hex::to(&document.author[..4]));
+            lines.push(format!(
+                "  [{}] #{} <{}> {}",
+                time::format_date(document.timestamp),
+                document.channel,
+                name,
+                document.text
+            ));
+        }
+
+        let mut ui = self.ui.lock().await;
+        if lines.is_empty() {
+            ui.write_status(
+                Level::Info,
+                &tr_args("{ no posts found matching {} }", &[&format!("{:?}", query)]),
+            );
+        } else {
+            ui.write_status(
+                Level::Info,
+                &tr_args(
+                    "{} most recent posts matching {}:",
+                    &[&lines.len(), &format!("{:?}", query)],
+                ),
+            );
+            for line in lines {
+                ui.write_status(Level::Info, &line);
+            }
+        }
+        ui.update();
+    }
+
+    /// Handle the `/store` command.
+    ///
+    /// `/store reindex` rebuilds the full-text index of the active cabal from
+    /// every text post held by the store.
+    async fn store_handler(&mut self, args: Vec<String>) {
+        if args.get(1).map(String::as_str) != Some("reindex") {
+            self.write_status(Level::Error, &usage("/store reindex"))
+                .await;
+            return;
+        }
+        let Some((address, cable)) = self.get_active_cable().await else {
+            self.write_status(
+                Level::Error,
+                tr("no active cabal to reindex. add one with \"/cabal add\" first"),
+            )
+            .await;
+            return;
+        };
+
+        self.index.clear(&address).await;
+        let mut store = cable.store.clone();
+        for channel in cable.store.get_channels().await.unwrap_or_default() {
+            let opts = ChannelOptions {
+                channel,
+                time_start: 0,
+                time_end: 0,
+                limit: 0,
+            };
+            let mut posts = store.get_posts(&opts).await;
+            while let Some(Ok(post)) = posts.next().await {
+                let Ok(hash) = post.hash() else {
+                    continue;
+                };
+                if let PostBody::Text { channel, text } = post.body {
+                    let document = Document {
+                        channel,
+                        timestamp: post.header.timestamp,
+                        author: post.header.public_key,
+                        text,
+                    };
+                    self.index.insert(&address, hash, document).await;
+                }
+            }
+        }
+
+        let count = self.index.len(&address).await;
+        self.write_status(Level::Info, &tr_args("indexed {} posts", &[&count]))
+            .await;
+    }
+
+    /// Handle the `/seen` command.
+    ///
+    /// Prints when the given peer of the active cabal last posted, and in
+    /// which channel.
+    async fn seen_handler(&mut self, args: Vec<String>) {
+        let Some(peer) = args.get(1) else {
+            self.write_status(Level::Error, &usage("/seen PEER")).await;
+            return;
+        };
+        let public_key = match self.resolve_peer(peer).await {
+            Ok(public_key) => public_key,
+            Err(err) => {
+                self.write_status(Level::Error, &err).await;
+                return;
+            }
+        };
+
+        let mut ui = self.ui.lock().await;
+        let seen = ui
+            .active_address
+            .as_ref()
+            .and_then(|address| ui.last_seen(address, &public_key))
+            .cloned();
+        match seen {
+            Some((timestamp, channel)) => ui.write_status(
+                Level::Info,
+                &tr_args(
+                    "{} was last seen {} ({}) in #{}",
+                    &[
+                        &peer,
+                        &time::format_ago(timestamp, self.clock.as_ref()),
+                        &time::format_date(timestamp),
+                        &channel,
+                    ],
+                ),
+            ),
+            None => ui.write_status(
+                Level::Info,
+                &tr_args("{} has not been seen in any joined channel", &[&peer]),
+            ),
+        }
+        ui.update();
+    }
+
+    /// Handle the `/whois` command.
+    ///
+    /// Prints the nickname, public key, channel memberships, last post time
+    /// and block status of the given peer.
+    async fn whois_handler(&mut self, args: Vec<String>) {
+        let peer = match args.get(1) {
+            Some(peer) => peer,
+            None => {
+                self.write_status(Level::Error, &usage("/whois PEER")).await;
+                return;
+            }
+        };
+
+        let public_key = match self.resolve_peer(peer).await {
+            Ok(public_key) => public_key,
+            Err(err) => {
+                self.write_status(Level::Error, &err).await;
+                return;
+            }
+        };
+
+        let mut lines = Vec::new();
+        if let Some((address, cable)) = self.get_active_cable().await {
+            let nick = self.nicks.get(&address, &cable.store, &public_key).await;
+            lines.push(tr_args(
+                "  nick: {}",
+                &[&nick.as_deref().unwrap_or(tr("{ unknown }"))],
+            ));
+            lines.push(tr_args("  public key: {}", &[&hex::to(&public_key)]));
+
+            let mut channels = Vec::new();
+            for channel in cable.store.get_channels().await.unwrap_or_default() {
+                if cable.store.is_channel_member(&channel, &public_key).await {
+                    channels.push(format!("#{}", channel));
+                }
+            }
+            if !channels.is_empty() {
+                lines.push(tr_args("  channels: {}", &[&channels.join(", ")]));
+            }
+        } else {
+            lines.push(tr_args("  public key: {}", &[&hex::to(&public_key)]));
+        }
+
+        let mut ui = self.ui.lock().await;
+        if let Some(address) = &ui.active_address {
+            if let Some((timestamp, channel)) = ui.last_seen(address, &public_key) {
+                lines.push(tr_args(
+                    "  last seen: {} ({}) in #{}",
+                    &[
+                        &time::format_date(*timestamp),
+                        &time::format_ago(*timestamp, self.clock.as_ref()),
+                        &channel,
+                    ],
+                ));
+            }
+        }
+        if let Some(note) = ui.notes.get(&public_key) {
+            lines.push(tr_args("  note: {}", &[&note]));
+        }
+        if ui.blocked.contains(&public_key) {
+            lines.push(tr("  blocked: yes").to_string());
+        }
+        for line in lines {
+            ui.write_status(Level::Info, &line);
+        }
+        ui.update();
+    }
+
     /// Handle the `/win` and `/w` commands.
     ///
-    /// Sets the active window of the UI.
+    /// Sets the active window of the UI, given its index or (part of) its
+    /// name. `/win rename INDEX NAME` sets a
+    /// local display label for a window, or removes it if no name is given;
+    /// `/win move SRC DST` moves a window to another position.
     async fn win_handler(&mut self, args: Vec<String>) {
         let mut ui = self.ui.lock().await;
         if let Some(index) = args.get(1) {
-            if let Ok(i) = index.parse() {
+            if index == "rename" {
+                let index = args.get(2).and_then(|index| index.parse::<usize>().ok());
+                let name = args.get(3..).map(|name| name.join(" ")).unwrap_or_default();
+                match index.and_then(|index| ui.windows.get_mut(index)) {
+                    Some(window) => {
+                        let channel = window.channel.to_owned();
+                        window.label = (!name.is_empty()).then_some(name);
+                        let msg = match &window.label {
+                            Some(label) => tr_args("renamed {} to {}", &[&channel, &label]),
+                            None => tr_args("removed the label of {}", &[&channel]),
+                        };
+                        ui.write_status(Level::Info, &msg);
+                    }
+                    None if index.is_some() => {
+                        ui.write_status(Level::Error, tr("no window with that index"))
+                    }
+                    None => ui.write_status(Level::Error, &usage("/win rename INDEX [NAME]")),
+                }
+                ui.update();
+            } else if index == "move" {
+                let len = ui.windows.len();
+                // The status window remains the first window.
+                let parse = |arg: Option<&String>| {
+                    arg.and_then(|index| index.parse::<usize>().ok())
+                        .filter(|index| (1..len).contains(index))
+                };
+                match (parse(args.get(2)), parse(args.get(3))) {
+                    (Some(src), Some(dst)) => {
+                        ui.move_window(src, dst);
+                        let msg = tr_args("moved {} to {}", &[&ui.windows[dst].name(), &dst]);
+                        ui.write_status(Level::Info, &msg);
+                    }
+                    _ if args.len() == 4 => {
+                        ui.write_status(Level::Error, tr("no window with that index"))
+                    }
+                    _ => ui.write_status(Level::Error, &usage("/win move SRC DST")),
+                }
+                ui.update();
+            } else if matches!(index.as_str(), "mentions" | "activity" | "network") {
+                let i = match index.as_str() {
+                    "mentions" => ui.mentions_window_index(),
+                    "activity" => ui.activity_window_index(),
+                    _ => ui.network_window_index(),
+                };
+                ui.set_active_index(i);
+                ui.update();
+            } else if let Ok(i) = index.parse() {
+                ui.set_active_index(i);
+                ui.update();
+            } else if let Some(i) = ui.match_windows(index).first().copied() {
                 ui.set_active_index(i);
                 ui.update();
             } else {
-                ui.write_status("window index must be a number");
+                ui.write_status(Level::Error, &tr_args("no window matches {}", &[&index]));
                 ui.update();
             }
         } else {
-            ui.write_status("usage: /win INDEX");
+            ui.write_status(
+                Level::Error,
+                &usage("/win INDEX|CHANNEL|mentions|activity|network"),
+            );
             ui.update();
         }
     }
 
     /// Parse UI input and invoke the appropriate handler.
     pub async fn handle(&mut self, line: &str) -> Result<(), Error> {
-        let args = line
-            .split_whitespace()
-            .map(|s| s.to_string())
-            .collect::<Vec<String>>();
+        // Arguments may be quoted (e.g. `/nick "cabin dweller"`), but the
+        // command itself is always the first word of the line. Free text
+        // which follows the leading arguments of some commands is passed on
+        // as typed.
+        let command = line.split_whitespace().next().unwrap_or_default();
+        let arity = COMMANDS
+            .iter()
+            .find(|(name, _arity)| *name == command)
+            .map_or(Arity::Args, |(_name, arity)| *arity);
+        let args = match arity {
+            Arity::Args => utils::parse_args(line),
+            Arity::ArgsThenText(n) => utils::parse_args_then_text(line, n),
+        };
         if args.is_empty() {
             return Ok(());
         }
+        if self.confirm_held(line).await {
+            return Ok(());
+        }
+        // Sensitive lines are echoed without their arguments.
+        let echo = self.redaction.echo(line);
+        let status_lines = self.ui.lock().await.windows[0].lines.len();
 
-        match args.get(0).unwrap().as_str() {
+        match command {
+            "/awaylog" => {
+                self.write_status(Level::Command, &echo).await;
+                self.awaylog_handler(args).await;
+            }
+            "/block" | "/unblock" => {
+                self.write_status(Level::Command, &echo).await;
+                self.block_handler(args).await;
+            }
             "/cabal" => {
-                self.write_status(line).await;
+                self.write_status(Level::Command, &echo).await;
                 self.cabal_handler(args).await;
             }
             "/channels" => {
-                self.write_status(line).await;
-                self.channels_handler().await;
+                self.write_status(Level::Command, &echo).await;
+                self.channels_handler(args).await;
+            }
+            "/chanstats" => {
+                self.write_status(Level::Command, &echo).await;
+                self.chanstats_handler(args).await;
+            }
+            "/clear" => {
+                self.write_status(Level::Command, &echo).await;
+                self.clear_handler().await;
             }
             "/connect" => {
-                self.write_status(line).await;
+                self.write_status(Level::Command, &echo).await;
                 self.connect_handler(args).await;
             }
+            "/disconnect" => {
+                self.write_status(Level::Command, &echo).await;
+                self.disconnect_handler(args).await;
+            }
             "/connections" => {
-                self.write_status(line).await;
+                self.write_status(Level::Command, &echo).await;
                 self.connections_handler().await;
             }
             "/delete" => {
-                self.write_status(line).await;
+                self.write_status(Level::Command, &echo).await;
                 self.delete_handler(args).await?;
             }
+            "/doctor" => {
+                self.write_status(Level::Command, &echo).await;
+                self.doctor_handler(args).await;
+            }
             "/help" => {
-                self.write_status(line).await;
+                self.write_status(Level::Command, &echo).await;
                 self.help_handler().await;
             }
+            "/info" => {
+                self.write_status(Level::Command, &echo).await;
+                self.info_handler(args).await;
+            }
+            "/history" => {
+                self.write_status(Level::Command, &echo).await;
+                self.history_handler(args).await;
+            }
+            "/ignore" | "/unignore" => {
+                self.write_status(Level::Command, &echo).await;
+                self.ignore_handler(args).await;
+            }
+            "/create" => {
+                self.write_status(Level::Command, &echo).await;
+                self.create_handler(args).await?;
+            }
+            "/invite" => {
+                self.write_status(Level::Command, &echo).await;
+                self.invite_handler(args).await;
+            }
             "/join" | "/j" => {
-                self.join_handler(args).await?;
+                self.join_handler(args, true).await?;
             }
             "/leave" => {
                 self.leave_handler(args).await?;
             }
             "/listen" => {
-                self.write_status(line).await;
+                self.write_status(Level::Command, &echo).await;
                 self.listen_handler(args).await;
             }
+            "/lock" => {
+                self.write_status(Level::Command, &echo).await;
+                self.lock_handler().await;
+            }
             "/members" => {
-                self.write_status(line).await;
+                self.write_status(Level::Command, &echo).await;
                 self.members_handler(args).await;
             }
+            "/note" | "/unnote" => {
+                self.write_status(Level::Command, &echo).await;
+                self.note_handler(args).await;
+            }
             "/nick" => {
-                self.write_status(line).await;
+                self.write_status(Level::Command, &echo).await;
                 self.nick_handler(args).await?;
             }
+            "/pin" | "/unpin" => {
+                self.write_status(Level::Command, &echo).await;
+                self.pin_handler(args).await;
+            }
+            "/pins" => {
+                self.pins_handler().await;
+            }
+            "/reply" => {
+                self.reply_handler(args, line.trim_end()).await?;
+            }
+            "/retry" => {
+                self.write_status(Level::Command, &echo).await;
+                self.retry_handler(args).await;
+            }
+            "/session" => {
+                self.write_status(Level::Command, &echo).await;
+                self.session_handler(args).await?;
+            }
+            "/set" => {
+                self.write_status(Level::Command, &echo).await;
+                self.set_handler(args).await;
+            }
+            "/split" | "/unsplit" => {
+                self.write_status(Level::Command, &echo).await;
+                self.split_handler(args).await;
+            }
+            "/sync" => {
+                self.write_status(Level::Command, &echo).await;
+                self.sync_handler(args).await;
+            }
+            "/toggle" => {
+                self.write_status(Level::Command, &echo).await;
+                self.toggle_handler(args).await;
+            }
             "/topic" => {
-                self.write_status(line).await;
+                self.write_status(Level::Command, &echo).await;
                 self.topic_handler(args).await?;
             }
             "/quit" | "/exit" | "/q" => {
-                self.write_status(line).await;
+                self.write_status(Level::Command, &echo).await;
                 self.exit = true;
             }
             "/whoami" => {
-                self.write_status(line).await;
+                self.write_status(Level::Command, &echo).await;
                 self.whoami_handler().await;
             }
+            "/publish" => {
+                self.write_status(Level::Command, &echo).await;
+                self.publish_handler(args).await;
+            }
+            "/redraw" => {
+                self.write_status(Level::Command, &echo).await;
+                self.ui.lock().await.redraw();
+            }
+            "/schedule" => {
+                self.write_status(Level::Command, &echo).await;
+                self.schedule_handler(args).await;
+            }
+            "/search" => {
+                self.write_status(Level::Command, &echo).await;
+                self.search_handler(args).await;
+            }
+            "/store" => {
+                self.write_status(Level::Command, &echo).await;
+                self.store_handler(args).await;
+            }
+            "/seen" => {
+                self.write_status(Level::Command, &echo).await;
+                self.seen_handler(args).await;
+            }
+            "/whois" => {
+                self.write_status(Level::Command, &echo).await;
+                self.whois_handler(args).await;
+            }
             "/win" | "/w" => {
                 self.win_handler(args).await;
             }
             x => {
                 if x.starts_with('/') {
-                    self.write_status(line).await;
-                    self.write_status(&format!("no such command: {}", x)).await;
+                    self.write_status(Level::Command, &echo).await;
+                    self.write_status(Level::Error, &tr_args("no such command: {}", &[&x]))
+                        .await;
                 } else {
                     self.post(&line.trim_end().to_string()).await?;
                 }
             }
         }
 
+        // Output which does not fit on the screen is paged.
+        let mut ui = self.ui.lock().await;
+        let output = ui.windows[0].lines.len().saturating_sub(status_lines);
+        ui.start_pager(output);
+        ui.update();
+
         Ok(())
     }
 
+    /// Start the bridges, feeds and webhooks defined in the configuration
+    /// file, adding their cabals if required.
+    async fn start_integrations(
+        &mut self,
+        bridges: &[BridgeConfig],
+        feeds: &[FeedConfig],
+        webhooks: &[WebhookConfig],
+    ) {
+        for bridge_config in bridges {
+            let channel = &bridge_config.channel;
+            let started = tr_args("bridging channel {}", &[&channel]);
+            let failed = tr_args("failed to bridge channel {}", &[&channel]);
+            self.start_integration("bridge", &bridge_config.cabal, started, failed, |cable| {
+                bridge::spawn(cable, channel.clone(), &bridge_config.protocol)
+            })
+            .await;
+        }
+        for feed_config in feeds {
+            let started = tr_args(
+                "posting items of {} to channel {}",
+                &[&feed_config.url, &feed_config.channel],
+            );
+            let failed = tr_args("failed to start feed {}", &[&feed_config.url]);
+            self.start_integration("feed", &feed_config.cabal, started, failed, |cable| {
+                feeds::spawn(cable, feed_config.clone())
+            })
+            .await;
+        }
+        for webhook_config in webhooks {
+            let started = tr_args(
+                "posting channel {} to webhook {}",
+                &[&webhook_config.channel, &webhook_config.url],
+            );
+            let failed = tr_args("failed to start webhook {}", &[&webhook_config.url]);
+            self.start_integration("webhook", &webhook_config.cabal, started, failed, |cable| {
+                webhooks::spawn(cable, webhook_config.clone())
+            })
+            .await;
+        }
+    }
+
+    /// Start an integration of the given kind in the cabal with the given
+    /// hex-encoded address, adding the cabal if required.
+    ///
+    /// `spawn` starts the integration with the cable manager of the cabal;
+    /// the `started` or `failed` message is then written to the status
+    /// window, the latter followed by the error.
+    async fn start_integration<F>(
+        &mut self,
+        kind: &str,
+        cabal: &str,
+        started: String,
+        failed: String,
+        spawn: F,
+    ) where
+        F: FnOnce(CableManager<S>) -> Result<(), String>,
+    {
+        let Some(addr) = hex::from(cabal) else {
+            self.write_status(
+                Level::Error,
+                &tr_args("invalid {} cabal address: {}", &[&kind, &cabal]),
+            )
+            .await;
+            return;
+        };
+        self.add_cable(&addr);
+        let cable = self.client.cable(&addr).unwrap().clone();
+        match spawn(cable) {
+            Ok(()) => self.write_status(Level::Info, &started).await,
+            Err(err) => {
+                self.write_status(Level::Error, &format!("{}: {}", failed, err))
+                    .await
+            }
+        }
+    }
+
+    /// Perform the given keybinding action, returning a command to be
+    /// handled if the action is implemented by one.
+    fn perform(ui: &mut Ui, action: Action) -> Option<String> {
+        match action {
+            Action::NextWindow => ui.next_window(),
+            Action::PrevWindow => ui.prev_window(),
+            Action::ScrollUp => {
+                let page = ui.page_height();
+                ui.get_active_window().scroll_up(page);
+            }
+            Action::ScrollDown => {
+                let page = ui.page_height();
+                ui.get_active_window().scroll_down(page);
+            }
+            Action::ClearInput => {
+                ui.input.kill_line();
+            }
+            Action::ToggleMembers => {
+                let window = ui.get_active_window();
+                if !window.is_virtual() {
+                    return Some(format!("/members {}", window.channel));
+                }
+            }
+            Action::SelectMessage => ui.start_selection(),
+            Action::SwitchWindow => ui.start_switcher(),
+            Action::Complete => ui.complete(),
+            Action::Redraw => {
+                ui.redraw();
+                return None;
+            }
+            // The terminal is restored by the handler of the signal (see
+            // `ui::resizer`) before the process is stopped.
+            Action::Suspend => {
+                let _ = low_level::raise(SIGTSTP);
+                return None;
+            }
+            Action::MoveWindowLeft | Action::MoveWindowRight => {
+                let src = ui.active_window;
+                let dst = if action == Action::MoveWindowLeft {
+                    src.checked_sub(1)
+                } else {
+                    Some(src + 1)
+                };
+                // The status window remains the first window.
+                let dst = dst.filter(|dst| src > 0 && (1..ui.windows.len()).contains(dst));
+                if let Some(dst) = dst {
+                    return Some(format!("/win move {} {}", src, dst));
+                }
+            }
+        }
+        ui.update();
+
+        None
+    }
+
+    /// Handle the given key while command output is paged.
+    ///
+    /// Space displays the next page and enter the next line, while q or
+    /// escape skip to the end of the output.
+    fn page(ui: &mut Ui, key: Key) {
+        match key {
+            Key::Char(' ') => {
+                let page = ui.page_height();
+                ui.page_down(page);
+            }
+            Key::Enter => ui.page_down(1),
+            Key::Char('q') | Key::Escape => ui.end_pager(),
+            _ => {}
+        }
+        ui.update();
+    }
+
+    /// Handle the given key while the window switcher is open.
+    ///
+    /// Typed characters refine the query, the up and down arrow keys move the
+    /// highlight and enter makes the highlighted window active.
+    fn switch(ui: &mut Ui, key: Key) {
+        let Some(switcher) = ui.switcher.as_ref() else {
+            return;
+        };
+        let mut query = switcher.query.clone();
+        let mut selected = switcher.selected;
+
+        match key {
+            Key::Char(c) => {
+                query.push(c);
+                selected = 0;
+            }
+            Key::Backspace => {
+                query.pop();
+                selected = 0;
+            }
+            Key::Up => selected = selected.saturating_sub(1),
+            Key::Down => selected += 1,
+            Key::Enter => {
+                let index = ui.match_windows(&query).get(selected).copied();
+                ui.end_switcher();
+                if let Some(index) = index {
+                    ui.set_active_index(index);
+                }
+            }
+            Key::Escape => ui.end_switcher(),
+            _ => {}
+        }
+
+        // The highlight remains within the list of matches.
+        let len = ui.match_windows(&query).len();
+        if let Some(switcher) = ui.switcher.as_mut() {
+            switcher.selected = selected.min(len.saturating_sub(1));
+            switcher.query = query;
+        }
+        ui.update();
+    }
+
+    /// Handle the given key while the UI is locked.
+    ///
+    /// Typed characters are added to the passphrase and enter checks it
+    /// against the given hash, unlocking the UI if it matches.
+    fn enter_passphrase(ui: &mut Ui, hash: Option<&str>, key: Key) {
+        let Some(lock) = ui.locked.as_mut() else {
+            return;
+        };
+
+        match key {
+            Key::Char(c) => lock.passphrase.push(c),
+            Key::Backspace => {
+                lock.passphrase.pop();
+            }
+            Key::Escape => lock.passphrase.clear(),
+            Key::Enter => {
+                let passphrase = std::mem::take(&mut lock.passphrase);
+                if hash.is_some_and(|hash| passphrase_matches(&passphrase, hash)) {
+                    let failures = lock.failures;
+                    ui.end_lock();
+                    if failures > 0 {
+                        ui.write_status(
+                            Level::Info,
+                            &tr_args("unlocked after {} incorrect passphrases", &[&failures]),
+                        );
+                    }
+                } else {
+                    lock.failures += 1;
+                }
+            }
+            _ => {}
+        }
+        ui.update();
+    }
+
+    /// Perform the action bound to the given key in selection mode,
+    /// returning a line of input to be handled, if any.
+    fn select(ui: &mut Ui, key: Key) -> Option<String> {
+        // The selected post may have been removed since it was selected, in
+        // which case selection ends rather than capturing keys.
+        let Some((n, line)) = ui.selection.zip(ui.selected_post().cloned()) else {
+            ui.end_selection();
+            ui.update();
+            return None;
+        };
+        let mut command = None;
+
+        match key {
+            Key::Char('k') | Key::Up => ui.move_selection(1),
+            Key::Char('j') | Key::Down => ui.move_selection(-1),
+            Key::Char('c') | Key::Char('y') => {
+                let text = utils::parse_reply(&line.text)
+                    .map(|(_hash, text)| text)
+                    .unwrap_or(&line.text)
+                    .to_owned();
+                ui.copy_to_clipboard(&text);
+                ui.end_selection();
+                ui.write_status(Level::Info, tr("copied post to clipboard"));
+            }
+            Key::Char('r') => {
+                ui.end_selection();
+                let reply = format!("/reply {} ", n);
+                ui.input.set_value(&reply);
+                ui.input.set_cursor(reply.len());
+            }
+            Key::Char('d') => {
+                ui.end_selection();
+                command = Some(format!("/delete {}", n));
+            }
+            Key::Char('o') => match utils::find_url(&line.text) {
+                Some(url) => {
+                    if let Err(err) = utils::open_url(url) {
+                        ui.write_status(
+                            Level::Error,
+                            &tr_args("failed to open {}: {}", &[&url, &err]),
+                        );
+                    }
+                }
+                None => ui.write_status(Level::Error, tr("no url found in the selected post")),
+            },
+            Key::Char('i') => command = Some(format!("/info {}", n)),
+            Key::Char('q') | Key::Escape => ui.end_selection(),
+            _ => {}
+        }
+        ui.update();
+
+        command
+    }
+
     /// Post the given text message to the channel and cabal associated with
     /// the active UI window.
+    ///
+    /// If the active window was changed within the number of seconds defined
+    /// by the `guard` setting, the message is returned to the input line and
+    /// only sent once it has been submitted a second time.
     pub async fn post(&mut self, msg: &String) -> Result<(), Error> {
+        self.post_from(msg, msg).await
+    }
+
+    /// Post the given text to the active channel, as `post`, where the text
+    /// was derived from the given line of input (e.g. a `/reply` command).
+    ///
+    /// The line of input, rather than the text, is put back in the input
+    /// line while confirmation to send the post is requested.
+    async fn post_from(&mut self, msg: &String, input: &str) -> Result<(), Error> {
         let mut ui = self.ui.lock().await;
+
+        // Entering a number in the mentions window jumps to the mention.
+        if ui.get_active_window().channel == MENTIONS_WINDOW {
+            if let Ok(n) = msg.trim().parse() {
+                if let Err(err) = ui.jump_to_mention(n) {
+                    ui.get_active_window().write(&err);
+                }
+                ui.update();
+                return Ok(());
+            }
+        }
+
+        let index = ui.get_active_index();
+        let pending = Some((index, msg.to_owned()));
+        if ui.active_recently_changed()
+            && !ui.get_active_window().is_virtual()
+            && self.pending_post != pending
+        {
+            self.pending_post = pending;
+            let w = ui.get_active_window();
+            let confirm = tr_args("press enter again to send to {}", &[&w.name()]);
+            w.write(&confirm);
+            ui.input.set_value(input);
+            ui.input.set_cursor(input.len());
+            ui.update();
+            return Ok(());
+        }
+        self.pending_post = None;
+
+        let flood = ui.settings.flood;
         let w = ui.get_active_window();
-        if w.channel == "!status" {
-            ui.write_status("can't post text in status channel. see /help for command list");
+        if w.is_virtual() {
+            let msg = tr_args(
+                "can't post text in {} window. see /help for command list",
+                &[&w.channel],
+            );
+            ui.write_status(Level::Error, &msg);
             ui.update();
+        } else if msg.len() > MAX_TEXT_LEN {
+            // Text exceeding the maximum length of a post is split into
+            // several posts, once confirmed.
+            let parts = client::split_text(msg, MAX_TEXT_LEN);
+            if self.pending_split != pending {
+                self.pending_split = pending;
+                let confirm = tr_args(
+                    "message is {} bytes, exceeding the limit of {} bytes per post; press enter again to send it as {} posts",
+                    &[&msg.len(), &MAX_TEXT_LEN, &parts.len()],
+                );
+                w.write(&confirm);
+                ui.input.set_value(input);
+                ui.input.set_cursor(input.len());
+                ui.update();
+                return Ok(());
+            }
+            self.pending_split = None;
+            let (address, channel) = (w.address.clone(), w.channel.clone());
+            drop(ui);
+            for part in parts {
+                self.send_post(&address, &channel, part).await;
+            }
+        } else if self
+            .flood
+            .hold(&w.address, &w.channel, msg, flood, self.ui.clone())
+            .await
+        {
+            // The post is sent once confirmed.
         } else {
-            let cable = self.cables.get_mut(&w.address).unwrap();
-            // TODO: Match on validation error and display to user.
-            cable.post_text(&w.channel, msg).await?;
+            let (address, channel) = (w.address.clone(), w.channel.clone());
+            drop(ui);
+            self.send_post(&address, &channel, msg).await;
         }
         Ok(())
     }
 
+    /// Send a text post to the given channel.
+    ///
+    /// A post which fails to send is kept to be resent by `/retry`, and
+    /// marked as failed in the window of the channel, rather than being
+    /// lost.
+    async fn send_post(&mut self, address: &Addr, channel: &Channel, text: &str) {
+        // TODO: Match on validation error and display to user.
+        let err = match self.client.post(address, channel, text).await {
+            Ok(_hash) => return,
+            Err(err) => err,
+        };
+
+        keep_failed(&self.failed, &self.ui, address, channel, text, &err).await;
+    }
+
+    /// Handle the `/retry` command.
+    ///
+    /// Resends all posts which failed to send, or only the given one. Posts
+    /// which fail again remain queued under the same number.
+    async fn retry_handler(&mut self, args: Vec<String>) {
+        let failed = self
+            .failed
+            .lock()
+            .await
+            .posts
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+        let numbers: Vec<usize> = match args.get(1).map(|arg| arg.parse::<usize>()) {
+            None => failed,
+            Some(Ok(n)) if failed.contains(&n) => vec![n],
+            Some(Ok(n)) => {
+                self.write_status(Level::Error, &tr_args("no failed post {}", &[&n]))
+                    .await;
+                return;
+            }
+            Some(Err(_)) => {
+                self.write_status(Level::Error, &usage("/retry [N]")).await;
+                return;
+            }
+        };
+        if numbers.is_empty() {
+            self.write_status(Level::Info, tr("{ no failed posts }"))
+                .await;
+            return;
+        }
+
+        for n in numbers {
+            let Some(post) = self.failed.lock().await.posts.remove(&n) else {
+                continue;
+            };
+            match self
+                .client
+                .post(&post.address, &post.channel, &post.text)
+                .await
+            {
+                Ok(_hash) => {
+                    let mut ui = self.ui.lock().await;
+                    if let (Some(window), Some(line)) =
+                        (ui.get_window(&post.address, &post.channel), post.line)
+                    {
+                        window.remove_line(line);
+                    }
+                    ui.write_status(
+                        Level::Info,
+                        &tr_args("resent post {} to #{}", &[&n, &post.channel]),
+                    );
+                    ui.update();
+                }
+                Err(err) => {
+                    self.write_status(
+                        Level::Error,
+                        &tr_args(
+                            "failed to resend post {} to #{}: {}",
+                            &[&n, &post.channel, &err],
+                        ),
+                    )
+                    .await;
+                    self.failed.lock().await.posts.insert(n, post);
+                }
+            }
+        }
+    }
+
+    /// Handle an answer to the confirmation of held posts, returning `true`
+    /// if the line was consumed as an answer.
+    async fn confirm_held(&mut self, line: &str) -> bool {
+        let mut held = self.flood.held.lock().await;
+        // Commands remain available while confirmation is requested.
+        if !held.as_ref().is_some_and(|held| held.prompted) || line.starts_with('/') {
+            return false;
+        }
+
+        match line.trim() {
+            "y" | "yes" => {
+                let posts = held.take().map(|held| held.posts).unwrap_or_default();
+                drop(held);
+                self.flood.sent.clear();
+                // A post which fails to send is kept for `/retry`, without
+                // losing the posts held after it.
+                for (address, channel, text) in posts {
+                    self.send_post(&address, &channel, &text).await;
+                }
+            }
+            "n" | "no" => {
+                let count = held.take().map_or(0, |held| held.posts.len());
+                drop(held);
+                self.write_status(Level::Info, &tr_args("discarded {} messages", &[&count]))
+                    .await;
+            }
+            _ => {
+                let count = held.as_ref().map_or(0, |held| held.posts.len());
+                drop(held);
+                let mut ui = self.ui.lock().await;
+                let msg = tr_args("about to send {} messages, continue? (y/n)", &[&count]);
+                ui.get_active_window().write(&msg);
+                ui.update();
+            }
+        }
+
+        true
+    }
+
     /// Run the application.
     ///
     /// Handle input and update the UI.
     pub async fn run(
         &mut self,
-        mut reader: Box<dyn Read>,
+        reader: Box<dyn Read>,
         close_channel_receiver: CloseChannelReceiver,
     ) -> Result<(), Error> {
         self.launch_abort_listener(close_channel_receiver).await;
 
+        match history::load() {
+            Ok(entries) => self.ui.lock().await.input.history = entries,
+            Err(err) => warn!("Failed to load input history: {}", err),
+        }
+
+        // The input history is saved however the session ends, including
+        // when a command fails.
+        let result = self.run_session(reader).await;
+
+        self.cancel_connections();
+        self.save_stores().await;
+
+        let mut ui = self.ui.lock().await;
+        if ui.settings.history != HistoryMode::Off {
+            if let Err(err) = history::save(&ui.input.history) {
+                warn!("Failed to save input history: {}", err);
+            }
+        }
+        ui.finish();
+
+        result
+    }
+
+    /// Load the persisted state and configuration, then handle input until
+    /// the application exits.
+    async fn run_session(&mut self, mut reader: Box<dyn Read>) -> Result<(), Error> {
+        self.load_notes().await;
+
         self.ui.lock().await.update();
         self.write_status_banner().await;
 
-        let mut buf = vec![0];
+        self.load_config().await;
+
+        match awaylog::load() {
+            Ok(entries) if !entries.is_empty() => {
+                self.write_status(
+                    Level::Info,
+                    &tr_args(
+                        "{} mentions while you were away, /awaylog to view",
+                        &[&entries.len()],
+                    ),
+                )
+                .await
+            }
+            Ok(_entries) => {}
+            Err(err) => warn!("Failed to load away log: {}", err),
+        }
+
+        let mut buf = [0; INPUT_BUFFER_LEN];
+        let mut input_errors = 0;
         while !self.exit {
             // Parse input from stdin.
-            reader.read_exact(&mut buf).unwrap();
+            let len = match reader.read(&mut buf) {
+                // The input was closed (e.g. piped input ended or the
+                // terminal was closed), so exit as if `/quit` was entered.
+                Ok(0) => {
+                    debug!("Input closed; exiting");
+                    break;
+                }
+                Ok(len) => {
+                    input_errors = 0;
+                    len
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    warn!("Failed to read input: {}", err);
+                    input_errors += 1;
+                    if input_errors >= MAX_INPUT_ERRORS {
+                        break;
+                    }
+                    self.write_status(Level::Error, &tr_args("failed to read input: {}", &[&err]))
+                        .await;
+                    continue;
+                }
+            };
             let lines = {
                 let mut ui = self.ui.lock().await;
-                ui.input.putc(buf[0]);
+                ui.record_input();
+                let vi = ui.settings.vi;
+                ui.input.set_vi(vi);
+                for b in &buf[..len] {
+                    ui.input.putc(*b);
+                }
+                ui.input.flush_escape();
                 ui.update();
                 let mut lines = vec![];
                 while let Some(event) = ui.input.next_event() {
                     match event {
-                        // TODO: Handle PageUp and PageDown.
-                        InputEvent::KeyCode(KeyCode::PageUp) => {}
-                        InputEvent::KeyCode(KeyCode::PageDown) => {}
-                        InputEvent::KeyCode(_) => {}
+                        InputEvent::Captured(key) if ui.locked.is_some() => {
+                            Self::enter_passphrase(&mut ui, self.lock.as_deref(), key);
+                        }
+                        // Keybindings and pasted lines are ignored while
+                        // locked.
+                        _ if ui.locked.is_some() => {}
+                        InputEvent::KeyCode(keycode) => {
+                            if let Some(action) = self.keys.keycode_action(&keycode) {
+                                lines.extend(Self::perform(&mut ui, action));
+                            }
+                        }
+                        InputEvent::Chord(chord) => {
+                            if let Some(action) = self.keys.action(&chord) {
+                                lines.extend(Self::perform(&mut ui, action));
+                            }
+                        }
+                        InputEvent::Captured(key) if ui.paging => {
+                            Self::page(&mut ui, key);
+                        }
+                        InputEvent::Captured(key) if ui.switcher.is_some() => {
+                            Self::switch(&mut ui, key);
+                        }
+                        InputEvent::Captured(key) => {
+                            lines.extend(Self::select(&mut ui, key));
+                        }
                         InputEvent::Line(line) => {
+                            if ui.settings.history.records(&line) && !self.redaction.matches(&line)
+                            {
+                                ui.input.push_history(line.clone());
+                            }
                             lines.push(line);
                         }
                     }
@@ -914,7 +5685,211 @@ where
                 }
             }
         }
-        self.ui.lock().await.finish();
+
+        Ok(())
+    }
+
+    /// Load the notes about peers persisted by `/note`.
+    async fn load_notes(&mut self) {
+        match notes::load() {
+            Ok(notes) => self.ui.lock().await.notes.extend(notes),
+            Err(err) => warn!("Failed to load notes: {}", err),
+        }
+    }
+
+    /// Load the configuration file, applying its settings and keybindings
+    /// and starting any bridges, feeds, webhooks and metrics endpoint
+    /// defined in it.
+    async fn load_config(&mut self) {
+        // The configuration file of an older version of cabin is upgraded
+        // before it is parsed.
+        match migrate::upgrade(&Config::path(), migrate::CONFIG) {
+            Ok(applied) => {
+                for msg in applied {
+                    self.write_status(Level::Info, &tr_args("config: {}", &[&msg]))
+                        .await;
+                }
+            }
+            Err(err) => {
+                self.write_status(
+                    Level::Error,
+                    &tr_args("failed to load {}: {}", &[&Config::path().display(), &err]),
+                )
+                .await;
+                return;
+            }
+        }
+
+        match Config::load() {
+            Ok(config) => {
+                self.ui.lock().await.settings = config.settings;
+                if let Some(locale) = &config.locale {
+                    if let Err(err) = i18n::set_locale(locale) {
+                        self.write_status(Level::Error, &tr_args("config: {}", &[&err]))
+                            .await;
+                    }
+                }
+                for err in self.keys.configure(&config.keys) {
+                    self.write_status(Level::Error, &tr_args("config: {}", &[&err]))
+                        .await;
+                }
+                for err in self.redaction.configure(&config.redact) {
+                    self.write_status(Level::Error, &tr_args("config: {}", &[&err]))
+                        .await;
+                }
+                if let Some(allow) = config.allow {
+                    self.write_status(
+                        Level::Network,
+                        &tr_args(
+                            "accepting incoming connections from {} allowed addresses",
+                            &[&allow.len()],
+                        ),
+                    )
+                    .await;
+                    self.admission.allow = Some(allow.into_iter().collect());
+                }
+                self.start_integrations(&config.bridges, &config.feeds, &config.webhooks)
+                    .await;
+                self.admission.max_incoming = config.max_incoming;
+                self.admission.max_connections = config.max_connections;
+                self.stun = config.stun;
+                self.lock = config.lock;
+                if let Some(addr) = &config.metrics {
+                    match metrics::serve(addr).await {
+                        Ok(()) => {
+                            self.write_status(
+                                Level::Info,
+                                &tr_args("serving metrics on {}/metrics", &[&addr]),
+                            )
+                            .await
+                        }
+                        Err(err) => {
+                            self.write_status(
+                                Level::Error,
+                                &tr_args("failed to serve metrics on {}: {}", &[&addr, &err]),
+                            )
+                            .await
+                        }
+                    }
+                }
+            }
+            Err(err) => {
+                self.write_status(
+                    Level::Error,
+                    &tr_args("failed to load {}: {}", &[&Config::path().display(), &err]),
+                )
+                .await;
+            }
+        }
+    }
+
+    /// Run without a terminal until a `SIGTERM` or `SIGINT` is received.
+    ///
+    /// The session at the given path is restored on startup (if it exists)
+    /// and saved on shutdown. Readiness and shutdown are reported to the
+    /// service manager via `sd_notify`.
+    pub async fn run_daemon(
+        &mut self,
+        session_path: &Path,
+        close_channel_receiver: CloseChannelReceiver,
+    ) -> Result<(), Error> {
+        self.ui.lock().await.headless = true;
+        self.launch_abort_listener(close_channel_receiver).await;
+        self.load_config().await;
+        self.load_notes().await;
+
+        match Session::load(session_path) {
+            Ok(session) => {
+                self.import_session(session).await?;
+                self.write_status(
+                    Level::Info,
+                    &tr_args("loaded session from {}", &[&session_path.display()]),
+                )
+                .await;
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => {
+                self.write_status(
+                    Level::Error,
+                    &tr_args(
+                        "failed to load session from {}: {}",
+                        &[&session_path.display(), &err],
+                    ),
+                )
+                .await;
+            }
+        }
+
+        let pid_file = match daemon::PidFile::create() {
+            Ok(pid_file) => Some(pid_file),
+            Err(err) => {
+                warn!("Failed to write pid file: {}", err);
+                None
+            }
+        };
+        // TODO: Serve the full-screen UI over an embedded SSH server bound
+        // to localhost or a LAN interface. This needs an SSH server crate,
+        // a host key, and a UI rendered to a terminal per session rather
+        // than to stdout. Until then, remote clients attach over the
+        // system's sshd (see "Daemon Mode" in the readme).
+
+        // Lines of input sent by attached clients.
+        let (line_sender, mut lines) = mpsc::unbounded::<String>();
+        let control_socket = match control::ControlSocket::bind().await {
+            Ok((control_socket, listener)) => {
+                task::spawn(control::serve(listener, self.ui.clone(), line_sender));
+                Some(control_socket)
+            }
+            Err(err) => {
+                warn!("Failed to bind control socket: {}", err);
+                None
+            }
+        };
+
+        if let Err(err) = daemon::notify("READY=1") {
+            warn!("Failed to notify service manager: {}", err);
+        }
+
+        // Handle input from attached clients until terminated.
+        let mut terminated = Box::pin(daemon::terminated());
+        loop {
+            match futures::future::select(terminated, lines.next()).await {
+                Either::Left((result, _next)) => {
+                    result?;
+                    break;
+                }
+                Either::Right((Some(line), pending)) => {
+                    terminated = pending;
+                    if let Err(err) = self.handle(&line).await {
+                        self.write_status(Level::Error, &err.to_string()).await;
+                    }
+                }
+                Either::Right((None, pending)) => {
+                    pending.await?;
+                    break;
+                }
+            }
+        }
+        drop(control_socket);
+
+        if let Err(err) = daemon::notify("STOPPING=1") {
+            warn!("Failed to notify service manager: {}", err);
+        }
+        let session = self.export_session().await;
+        self.cancel_connections();
+        self.save_stores().await;
+        let saved = match session_path.parent() {
+            Some(dir) => fs::create_dir_all(dir).and_then(|()| session.save(session_path)),
+            None => session.save(session_path),
+        };
+        if let Err(err) = saved {
+            error!(
+                "Failed to save session to {}: {}",
+                session_path.display(),
+                err
+            );
+        }
+        drop(pid_file);
 
         Ok(())
     }
@@ -925,9 +5900,9 @@ where
     }
 
     /// Write the given message to the UI.
-    pub async fn write_status(&self, msg: &str) {
+    pub async fn write_status(&self, level: Level, msg: &str) {
         let mut ui = self.ui.lock().await;
-        ui.write_status(msg);
+        ui.write_status(level, msg);
         ui.update();
     }
 
@@ -937,8 +5912,15 @@ where
         let banner = include_str!("../welcome.txt");
 
         let mut ui = self.ui.lock().await;
-        for line in banner.lines() {
-            ui.write_status(line)
+        // The artwork (which precedes the first blank line) is meaningless to
+        // a screen reader, so it is omitted in accessible mode.
+        let skip = if ui.accessible {
+            banner.lines().position(str::is_empty).unwrap_or(0)
+        } else {
+            0
+        };
+        for line in banner.lines().skip(skip) {
+            ui.write_status(Level::Info, tr(line))
         }
         ui.update();
     }
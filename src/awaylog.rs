@@ -0,0 +1,83 @@
+//! Persistence of mentions received while running as a daemon.
+//!
+//! Mentions of the local peer received without a terminal are appended to
+//! the away log in the state directory, so that they can be reviewed with
+//! `/awaylog` once cabin is next started interactively.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use crate::paths;
+
+/// A mention recorded in the away log.
+pub struct Entry {
+    pub timestamp: u64,
+    pub channel: String,
+    /// The nickname of the author, or their abbreviated public key.
+    pub name: String,
+    pub text: String,
+}
+
+/// Return the path of the away log.
+fn path() -> PathBuf {
+    paths::state_dir().join("awaylog")
+}
+
+/// Replace the characters used to delimit entries and fields.
+fn sanitise(field: &str) -> String {
+    field.replace(['\t', '\n', '\r'], " ")
+}
+
+/// Append the given entry to the away log.
+pub fn append(entry: &Entry) -> io::Result<()> {
+    let path = path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(
+        file,
+        "{}\t{}\t{}\t{}",
+        entry.timestamp,
+        sanitise(&entry.channel),
+        sanitise(&entry.name),
+        sanitise(&entry.text)
+    )
+}
+
+/// Load the entries of the away log, oldest first.
+///
+/// Returns no entries if nothing has been logged. Malformed lines are
+/// skipped.
+pub fn load() -> io::Result<Vec<Entry>> {
+    let contents = match fs::read_to_string(path()) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    Ok(contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, '\t');
+            Some(Entry {
+                timestamp: fields.next()?.parse().ok()?,
+                channel: fields.next()?.to_owned(),
+                name: fields.next()?.to_owned(),
+                text: fields.next()?.to_owned(),
+            })
+        })
+        .collect())
+}
+
+/// Remove all entries of the away log.
+pub fn clear() -> io::Result<()> {
+    match fs::remove_file(path()) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,171 @@
+//! Bridges between cable channels and external chat protocols.
+//!
+//! A bridge forwards the text posts of a channel to an external endpoint
+//! (such as a Matrix room) and publishes messages received from the endpoint
+//! to the channel, prefixed with the name of their author. Bridges are
+//! configured per channel in the `[[bridges]]` section of the configuration
+//! file.
+
+use std::collections::HashSet;
+
+use async_std::{
+    prelude::*,
+    sync::{Arc, Mutex},
+    task,
+};
+use cable::{post::PostBody, Channel, ChannelOptions, Hash};
+use cable_core::{CableManager, Store};
+use futures::channel::mpsc::{UnboundedReceiver, UnboundedSender};
+use log::{error, warn};
+use serde::Deserialize;
+
+use crate::{
+    client, hex,
+    time::{Clock, SystemClock},
+};
+
+/// A message passed across a bridge.
+#[derive(Debug, Clone)]
+pub struct BridgeMessage {
+    /// The display name of the author.
+    pub author: String,
+    pub text: String,
+}
+
+/// The configuration of a single bridge.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BridgeConfig {
+    /// The hex-encoded address of the cabal.
+    pub cabal: String,
+    /// The bridged channel.
+    pub channel: Channel,
+    /// The external protocol and its parameters.
+    #[serde(flatten)]
+    pub protocol: Protocol,
+}
+
+/// An external protocol to which a channel may be bridged.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "protocol", rename_all = "lowercase")]
+pub enum Protocol {
+    /// A Matrix room, accessed via the client-server API of a homeserver.
+    Matrix {
+        /// The base URL of the homeserver (e.g. `https://matrix.org`).
+        homeserver: String,
+        /// The room ID (e.g. `!abcdef:matrix.org`).
+        room: String,
+        /// The access token of the bridging Matrix user.
+        access_token: String,
+    },
+}
+
+/// The external side of a bridge: messages sent to `outbound` are delivered
+/// to the external endpoint, while messages received from the endpoint are
+/// yielded by `inbound`.
+pub struct Endpoint {
+    pub outbound: UnboundedSender<BridgeMessage>,
+    pub inbound: UnboundedReceiver<BridgeMessage>,
+}
+
+impl Protocol {
+    /// Connect to the external endpoint.
+    fn connect(&self) -> Result<Endpoint, String> {
+        match self {
+            #[cfg(feature = "matrix")]
+            Protocol::Matrix {
+                homeserver,
+                room,
+                access_token,
+            } => Ok(crate::matrix::connect(homeserver, room, access_token)),
+            #[cfg(not(feature = "matrix"))]
+            Protocol::Matrix { .. } => Err(
+                "matrix bridges require cabin to be built with the `matrix` feature".to_string(),
+            ),
+        }
+    }
+}
+
+/// Bridge the given channel to the external endpoint of the given protocol.
+///
+/// Only posts published after the bridge is started are forwarded. Posts
+/// which were published by the bridge itself are never forwarded, preventing
+/// loops.
+pub fn spawn<S: Store>(
+    cable: CableManager<S>,
+    channel: Channel,
+    protocol: &Protocol,
+) -> Result<(), String> {
+    let Endpoint {
+        outbound,
+        mut inbound,
+    } = protocol.connect()?;
+    let start = SystemClock.now().map_err(|err| err.to_string())?;
+
+    // Hashes of the posts published by the bridge.
+    let bridged: Arc<Mutex<HashSet<Hash>>> = Arc::default();
+
+    // Forward text posts from the channel to the external endpoint.
+    let mut channel_cable = cable.clone();
+    let opts = ChannelOptions {
+        channel: channel.clone(),
+        time_start: start,
+        time_end: 0,
+        limit: 4096,
+    };
+    let forwarded = bridged.clone();
+    task::spawn(async move {
+        let mut stream = match channel_cable.open_channel(&opts).await {
+            Ok(stream) => stream,
+            Err(err) => {
+                error!("Failed to open bridged channel {}: {}", opts.channel, err);
+                return;
+            }
+        };
+
+        while let Some(Ok(post)) = stream.next().await {
+            let is_bridged = match post.hash() {
+                Ok(hash) => forwarded.lock().await.contains(&hash),
+                Err(_) => false,
+            };
+            if post.header.timestamp < start || is_bridged {
+                continue;
+            }
+
+            if let PostBody::Text { text, .. } = post.body {
+                let public_key = post.header.public_key;
+                let author = channel_cable
+                    .store
+                    .get_peer_name_and_hash(&public_key)
+                    .await
+                    .map(|(name, _hash)| name)
+                    .unwrap_or_else(|| hex::to(&public_key[..4]));
+                if outbound
+                    .unbounded_send(BridgeMessage { author, text })
+                    .is_err()
+                {
+                    warn!("Bridge endpoint for {} closed", opts.channel);
+                    return;
+                }
+            }
+        }
+    });
+
+    // Publish messages from the external endpoint to the channel.
+    let mut cable = cable;
+    task::spawn(async move {
+        while let Some(msg) = inbound.next().await {
+            let text = format!("<{}> {}", msg.author, msg.text);
+            // The lock is held while publishing so that the post cannot be
+            // forwarded before its hash has been recorded.
+            let mut bridged = bridged.lock().await;
+            match client::post_text(&mut cable, &channel, &text).await {
+                Ok(hash) => {
+                    bridged.insert(hash);
+                }
+                Err(err) => error!("Failed to publish bridged message: {}", err),
+            }
+        }
+    });
+
+    Ok(())
+}
@@ -0,0 +1,285 @@
+//! A headless client for building bots and other tools on top of cabin.
+//!
+//! The `Client` manages one cable instance per cabal and exposes the posting
+//! and channel management operations used by the TUI, without requiring a
+//! terminal or an input loop.
+
+use std::{collections::HashMap, io, path::PathBuf};
+
+use async_std::{net, prelude::*, sync::Arc, task};
+use cable::{error::Error, post::Post, ChannelOptions, Hash};
+use cable_core::{CableManager, Store};
+use futures::io::{AsyncRead, AsyncWrite};
+use log::{debug, error};
+
+use crate::{
+    hex,
+    metrics::{self, Metered},
+    paths,
+    time::{self, Clock, SystemClock},
+    ui::Addr,
+};
+
+/// A function returning a new store for the cabal with the given address.
+///
+/// The function is called once for each cabal added to a `Client`. Stores
+/// must be namespaced by cabal: the posts of different cabals are never
+/// kept in the same store. Stores which persist to disk should keep the data
+/// of each cabal in the directory returned by `store_dir()`, as
+/// `store::open()` and `store::save()` do.
+pub type StorageFn<S> = Box<dyn Fn(&Addr) -> Box<S>>;
+
+/// Return the directory in which a store persisting to disk keeps the data
+/// of the cabal with the given address: a subdirectory of `cabals` in the
+/// data directory, named by the hex-encoded address.
+pub fn store_dir(address: &Addr) -> PathBuf {
+    paths::data_dir().join("cabals").join(hex::to(address))
+}
+
+/// Publish a text post to a channel with the given cable manager, returning
+/// the hash of the post.
+///
+/// This is `Client::post` for tasks which hold a clone of the cable manager
+/// of a cabal rather than the client.
+pub async fn post_text<S: Store>(
+    cable: &mut CableManager<S>,
+    channel: &str,
+    text: &str,
+) -> Result<Hash, Error> {
+    let hash = cable.post_text(channel, text).await?;
+    metrics::POSTS_PUBLISHED.inc();
+
+    Ok(hash)
+}
+
+/// The number of posts requested when opening a channel.
+const CHANNEL_LIMIT: u64 = 4096;
+
+/// The maximum length (in bytes) of the text of a single post, as defined by
+/// the cable specification.
+pub const MAX_TEXT_LEN: usize = 16 * 1024;
+
+/// Split the given text into parts no longer than the given number of bytes,
+/// each of which may be published as a separate post.
+///
+/// Where possible, the text is split after the last line break (or else the
+/// last space) which fits within the limit.
+pub fn split_text(text: &str, max_len: usize) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while rest.len() > max_len {
+        let mut end = max_len;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let head = &rest[..end];
+        let split = head
+            .rfind('\n')
+            .or_else(|| head.rfind(' '))
+            .map(|i| i + 1)
+            .unwrap_or(end);
+
+        let part = rest[..split].trim_end();
+        if !part.is_empty() {
+            parts.push(part);
+        }
+        rest = &rest[split..];
+    }
+    if !rest.trim().is_empty() {
+        parts.push(rest);
+    }
+
+    parts
+}
+
+pub struct Client<S: Store> {
+    cables: HashMap<Addr, CableManager<S>>,
+    clock: Arc<dyn Clock>,
+    storage_fn: StorageFn<S>,
+}
+
+impl<S> Client<S>
+where
+    S: Store,
+{
+    pub fn new(storage_fn: StorageFn<S>) -> Self {
+        Self {
+            cables: HashMap::new(),
+            clock: Arc::new(SystemClock),
+            storage_fn,
+        }
+    }
+
+    /// Read the current time from the given clock rather than the system
+    /// clock, e.g. when calculating the start of channel time range
+    /// requests.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Add a cabal with the given address, creating a new store for it.
+    ///
+    /// Adding a cabal which is already known has no effect.
+    pub fn add_cabal(&mut self, address: &Addr) {
+        if !self.cables.contains_key(address) {
+            let store = (self.storage_fn)(address);
+            self.cables
+                .insert(address.to_owned(), CableManager::new(*store));
+        }
+    }
+
+    /// Determine whether a cabal with the given address has been added.
+    pub fn has_cabal(&self, address: &Addr) -> bool {
+        self.cables.contains_key(address)
+    }
+
+    /// Return the addresses of all added cabals.
+    pub fn cabals(&self) -> impl Iterator<Item = &Addr> {
+        self.cables.keys()
+    }
+
+    /// Return the cable manager of the cabal with the given address.
+    pub fn cable(&self, address: &Addr) -> Option<&CableManager<S>> {
+        self.cables.get(address)
+    }
+
+    /// Return the cable manager of the cabal with the given address, or an
+    /// error if the cabal has not been added.
+    fn cable_mut(&mut self, address: &Addr) -> Result<&mut CableManager<S>, Error> {
+        self.cables.get_mut(address).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("unknown cabal: {}", hex::to(address)),
+            )
+            .into()
+        })
+    }
+
+    /// Connect to a peer at the given TCP address (host:port) and exchange
+    /// posts of the given cabal with them in a background task.
+    pub async fn connect(&mut self, address: &Addr, tcp_addr: &str) -> Result<(), Error> {
+        self.cable_mut(address)?;
+        let stream = net::TcpStream::connect(tcp_addr).await?;
+
+        self.connect_stream(address, stream)
+    }
+
+    /// Exchange posts of the given cabal with the peer at the other end of
+    /// the given stream in a background task.
+    ///
+    /// Any duplex byte stream can be used, such as one end of
+    /// `transport::memory_pair()`.
+    pub fn connect_stream<T>(&mut self, address: &Addr, stream: T) -> Result<(), Error>
+    where
+        T: AsyncRead + AsyncWrite + Clone + Unpin + Send + Sync + 'static,
+    {
+        let cable = self.cable_mut(address)?.clone();
+
+        task::spawn(async move {
+            if let Err(err) = cable.listen(Metered::new(stream)).await {
+                error!("Cable stream listener error: {}", err);
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Listen for incoming TCP connections on the given address (host:port)
+    /// and exchange posts of the given cabal with each peer in a background
+    /// task.
+    pub async fn listen(&mut self, address: &Addr, tcp_addr: &str) -> Result<(), Error> {
+        let cable = self.cable_mut(address)?.clone();
+        let listener = net::TcpListener::bind(tcp_addr).await?;
+
+        task::spawn(async move {
+            let mut incoming = listener.incoming();
+            while let Some(stream) = incoming.next().await {
+                debug!("Received an incoming TCP connection");
+                if let Ok(stream) = stream {
+                    let cable = cable.clone();
+                    task::spawn(async move {
+                        if let Err(err) = cable.listen(Metered::new(stream)).await {
+                            error!("Cable stream listener error: {}", err);
+                        }
+                    });
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Join a channel, publishing a `post/join` if the local peer is not
+    /// already a member.
+    ///
+    /// Returns a stream of the stored and received posts of the channel from
+    /// the past two weeks.
+    pub async fn join(
+        &mut self,
+        address: &Addr,
+        channel: &str,
+    ) -> Result<impl Stream<Item = Result<Post, Error>> + Send + Unpin, Error> {
+        let time_start = time::two_weeks_ago(self.clock.as_ref())?;
+        let cable = self.cable_mut(address)?;
+        if let Some((public_key, _private_key)) = cable.store.get_keypair().await {
+            if !cable
+                .store
+                .is_channel_member(&channel.to_owned(), &public_key)
+                .await
+            {
+                cable.post_join(channel).await?;
+            }
+        }
+
+        let opts = ChannelOptions {
+            channel: channel.to_owned(),
+            time_start,
+            time_end: 0,
+            limit: CHANNEL_LIMIT,
+        };
+
+        cable.open_channel(&opts).await
+    }
+
+    /// Leave a channel, cancelling any active channel time range requests
+    /// and publishing a `post/leave` if the local peer is a member.
+    pub async fn leave(&mut self, address: &Addr, channel: &str) -> Result<(), Error> {
+        let cable = self.cable_mut(address)?;
+        cable.close_channel(&channel.to_owned()).await?;
+        if let Some((public_key, _private_key)) = cable.store.get_keypair().await {
+            if cable
+                .store
+                .is_channel_member(&channel.to_owned(), &public_key)
+                .await
+            {
+                cable.post_leave(channel).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Publish a text post to a channel, returning the hash of the post.
+    ///
+    /// Text longer than `MAX_TEXT_LEN` is rejected by cable; see
+    /// `split_text`.
+    pub async fn post(&mut self, address: &Addr, channel: &str, text: &str) -> Result<Hash, Error> {
+        post_text(self.cable_mut(address)?, channel, text).await
+    }
+
+    /// Publish a `post/topic` to set the topic of a channel.
+    pub async fn set_topic(
+        &mut self,
+        address: &Addr,
+        channel: &str,
+        topic: &str,
+    ) -> Result<Hash, Error> {
+        self.cable_mut(address)?.post_topic(channel, topic).await
+    }
+
+    /// Publish a `post/info` to set the nickname of the local peer.
+    pub async fn set_nick(&mut self, address: &Addr, nick: &str) -> Result<Hash, Error> {
+        self.cable_mut(address)?.post_info_name(nick).await
+    }
+}
@@ -0,0 +1,75 @@
+//! Completion of the input line.
+//!
+//! The tab key completes the word before the cursor: the name of a command
+//! at the start of the line, or the argument of a command which has a
+//! completer registered in `COMPLETERS`. Pressing it again cycles through the
+//! other candidates.
+
+/// The kind of argument completed for a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Completer {
+    /// Aliases and addresses of known cabals.
+    Cabals,
+    /// Indices and names of windows.
+    Windows,
+    /// Joined channels of the active cabal.
+    Channels,
+    /// Nicknames of peers of the active cabal.
+    Peers,
+}
+
+/// The completer of the first argument of each command (or subcommand).
+const COMPLETERS: [(&str, Completer); 14] = [
+    ("/cabal set", Completer::Cabals),
+    ("/win", Completer::Windows),
+    ("/w", Completer::Windows),
+    ("/split", Completer::Windows),
+    ("/leave", Completer::Channels),
+    ("/publish", Completer::Channels),
+    ("/chanstats", Completer::Channels),
+    ("/whois", Completer::Peers),
+    ("/seen", Completer::Peers),
+    ("/block", Completer::Peers),
+    ("/unblock", Completer::Peers),
+    ("/note", Completer::Peers),
+    ("/unnote", Completer::Peers),
+    ("/ignore", Completer::Peers),
+];
+
+/// Return the completer of the word following the given words of the input
+/// line, if any.
+pub fn completer(words: &[&str]) -> Option<Completer> {
+    let command = words.join(" ");
+    COMPLETERS
+        .iter()
+        .find(|(name, _completer)| *name == command)
+        .map(|(_name, completer)| *completer)
+}
+
+/// Return the candidates which begin with the given prefix (ignoring case),
+/// sorted and without duplicates.
+pub fn matching(prefix: &str, candidates: Vec<String>) -> Vec<String> {
+    let prefix = prefix.to_lowercase();
+    let mut matches: Vec<String> = candidates
+        .into_iter()
+        .filter(|candidate| candidate.to_lowercase().starts_with(&prefix))
+        .collect();
+    matches.sort();
+    matches.dedup();
+
+    matches
+}
+
+/// An ongoing completion, which is continued by pressing the tab key again
+/// while the input line is unchanged.
+#[derive(Debug)]
+pub struct Completion {
+    /// The position in the input value of the word being completed.
+    pub start: usize,
+    /// The candidates for the word.
+    pub candidates: Vec<String>,
+    /// The position of the inserted candidate in the list of candidates.
+    pub current: usize,
+    /// The input value after inserting the candidate.
+    pub value: String,
+}
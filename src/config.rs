@@ -0,0 +1,79 @@
+//! Configuration file.
+//!
+//! The configuration is read from `config.toml` in the cabin configuration
+//! directory (see `paths::config_dir()`) at startup. All sections are
+//! optional.
+
+use std::{collections::BTreeMap, fs, io, net::IpAddr, path::PathBuf};
+
+use serde::Deserialize;
+
+use crate::{
+    bridge::BridgeConfig, feeds::FeedConfig, paths, settings::Settings, webhooks::WebhookConfig,
+};
+
+/// The parsed configuration file.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Config {
+    /// Key chords, keyed by the name of the action they invoke
+    /// (e.g. `next-window = "ctrl-n"`).
+    #[serde(default)]
+    pub keys: BTreeMap<String, String>,
+    /// Initial settings, which may be changed at runtime with `/set`.
+    #[serde(default)]
+    pub settings: Settings,
+    /// Channels bridged to external protocols.
+    #[serde(default)]
+    pub bridges: Vec<BridgeConfig>,
+    /// RSS and Atom feeds whose items are posted to channels.
+    #[serde(default)]
+    pub feeds: Vec<FeedConfig>,
+    /// Webhooks notified of new posts in channels.
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+    /// Patterns (regular expressions) of sensitive lines of input, which are
+    /// not recorded in the input history or echoed in full (e.g.
+    /// `redact = ["^/key import"]`).
+    #[serde(default)]
+    pub redact: Vec<String>,
+    /// IP addresses from which incoming connections are accepted. If set,
+    /// connections from any other address are dropped.
+    pub allow: Option<Vec<IpAddr>>,
+    /// The maximum number of incoming connections accepted by each listener
+    /// at once. Further connections are closed as soon as they are accepted.
+    pub max_incoming: Option<usize>,
+    /// The maximum number of open peer connections, incoming and outgoing.
+    pub max_connections: Option<usize>,
+    /// The STUN server (host:port) queried for the public IP address of the
+    /// local peer by `/listen auto`. If unset, the address of the local
+    /// network interface is used.
+    pub stun: Option<String>,
+    /// The Argon2 hash (as a PHC string) of the passphrase which unlocks
+    /// the UI after `/lock` (e.g. the output of `argon2 SALT -id -e`).
+    pub lock: Option<String>,
+    /// The address (host:port) on which metrics are served, if any.
+    pub metrics: Option<String>,
+    /// The locale of user-facing strings (e.g. `es`), overriding the
+    /// locale of the environment.
+    pub locale: Option<String>,
+}
+
+impl Config {
+    /// Return the path of the configuration file.
+    pub fn path() -> PathBuf {
+        paths::config_dir().join("config.toml")
+    }
+
+    /// Read and parse the configuration file.
+    ///
+    /// Returns the default configuration if the file does not exist.
+    pub fn load() -> io::Result<Self> {
+        let contents = match fs::read_to_string(Self::path()) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err),
+        };
+
+        toml::from_str(&contents).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
@@ -0,0 +1,227 @@
+//! The control socket through which clients attach to a daemon.
+//!
+//! Attached clients send lines of input to the daemon and receive the lines
+//! inserted into its status and channel windows. On Unix the control socket
+//! is a domain socket in the runtime directory; on Windows it is a named
+//! pipe. Its location is printed by `cabin --socket-path`.
+
+use async_std::sync::{Arc, Mutex};
+use futures::channel::mpsc::UnboundedSender;
+
+use crate::ui::Ui;
+
+pub use imp::{attach, socket_path, ControlSocket, Listener};
+
+/// Determine whether the given line of input detaches the client which sent
+/// it, rather than stopping the daemon.
+fn is_detach(line: &str) -> bool {
+    matches!(line.trim(), "/quit" | "/exit" | "/q")
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::{
+        fs,
+        io::{self, Write},
+        net::Shutdown,
+        os::unix::{fs::PermissionsExt, net::UnixStream},
+        path::PathBuf,
+        process, thread,
+    };
+
+    use async_std::{
+        io::BufReader,
+        os::unix::net::UnixListener,
+        prelude::*,
+        sync::{Arc, Mutex},
+        task,
+    };
+    use futures::channel::mpsc::{self, UnboundedSender};
+    use log::{debug, warn};
+
+    use super::is_detach;
+    use crate::{paths, ui::Ui};
+
+    pub type Listener = UnixListener;
+
+    /// Return the path of the control socket.
+    pub fn socket_path() -> PathBuf {
+        paths::runtime_dir().join("cabin.sock")
+    }
+
+    /// The control socket, removed when dropped.
+    pub struct ControlSocket {
+        path: PathBuf,
+    }
+
+    impl ControlSocket {
+        /// Bind the control socket, readable and writable only by the
+        /// current user.
+        ///
+        /// A socket left behind by a daemon which did not shut down cleanly
+        /// is replaced; an error is returned if another daemon is listening
+        /// on it.
+        pub async fn bind() -> io::Result<(Self, Listener)> {
+            let path = socket_path();
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            if path.exists() {
+                if UnixStream::connect(&path).is_ok() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AddrInUse,
+                        format!("another daemon is listening on {}", path.display()),
+                    ));
+                }
+                fs::remove_file(&path)?;
+            }
+
+            let listener = UnixListener::bind(&path).await?;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+
+            Ok((Self { path }, listener))
+        }
+    }
+
+    impl Drop for ControlSocket {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+
+    /// Accept clients on the given listener until it is closed.
+    pub(super) async fn serve(
+        listener: Listener,
+        ui: Arc<Mutex<Ui>>,
+        lines: UnboundedSender<String>,
+    ) {
+        let mut incoming = listener.incoming();
+        while let Some(stream) = incoming.next().await {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    warn!("Failed to accept client: {}", err);
+                    continue;
+                }
+            };
+            debug!("Client attached");
+
+            let (sender, mut receiver) = mpsc::unbounded::<String>();
+            {
+                let mut ui = ui.lock().await;
+                ui.attach(sender);
+                ui.update();
+            }
+
+            let mut writer = stream.clone();
+            task::spawn(async move {
+                while let Some(line) = receiver.next().await {
+                    if writer
+                        .write_all(format!("{}\n", line).as_bytes())
+                        .await
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+
+            let lines = lines.clone();
+            task::spawn(async move {
+                let mut reader = BufReader::new(stream.clone()).lines();
+                while let Some(Ok(line)) = reader.next().await {
+                    if is_detach(&line) || lines.unbounded_send(line).is_err() {
+                        break;
+                    }
+                }
+                // Closing the connection ends the relay to the client.
+                let _ = stream.shutdown(Shutdown::Both);
+                debug!("Client detached");
+            });
+        }
+    }
+
+    /// Attach to a running daemon.
+    ///
+    /// Lines of standard input are sent to the daemon and the lines it
+    /// relays are written to standard output, until either is closed.
+    pub fn attach() -> io::Result<()> {
+        let path = socket_path();
+        let stream = UnixStream::connect(&path).map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("no daemon is listening on {}: {}", path.display(), err),
+            )
+        })?;
+
+        let mut reader = stream.try_clone()?;
+        thread::spawn(move || {
+            let _ = io::copy(&mut reader, &mut io::stdout());
+            // The daemon has stopped or the client has detached.
+            let _ = io::stdout().flush();
+            process::exit(0);
+        });
+
+        let mut writer = stream;
+        io::copy(&mut io::stdin().lock(), &mut writer)?;
+        writer.shutdown(Shutdown::Write)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::{env, io, path::PathBuf};
+
+    use async_std::sync::{Arc, Mutex};
+    use futures::channel::mpsc::UnboundedSender;
+
+    use crate::ui::Ui;
+
+    /// Named pipes cannot be created, so no listener exists.
+    pub enum Listener {}
+
+    /// Return the name of the named pipe of the current user.
+    pub fn socket_path() -> PathBuf {
+        let user = env::var("USERNAME").unwrap_or_else(|_| "default".to_string());
+        PathBuf::from(format!(r"\\.\pipe\cabin-{}", user))
+    }
+
+    fn unsupported() -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            "named pipe control sockets are not yet supported",
+        )
+    }
+
+    pub struct ControlSocket;
+
+    impl ControlSocket {
+        pub async fn bind() -> io::Result<(Self, Listener)> {
+            Err(unsupported())
+        }
+    }
+
+    pub(super) async fn serve(
+        listener: Listener,
+        _ui: Arc<Mutex<Ui>>,
+        _lines: UnboundedSender<String>,
+    ) {
+        match listener {}
+    }
+
+    pub fn attach() -> io::Result<()> {
+        Err(unsupported())
+    }
+}
+
+/// Accept clients attaching via the given listener.
+///
+/// Lines inserted into the windows of the UI are relayed to each client, and
+/// each line received from a client is passed on to the given sender to be
+/// handled as input. Quitting detaches the client rather than stopping the
+/// daemon.
+pub async fn serve(listener: Listener, ui: Arc<Mutex<Ui>>, lines: UnboundedSender<String>) {
+    imp::serve(listener, ui, lines).await
+}
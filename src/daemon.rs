@@ -0,0 +1,91 @@
+//! Support for running cabin as a daemon (for example, under systemd).
+//!
+//! In daemon mode cabin does not require a terminal: the session is restored
+//! from the state directory, readiness is reported via `sd_notify` and the
+//! session is saved again when a `SIGTERM` (or `SIGINT`) is received.
+//!
+//! Clients attach to the daemon via its control socket (see
+//! [`crate::control`]).
+
+use std::{
+    env, fs, io,
+    os::unix::net::UnixDatagram,
+    path::{Path, PathBuf},
+    process,
+};
+
+use async_std::task;
+use signal_hook::{
+    consts::{SIGINT, SIGTERM},
+    iterator::Signals,
+};
+
+use crate::paths;
+
+/// Return the default path of the session restored and saved by the daemon.
+pub fn session_path() -> PathBuf {
+    paths::state_dir().join("session.toml")
+}
+
+/// Send the given state (e.g. `READY=1`) to the service manager.
+///
+/// Does nothing unless `NOTIFY_SOCKET` is set, as is the case for systemd
+/// units of `Type=notify`.
+pub fn notify(state: &str) -> io::Result<()> {
+    let Some(path) = env::var_os("NOTIFY_SOCKET") else {
+        return Ok(());
+    };
+    let socket = UnixDatagram::unbound()?;
+
+    // A leading `@` denotes a socket in the abstract namespace.
+    #[cfg(target_os = "linux")]
+    if let Some(name) = path.to_str().and_then(|path| path.strip_prefix('@')) {
+        use std::os::{linux::net::SocketAddrExt, unix::net::SocketAddr};
+
+        let addr = SocketAddr::from_abstract_name(name)?;
+        socket.send_to_addr(state.as_bytes(), &addr)?;
+        return Ok(());
+    }
+
+    socket.send_to(state.as_bytes(), path)?;
+
+    Ok(())
+}
+
+/// A file containing the process ID of the daemon, removed when dropped.
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// Write the process ID to `cabin.pid` in the runtime directory.
+    pub fn create() -> io::Result<Self> {
+        let dir = paths::runtime_dir();
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("cabin.pid");
+        fs::write(&path, format!("{}\n", process::id()))?;
+
+        Ok(Self { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Wait until the process receives a `SIGTERM` or `SIGINT`.
+pub async fn terminated() -> io::Result<()> {
+    let mut signals = Signals::new([SIGTERM, SIGINT])?;
+    task::spawn_blocking(move || {
+        signals.forever().next();
+    })
+    .await;
+
+    Ok(())
+}
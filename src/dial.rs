@@ -0,0 +1,102 @@
+//! Outgoing TCP connections to hosts with several addresses.
+//!
+//! A host name may resolve to several IPv6 and IPv4 addresses, not all of
+//! which are reachable. Connection attempts are made in the style of Happy
+//! Eyeballs (RFC 8305): the addresses are tried in order, alternating between
+//! IP versions, with each attempt started shortly after the previous one
+//! unless it has already failed. The first connection to succeed is used.
+
+use std::{io, net::SocketAddr, time::Duration};
+
+use async_std::{
+    future,
+    net::{TcpStream, ToSocketAddrs},
+};
+use futures::stream::{FuturesUnordered, StreamExt};
+
+/// The time after which the next address is tried if the previous attempt
+/// has not completed.
+const ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// The time after which a single connection attempt is abandoned.
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Connect to the given host:port, returning the stream and the address to
+/// which it is connected.
+///
+/// If no address accepts the connection, the returned error lists the
+/// failure of each address.
+pub async fn connect(tcp_addr: &str) -> io::Result<(TcpStream, SocketAddr)> {
+    let addrs = interleave(tcp_addr.to_socket_addrs().await?.collect());
+    if addrs.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "host name resolved to no addresses",
+        ));
+    }
+
+    let mut remaining = addrs.into_iter();
+    let mut attempts = FuturesUnordered::new();
+    let mut errors = Vec::new();
+    loop {
+        if let Some(addr) = remaining.next() {
+            attempts.push(attempt(addr));
+        }
+
+        // Wait for an attempt to complete or, if addresses remain, for the
+        // delay before starting the next.
+        let result = if remaining.len() == 0 {
+            attempts.next().await
+        } else {
+            match future::timeout(ATTEMPT_DELAY, attempts.next()).await {
+                Ok(result) => result,
+                Err(_) => continue,
+            }
+        };
+
+        match result {
+            Some((addr, Ok(stream))) => return Ok((stream, addr)),
+            Some((addr, Err(err))) => errors.push(format!("{}: {}", addr, err)),
+            None => break,
+        }
+    }
+
+    // A single failure is reported as is.
+    if let [err] = errors.as_slice() {
+        return Err(io::Error::other(err.to_owned()));
+    }
+    Err(io::Error::other(format!(
+        "no address accepted the connection ({})",
+        errors.join("; ")
+    )))
+}
+
+/// Attempt a connection to a single address.
+async fn attempt(addr: SocketAddr) -> (SocketAddr, io::Result<TcpStream>) {
+    let result = match future::timeout(ATTEMPT_TIMEOUT, TcpStream::connect(addr)).await {
+        Ok(result) => result,
+        Err(_) => Err(io::Error::new(io::ErrorKind::TimedOut, "timed out")),
+    };
+
+    (addr, result)
+}
+
+/// Reorder the given addresses to alternate between IP versions, starting
+/// with the version of the first (preferred) address.
+fn interleave(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let Some(first) = addrs.first() else {
+        return addrs;
+    };
+    let (mut preferred, mut other): (Vec<SocketAddr>, Vec<SocketAddr>) = addrs
+        .iter()
+        .partition(|addr| addr.is_ipv6() == first.is_ipv6());
+    preferred.reverse();
+    other.reverse();
+
+    let mut interleaved = Vec::with_capacity(addrs.len());
+    while !preferred.is_empty() || !other.is_empty() {
+        interleaved.extend(preferred.pop());
+        interleaved.extend(other.pop());
+    }
+    interleaved
+}
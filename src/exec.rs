@@ -0,0 +1,133 @@
+//! Connections made through external programs.
+//!
+//! `/connect exec:COMMAND` runs the command with the shell and speaks cable
+//! over its standard input and output, so that peers can be reached through
+//! SSH tunnels, network namespaces or any other program which forwards a
+//! byte stream (e.g. `exec:"ssh relay nc peer 7000"`). The standard error of
+//! the command is written to the network window.
+
+use std::{
+    io,
+    process::{Child, ChildStderr},
+};
+
+/// The prefix of connection addresses which name a command.
+pub const PREFIX: &str = "exec:";
+
+/// Return the command named by the given connection address, if any, with
+/// any quotes around it removed.
+pub fn command(addr: &str) -> Option<&str> {
+    addr.strip_prefix(PREFIX)
+        .map(|command| command.trim().trim_matches('"'))
+        .filter(|command| !command.is_empty())
+}
+
+/// A running command, which is killed when dropped.
+pub struct Process(Child);
+
+impl Process {
+    /// Take the standard error of the command.
+    pub fn take_stderr(&mut self) -> Option<ChildStderr> {
+        self.0.stderr.take()
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Run the given command with the shell, returning the running command and
+/// a stream connected to its standard input and output.
+pub fn spawn(command: &str) -> io::Result<(Process, imp::Stream)> {
+    let (child, stream) = imp::spawn(command)?;
+
+    Ok((Process(child), stream))
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::{
+        io,
+        os::{fd::OwnedFd, unix::net::UnixStream},
+        process::{Child, Command, Stdio},
+    };
+
+    pub type Stream = async_std::os::unix::net::UnixStream;
+
+    /// Run the given command with the shell, returning the child process
+    /// and a stream connected to its standard input and output.
+    ///
+    /// The command is given one end of a socket pair as both its standard
+    /// input and output, so that the other end can be used asynchronously.
+    /// Its standard error is piped.
+    pub fn spawn(command: &str) -> io::Result<(Child, Stream)> {
+        let (local, remote) = UnixStream::pair()?;
+        let stdin = OwnedFd::from(remote.try_clone()?);
+        let stdout = OwnedFd::from(remote);
+
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::from(stdin))
+            .stdout(Stdio::from(stdout))
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        Ok((child, Stream::from(local)))
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::{
+        io,
+        pin::Pin,
+        process::Child,
+        task::{Context, Poll},
+    };
+
+    use futures::io::{AsyncRead, AsyncWrite};
+
+    /// A stream which cannot be constructed, since commands are not
+    /// supported as transports on Windows.
+    #[derive(Clone)]
+    pub enum Stream {}
+
+    pub fn spawn(_command: &str) -> io::Result<(Child, Stream)> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "commands are not supported as transports on windows",
+        ))
+    }
+
+    impl AsyncRead for Stream {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            match *self {}
+        }
+    }
+
+    impl AsyncWrite for Stream {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            match *self {}
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            match *self {}
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            match *self {}
+        }
+    }
+}
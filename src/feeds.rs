@@ -0,0 +1,220 @@
+//! Posting of RSS and Atom feed items to channels.
+//!
+//! Feeds are configured per channel in the `[[feeds]]` section of the
+//! configuration file. Each feed is fetched periodically and any items which
+//! were not present in the previous fetch are posted to the channel. Items
+//! present when cabin is started are not posted.
+
+use serde::Deserialize;
+
+use cable::Channel;
+use cable_core::{CableManager, Store};
+
+/// The default number of seconds between fetches of a feed.
+fn default_interval() -> u64 {
+    900
+}
+
+/// The configuration of a single feed.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeedConfig {
+    /// The hex-encoded address of the cabal.
+    pub cabal: String,
+    /// The channel to which feed items are posted.
+    pub channel: Channel,
+    /// The URL of the RSS or Atom feed.
+    pub url: String,
+    /// The number of seconds between fetches of the feed.
+    #[serde(default = "default_interval")]
+    #[cfg_attr(not(feature = "feeds"), allow(dead_code))]
+    pub interval: u64,
+}
+
+/// A single item of a feed.
+#[derive(Debug, Clone, PartialEq)]
+struct Item {
+    /// A unique identifier of the item: the guid (RSS) or id (Atom), falling
+    /// back to the link or title.
+    id: String,
+    title: String,
+    link: Option<String>,
+}
+
+/// Periodically fetch the configured feed and post new items to its
+/// channel.
+#[cfg(feature = "feeds")]
+pub fn spawn<S: Store>(cable: CableManager<S>, config: FeedConfig) -> Result<(), String> {
+    use std::{collections::HashSet, time::Duration};
+
+    use async_std::task;
+    use log::{error, warn};
+
+    use crate::{client, i18n::tr};
+
+    if config.interval == 0 {
+        return Err(tr("the feed interval must be at least one second").to_string());
+    }
+
+    let mut cable = cable;
+    task::spawn(async move {
+        // Identifiers of the items seen in previous fetches; `None` until the
+        // first successful fetch.
+        let mut seen: Option<HashSet<String>> = None;
+        loop {
+            let url = config.url.clone();
+            let fetched = task::spawn_blocking(move || {
+                ureq::get(&url)
+                    .timeout(Duration::from_secs(30))
+                    .call()
+                    .map_err(|err| err.to_string())?
+                    .into_string()
+                    .map_err(|err| err.to_string())
+            })
+            .await;
+
+            match fetched {
+                Ok(xml) => {
+                    let items = parse_items(&xml);
+                    if let Some(seen) = &seen {
+                        // Items are listed newest first; post the oldest
+                        // first.
+                        for item in items.iter().rev().filter(|item| !seen.contains(&item.id)) {
+                            let text = match &item.link {
+                                Some(link) => format!("{} {}", item.title, link),
+                                None => item.title.to_owned(),
+                            };
+                            if let Err(err) =
+                                client::post_text(&mut cable, &config.channel, &text).await
+                            {
+                                error!("Failed to post feed item: {}", err);
+                            }
+                        }
+                    }
+                    seen = Some(items.into_iter().map(|item| item.id).collect());
+                }
+                Err(err) => warn!("Failed to fetch feed {}: {}", config.url, err),
+            }
+
+            task::sleep(Duration::from_secs(config.interval)).await;
+        }
+    });
+
+    Ok(())
+}
+
+/// Periodically fetch the configured feed and post new items to its
+/// channel.
+#[cfg(not(feature = "feeds"))]
+pub fn spawn<S: Store>(_cable: CableManager<S>, _config: FeedConfig) -> Result<(), String> {
+    use crate::i18n::tr;
+
+    Err(tr("feeds require cabin to be built with the `feeds` feature").to_string())
+}
+
+/// Parse the items of an RSS (`<item>`) or Atom (`<entry>`) feed.
+///
+/// This is a deliberately minimal parser which only extracts the title, link
+/// and identifier of each item.
+#[cfg_attr(not(feature = "feeds"), allow(dead_code))]
+fn parse_items(xml: &str) -> Vec<Item> {
+    let tag = if xml.contains("<entry") {
+        "entry"
+    } else {
+        "item"
+    };
+
+    elements(xml, tag)
+        .into_iter()
+        .filter_map(|element| {
+            let title = element_text(element, "title").unwrap_or_default();
+            let link = element_text(element, "link")
+                .filter(|link| !link.is_empty())
+                .or_else(|| attribute(element, "link", "href"));
+            let id = element_text(element, "guid")
+                .or_else(|| element_text(element, "id"))
+                .or_else(|| link.clone())
+                .or_else(|| Some(title.clone()).filter(|title| !title.is_empty()))?;
+
+            Some(Item { id, title, link })
+        })
+        .collect()
+}
+
+/// Return the contents of each element with the given tag name.
+fn elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let (open, close) = (format!("<{}", tag), format!("</{}>", tag));
+
+    let mut elements = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = find_tag(rest, &open) {
+        let after = &rest[start..];
+        let Some(body_start) = after.find('>') else {
+            break;
+        };
+        // A self-closing element has no contents.
+        if after[..body_start].ends_with('/') {
+            elements.push("");
+            rest = &after[body_start + 1..];
+            continue;
+        }
+        let body = &after[body_start + 1..];
+        let Some(end) = body.find(&close) else {
+            break;
+        };
+        elements.push(&body[..end]);
+        rest = &body[end + close.len()..];
+    }
+
+    elements
+}
+
+/// Find the start of the given opening tag (e.g. `<link`), ignoring tags
+/// which merely share a prefix (e.g. `<linkage`).
+fn find_tag(xml: &str, open: &str) -> Option<usize> {
+    let mut offset = 0;
+    while let Some(index) = xml[offset..].find(open) {
+        let start = offset + index;
+        match xml[start + open.len()..].chars().next() {
+            Some(c) if c == '>' || c == '/' || c.is_whitespace() => return Some(start),
+            _ => offset = start + open.len(),
+        }
+    }
+
+    None
+}
+
+/// Return the decoded text content of the first element with the given tag
+/// name.
+fn element_text(xml: &str, tag: &str) -> Option<String> {
+    let text = elements(xml, tag).into_iter().next()?.trim();
+    let text = text
+        .strip_prefix("<![CDATA[")
+        .and_then(|text| text.strip_suffix("]]>"))
+        .map(|text| text.to_owned())
+        .unwrap_or_else(|| decode_entities(text));
+
+    Some(text.trim().to_owned())
+}
+
+/// Return the value of the given attribute of the first element with the
+/// given tag name.
+fn attribute(xml: &str, tag: &str, name: &str) -> Option<String> {
+    let start = find_tag(xml, &format!("<{}", tag))?;
+    let element = &xml[start..start + xml[start..].find('>')?];
+    let pattern = format!("{}=", name);
+    let value = &element[element.find(&pattern)? + pattern.len()..];
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let value = &value[1..];
+
+    Some(decode_entities(&value[..value.find(quote)?]))
+}
+
+/// Decode the predefined XML entities.
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
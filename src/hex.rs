@@ -1,18 +1,37 @@
 pub fn to(addr: &[u8]) -> String {
     addr.iter()
-        .map(|byte| format!["{:x}", byte])
+        .map(|byte| format!["{:02x}", byte])
         .collect::<Vec<String>>()
         .join("")
 }
 
+/// Decode the given hex string, returning `None` if it has an odd length or
+/// contains anything other than hex digits.
 pub fn from(s: &str) -> Option<Vec<u8>> {
-    let mut result = Vec::with_capacity((s.len() + 1) / 2);
-    for i in 0..(s.len() + 1) / 2 {
-        if let Ok(b) = u8::from_str_radix(&s[i * 2..=(i * 2 + 1).min(s.len())], 16) {
-            result.push(b);
-        } else {
-            return None;
-        }
+    if s.len() % 2 != 0 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            // Both bytes are ASCII hex digits, so the pair is valid UTF-8.
+            u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_rejects_odd_length_and_non_hex_input() {
+        assert_eq!(from("0aff"), Some(vec![0x0a, 0xff]));
+        assert_eq!(from(""), Some(vec![]));
+        assert_eq!(from("abc"), None);
+        assert_eq!(from("é"), None);
+        assert_eq!(from("aé"), None);
+        assert_eq!(from("+1"), None);
     }
-    Some(result)
 }
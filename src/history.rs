@@ -0,0 +1,50 @@
+//! Persistence of the input history across sessions.
+//!
+//! The history is kept in a file of the data directory rather than in a
+//! cable store: each store holds the posts of a single cabal, whereas the
+//! history spans every cabal, along with commands which belong to none.
+
+use std::{fs, io, path::PathBuf};
+
+use crate::paths;
+
+/// The maximum number of input history entries which are persisted.
+const MAX_ENTRIES: usize = 1000;
+
+/// Return the path of the input history file.
+fn path() -> PathBuf {
+    paths::data_dir().join("history")
+}
+
+/// Load the persisted input history, oldest entry first.
+///
+/// Returns an empty history if no history has been persisted.
+pub fn load() -> io::Result<Vec<String>> {
+    match fs::read_to_string(path()) {
+        Ok(contents) => Ok(contents.lines().map(|line| line.to_string()).collect()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err),
+    }
+}
+
+/// Persist the given input history, retaining only the most recent entries.
+pub fn save(history: &[String]) -> io::Result<()> {
+    let path = path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let skip = history.len().saturating_sub(MAX_ENTRIES);
+    let mut contents = history[skip..].join("\n");
+    contents.push('\n');
+
+    fs::write(path, contents)
+}
+
+/// Remove the persisted input history.
+pub fn clear() -> io::Result<()> {
+    match fs::remove_file(path()) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
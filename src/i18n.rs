@@ -0,0 +1,107 @@
+//! Translation of user-facing strings.
+//!
+//! Strings are looked up by their English text in the catalog of the
+//! selected locale, falling back to the English text if the catalog does not
+//! contain a translation. Catalogs are TOML files in the `locales` directory
+//! of the repository and are included at compile time.
+//!
+//! The locale is selected by the `locale` key of the configuration file or,
+//! if that is not set, by the `LC_ALL`, `LC_MESSAGES` or `LANG` environment
+//! variables.
+
+use std::{collections::HashMap, env, fmt::Display, sync::RwLock};
+
+use log::debug;
+
+/// The catalogs of all supported locales, keyed by language code.
+const CATALOGS: [(&str, &str); 1] = [("es", include_str!("../locales/es.toml"))];
+
+/// The catalog of the selected locale, or `None` for English.
+static CATALOG: RwLock<Option<&'static HashMap<String, String>>> = RwLock::new(None);
+
+/// Select the locale with the given name (e.g. `es` or `es_ES.UTF-8`).
+///
+/// Only the language code is taken into account. Returns an error if no
+/// catalog exists for the language.
+pub fn set_locale(name: &str) -> Result<(), String> {
+    let language = name.split(['_', '.', '@']).next().unwrap_or_default();
+    let catalog = match language {
+        "" | "C" | "POSIX" | "en" => None,
+        _ => {
+            let (_code, source) = CATALOGS
+                .iter()
+                .find(|(code, _source)| *code == language)
+                .ok_or_else(|| format!("no translation available for locale {}", name))?;
+            let catalog: HashMap<String, String> =
+                toml::from_str(source).map_err(|err| err.to_string())?;
+
+            // The locale is selected at most a few times per process, so the
+            // catalog is leaked in order to hand out static references.
+            Some(&*Box::leak(Box::new(catalog)))
+        }
+    };
+    *CATALOG.write().unwrap() = catalog;
+
+    Ok(())
+}
+
+/// Select the locale named by the environment, if a catalog exists for it.
+pub fn set_locale_from_env() {
+    let name = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty());
+    if let Some(name) = name {
+        if let Err(err) = set_locale(&name) {
+            debug!("Using English: {}", err);
+        }
+    }
+}
+
+/// Return the translation of the given string in the selected locale.
+pub fn tr(msg: &'static str) -> &'static str {
+    let catalog = *CATALOG.read().unwrap();
+    catalog
+        .and_then(|catalog| catalog.get(msg))
+        .map(String::as_str)
+        .unwrap_or(msg)
+}
+
+/// Return the translation of the given message template in the selected
+/// locale, with each `{}` replaced by the next of the given arguments.
+///
+/// Translations may reorder the text around the placeholders, but not the
+/// arguments themselves.
+pub fn tr_args(template: &'static str, args: &[&dyn Display]) -> String {
+    let mut parts = tr(template).split("{}");
+    let mut msg = parts.next().unwrap_or_default().to_string();
+    let mut args = args.iter();
+    for part in parts {
+        if let Some(arg) = args.next() {
+            msg.push_str(&arg.to_string());
+        }
+        msg.push_str(part);
+    }
+
+    msg
+}
+
+/// Return a usage message for the given command syntax.
+pub fn usage(syntax: &str) -> String {
+    format!("{} {}", tr("usage:"), syntax)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arguments_fill_the_placeholders_in_order() {
+        assert_eq!(
+            tr_args("failed to connect to {}: {}", &[&"host:7822", &"refused"]),
+            "failed to connect to host:7822: refused"
+        );
+        assert_eq!(tr_args("{} problems found", &[&3]), "3 problems found");
+        assert_eq!(tr_args("no placeholders", &[&1]), "no placeholders");
+    }
+}
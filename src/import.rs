@@ -0,0 +1,212 @@
+//! Import of the history of the JavaScript cabal clients.
+//!
+//! `cabin import FILE CABAL` reads the JSON export of a cabal written by
+//! cabal-desktop or cabal-cli into the store of a cabal. The export holds the
+//! messages of every feed of the cabal, either as a JSON array or as one
+//! message per line, e.g.
+//!
+//! ```json
+//! {"key": "a1b2...", "seq": 7, "value": {"type": "chat/text", "timestamp": 1600000000000, "content": {"channel": "default", "text": "hi"}}}
+//! ```
+//!
+//! Cable posts are signed by their author, so messages cannot be imported as
+//! posts of the peers who wrote them. As with bridged messages, the text of
+//! each message is published by the local peer instead, prefixed with the
+//! date and name of its author (taken from the `about` messages of its feed).
+
+use std::path::Path;
+
+#[cfg(feature = "import")]
+use cable::Channel;
+
+/// A text message of an export.
+#[cfg(feature = "import")]
+#[derive(Debug, Clone, PartialEq)]
+struct Message {
+    channel: Channel,
+    /// The name of the author, or the start of the key of their feed if
+    /// they never set one.
+    author: String,
+    /// The time at which the message was written, in milliseconds since the
+    /// Unix epoch.
+    timestamp: u64,
+    text: String,
+}
+
+/// Import the text messages of the export at the given path into the store
+/// of the cabal with the given hex-encoded address, returning the number of
+/// imported messages.
+///
+/// The store is saved with `store::save()`, so cabin must not be running
+/// with the same cabal.
+#[cfg(feature = "import")]
+pub async fn run(path: &Path, cabal: &str) -> Result<usize, String> {
+    use std::{collections::HashSet, fs};
+
+    use cable_core::{CableManager, MemoryStore, Store};
+
+    use crate::{client, hex, i18n::tr_args, store, time};
+
+    let address =
+        hex::from(cabal).ok_or_else(|| tr_args("invalid cabal address: {}", &[&cabal]))?;
+    let json = fs::read_to_string(path)
+        .map_err(|err| tr_args("failed to read {}: {}", &[&path.display(), &err]))?;
+    let messages = parse(&json)?;
+
+    let store = store::open::<MemoryStore>(&address)
+        .await
+        .map_err(|err| tr_args("failed to open the store of {}: {}", &[&cabal, &err]))?;
+    let mut cable = CableManager::new(store);
+
+    let mut joined = HashSet::new();
+    for message in &messages {
+        if joined.insert(message.channel.clone()) {
+            let member = match cable.store.get_keypair().await {
+                Some((public_key, _private_key)) => {
+                    cable
+                        .store
+                        .is_channel_member(&message.channel, &public_key)
+                        .await
+                }
+                None => false,
+            };
+            if !member {
+                cable
+                    .post_join(&message.channel)
+                    .await
+                    .map_err(|err| err.to_string())?;
+            }
+        }
+
+        let text = format!(
+            "[{}] <{}> {}",
+            time::format_date(message.timestamp),
+            message.author,
+            message.text
+        );
+        client::post_text(&mut cable, &message.channel, &text)
+            .await
+            .map_err(|err| err.to_string())?;
+    }
+
+    store::save(&address, &cable.store)
+        .await
+        .map_err(|err| tr_args("failed to save the store of {}: {}", &[&cabal, &err]))?;
+
+    Ok(messages.len())
+}
+
+/// Import the text messages of the export at the given path into the store
+/// of the cabal with the given hex-encoded address, returning the number of
+/// imported messages.
+#[cfg(not(feature = "import"))]
+pub async fn run(_path: &Path, _cabal: &str) -> Result<usize, String> {
+    use crate::i18n::tr;
+
+    Err(tr("importing requires cabin to be built with the `import` feature").to_string())
+}
+
+/// Parse the text messages of an export, oldest first.
+///
+/// Messages of other types are skipped, apart from `about` messages, which
+/// name the author of a feed.
+#[cfg(feature = "import")]
+fn parse(json: &str) -> Result<Vec<Message>, String> {
+    use std::collections::HashMap;
+
+    use serde_json::Value;
+
+    use crate::i18n::tr_args;
+
+    let entries: Vec<Value> = match serde_json::from_str(json) {
+        Ok(Value::Array(entries)) => entries,
+        _ => json
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()
+            .map_err(|err| tr_args("invalid export: {}", &[&err]))?,
+    };
+
+    let mut names = HashMap::new();
+    let mut messages = Vec::new();
+    for entry in &entries {
+        let (Some(key), Some(value)) = (entry["key"].as_str(), entry.get("value")) else {
+            continue;
+        };
+        let content = &value["content"];
+        match value["type"].as_str() {
+            Some("about") => {
+                if let Some(name) = content["name"].as_str() {
+                    names.insert(key, name.to_string());
+                }
+            }
+            Some("chat/text") => {
+                let (Some(channel), Some(text)) =
+                    (content["channel"].as_str(), content["text"].as_str())
+                else {
+                    continue;
+                };
+                messages.push(Message {
+                    channel: channel.to_string(),
+                    author: key.to_string(),
+                    timestamp: value["timestamp"].as_u64().unwrap_or_default(),
+                    text: text.to_string(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    for message in &mut messages {
+        message.author = match names.get(message.author.as_str()) {
+            Some(name) => name.to_owned(),
+            None => message.author.chars().take(8).collect(),
+        };
+    }
+    messages.sort_by_key(|message| message.timestamp);
+
+    Ok(messages)
+}
+
+#[cfg(all(test, feature = "import"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_messages_are_named_and_sorted() {
+        let json = r#"
+{"key": "a1b2c3d4e5f6", "seq": 2, "value": {"type": "chat/text", "timestamp": 2000, "content": {"channel": "default", "text": "second"}}}
+{"key": "a1b2c3d4e5f6", "seq": 1, "value": {"type": "about", "timestamp": 500, "content": {"name": "alice"}}}
+{"key": "0f0e0d0c0b0a", "seq": 1, "value": {"type": "chat/text", "timestamp": 1000, "content": {"channel": "garden", "text": "first"}}}
+{"key": "0f0e0d0c0b0a", "seq": 2, "value": {"type": "chat/topic", "timestamp": 3000, "content": {"channel": "garden", "text": "tomatoes"}}}
+"#;
+
+        let messages = parse(json).unwrap();
+        assert_eq!(
+            messages,
+            vec![
+                Message {
+                    channel: "garden".to_string(),
+                    author: "0f0e0d0c".to_string(),
+                    timestamp: 1000,
+                    text: "first".to_string(),
+                },
+                Message {
+                    channel: "default".to_string(),
+                    author: "alice".to_string(),
+                    timestamp: 2000,
+                    text: "second".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn arrays_of_messages_are_accepted() {
+        let json = r#"[{"key": "ab", "value": {"type": "chat/text", "timestamp": 1, "content": {"channel": "default", "text": "hi"}}}]"#;
+
+        assert_eq!(parse(json).unwrap().len(), 1);
+        assert!(parse("{ not json").is_err());
+    }
+}
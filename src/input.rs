@@ -6,6 +6,32 @@ pub struct Input {
     pub history: Vec<String>,
     pub value: String,
     pub cursor: usize,
+    /// The position in the history of the displayed entry, if the history is
+    /// being navigated.
+    history_index: Option<usize>,
+    /// The input value prior to navigating the history.
+    draft: String,
+    /// The ongoing reverse search of the history, if any.
+    search: Option<Search>,
+    /// The bytes of a partially received escape sequence.
+    escape: Option<Vec<u8>>,
+    /// Whether vi-style modal editing is enabled.
+    vi: bool,
+    /// The active editing mode, if vi-style modal editing is enabled.
+    mode: Mode,
+    /// A normal mode operator awaiting a motion (e.g. the first `d` of
+    /// `dd`).
+    operator: Option<char>,
+    /// Whether keys are captured (by the message selection mode) rather than
+    /// editing the input line.
+    capture: bool,
+    /// Recently killed (cut) text, most recent last.
+    kill_ring: Vec<String>,
+    /// The input value and cursor prior to each undoable edit, most recent
+    /// last.
+    undo: Vec<(String, usize)>,
+    /// The kind of the most recent edit.
+    last_edit: Edit,
     decoder: Decoder,
     queue: VecDeque<InputEvent>,
 }
@@ -13,61 +39,648 @@ pub struct Input {
 pub enum InputEvent {
     Line(String),
     KeyCode(KeyCode),
+    Chord(Chord),
+    /// A key pressed while keys are captured.
+    Captured(Key),
+}
+
+/// Enable the kitty keyboard protocol (with disambiguated escape codes) and
+/// xterm's modifyOtherKeys, so that modifier combinations are reported
+/// unambiguously. Terminals supporting neither ignore these sequences.
+pub const ENABLE_KEYBOARD_PROTOCOL: &str = "\x1b[>1u\x1b[>4;1m";
+
+/// Restore the keyboard protocol which was active prior to
+/// `ENABLE_KEYBOARD_PROTOCOL`.
+pub const DISABLE_KEYBOARD_PROTOCOL: &str = "\x1b[<u\x1b[>4;0m";
+
+/// The maximum length of an escape sequence which is parsed by `Input`;
+/// longer sequences are passed to the decoder as-is.
+const MAX_ESCAPE_LEN: usize = 32;
+
+/// The editing mode of the input line when vi-style modal editing is
+/// enabled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Mode {
+    /// Printable characters are inserted into the input value.
+    #[default]
+    Insert,
+    /// Printable characters are interpreted as commands.
+    Normal,
+}
+
+/// An incremental reverse search of the history (Ctrl+R), as in readline.
+#[derive(Debug)]
+struct Search {
+    /// The text searched for.
+    query: String,
+    /// The position in the history of the displayed match, if any.
+    index: Option<usize>,
+    /// Whether the history contains no (further) match for the query.
+    failed: bool,
+    /// The input value prior to the search.
+    original: String,
+}
+
+/// The maximum number of entries in the kill ring.
+const KILL_RING_LEN: usize = 16;
+
+/// The maximum number of edits which may be undone.
+const UNDO_LEN: usize = 100;
+
+/// A kind of edit of the input value.
+///
+/// Consecutive insertions (or removals) of single characters are undone at
+/// once, as in readline.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Edit {
+    #[default]
+    Other,
+    Insert,
+    Remove,
+}
+
+/// Modifier keys held while pressing a key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub alt: bool,
+    pub ctrl: bool,
+}
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers {
+        shift: false,
+        alt: false,
+        ctrl: false,
+    };
+
+    /// Decode the modifier parameter of an escape sequence (one plus a
+    /// bitmask of the held modifiers).
+    fn decode(param: u32) -> Self {
+        let bits = param.saturating_sub(1);
+        Modifiers {
+            shift: bits & 1 != 0,
+            alt: bits & 2 != 0,
+            ctrl: bits & 4 != 0,
+        }
+    }
+}
+
+/// A key which may be combined with modifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Tab,
+    Backspace,
+    Escape,
+    Delete,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+}
+
+/// A key pressed together with modifiers, as reported by terminals
+/// supporting the kitty keyboard protocol or modifyOtherKeys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chord {
+    pub modifiers: Modifiers,
+    pub key: Key,
+}
+
+impl Chord {
+    /// Parse the parameters and final byte of a CSI escape sequence
+    /// (`ESC [ PARAMS FINAL`) which encodes a key with modifiers.
+    ///
+    /// Returns `None` for sequences without modifier information, which are
+    /// left to the legacy decoder.
+    fn parse_csi(params: &[u8], final_byte: u8) -> Option<Self> {
+        // Only the first sub-parameter (separated by ':') of each parameter
+        // is significant.
+        let params: Vec<u32> = std::str::from_utf8(params)
+            .ok()?
+            .split(';')
+            .map(|param| {
+                param
+                    .split(':')
+                    .next()
+                    .unwrap_or_default()
+                    .parse()
+                    .unwrap_or(1)
+            })
+            .collect();
+
+        let (code, modifiers) = match final_byte {
+            // kitty: CSI code ; modifiers u
+            b'u' => (params[0], params.get(1).copied().unwrap_or(1)),
+            // modifyOtherKeys: CSI 27 ; modifiers ; code ~
+            b'~' if params.len() == 3 && params[0] == 27 => (params[2], params[1]),
+            b'~' if params.len() >= 2 => {
+                let key = match params[0] {
+                    1 | 7 => Key::Home,
+                    3 => Key::Delete,
+                    4 | 8 => Key::End,
+                    5 => Key::PageUp,
+                    6 => Key::PageDown,
+                    _ => return None,
+                };
+                return Some(Chord {
+                    modifiers: Modifiers::decode(params[1]),
+                    key,
+                });
+            }
+            b'A' | b'B' | b'C' | b'D' | b'H' | b'F' if params.len() >= 2 => {
+                let key = match final_byte {
+                    b'A' => Key::Up,
+                    b'B' => Key::Down,
+                    b'C' => Key::Right,
+                    b'D' => Key::Left,
+                    b'H' => Key::Home,
+                    _ => Key::End,
+                };
+                return Some(Chord {
+                    modifiers: Modifiers::decode(params[1]),
+                    key,
+                });
+            }
+            _ => return None,
+        };
+
+        let key = match code {
+            9 => Key::Tab,
+            13 => Key::Enter,
+            27 => Key::Escape,
+            8 | 127 => Key::Backspace,
+            code => Key::Char(char::from_u32(code)?),
+        };
+
+        Some(Chord {
+            modifiers: Modifiers::decode(modifiers),
+            key,
+        })
+    }
 }
 
 impl Input {
     pub fn putc(&mut self, b: u8) {
-        for keycode in self.decoder.write(b) {
-            match keycode {
-                KeyCode::Enter | KeyCode::Linefeed => {
-                    self.queue.push_back(InputEvent::Line(self.value.clone()));
-                    self.value = String::default();
+        // Escape sequences are buffered so that those encoding modifier
+        // combinations can be parsed; all others are passed on to the
+        // decoder.
+        if let Some(mut buf) = self.escape.take() {
+            buf.push(b);
+            match (buf.len(), b) {
+                (2, b'[') => self.escape = Some(buf),
+                (2, b'O') => self.decode(&buf),
+                // A lone escape is flushed once no further input is
+                // waiting (see `flush_escape`), so an escape followed by a
+                // printable character is an alt combination.
+                (2, 0x20..=0x7e) => self.handle_chord(Chord {
+                    modifiers: Modifiers {
+                        alt: true,
+                        ..Modifiers::NONE
+                    },
+                    key: Key::Char(b as char),
+                }),
+                // An escape followed by anything else is a press of the
+                // escape key.
+                (2, _) => {
+                    self.handle_keycode(KeyCode::Escape);
+                    self.decode(&[b]);
                 }
-                KeyCode::Backspace | KeyCode::CtrlH => {
-                    self.remove_left(1);
-                }
-                KeyCode::Delete => {
-                    self.remove_right(1);
-                }
-                KeyCode::ArrowLeft => {
-                    self.cursor = self.cursor.max(1) - 1;
-                }
-                KeyCode::ArrowRight => {
-                    self.cursor = (self.cursor + 1).min(self.value.len());
-                }
-                KeyCode::Home => {
-                    self.cursor = 0;
-                }
-                KeyCode::End => {
-                    self.cursor = self.value.len();
-                }
-                code => {
-                    if let Some(c) = code.printable() {
-                        self.put_str(&c.to_string());
-                    } else {
+                (len, 0x30..=0x3f) if len < MAX_ESCAPE_LEN => self.escape = Some(buf),
+                (len, 0x40..=0x7e) => match Chord::parse_csi(&buf[2..len - 1], b) {
+                    Some(chord) => self.handle_chord(chord),
+                    None => self.decode(&buf),
+                },
+                _ => self.decode(&buf),
+            }
+        } else if b == 0x1b {
+            self.escape = Some(vec![b]);
+        } else if b == 0x1f {
+            // Ctrl+_
+            self.undo();
+        } else {
+            self.decode(&[b]);
+        }
+    }
+
+    /// Handle a pending escape which is not followed by any other byte as a
+    /// press of the escape key.
+    ///
+    /// This is to be called once all available input has been passed to
+    /// `putc`, since the escape key sends a lone escape byte, whereas
+    /// escape sequences arrive in a single read.
+    pub fn flush_escape(&mut self) {
+        if self.escape.as_deref() == Some(&[0x1b]) {
+            self.escape = None;
+            self.handle_keycode(KeyCode::Escape);
+        }
+    }
+
+    /// Pass the given bytes to the legacy decoder and handle the resulting
+    /// key codes.
+    fn decode(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            for keycode in self.decoder.write(*b) {
+                self.handle_keycode(keycode);
+            }
+        }
+    }
+
+    /// Handle a key pressed with modifiers.
+    ///
+    /// Combinations which have a legacy encoding are handled as such;
+    /// Ctrl+Left and Ctrl+Right move the cursor by word. All others are
+    /// emitted as events.
+    fn handle_chord(&mut self, chord: Chord) {
+        let Chord { modifiers, key } = chord;
+        let ctrl = Modifiers {
+            ctrl: true,
+            ..Modifiers::NONE
+        };
+        let shift = Modifiers {
+            shift: true,
+            ..Modifiers::NONE
+        };
+
+        match key {
+            Key::Char(c) if modifiers == Modifiers::NONE || modifiers == shift => {
+                self.handle_char(c);
+            }
+            Key::Char(c) if modifiers == ctrl && c.is_ascii_alphabetic() => {
+                self.decode(&[c.to_ascii_lowercase() as u8 & 0x1f]);
+            }
+            // Ctrl+_ (which some terminals report as Ctrl+/ or Ctrl+-).
+            Key::Char('_' | '/' | '-') if modifiers.ctrl && !modifiers.alt => self.undo(),
+            Key::Enter if modifiers == Modifiers::NONE => self.decode(b"\r"),
+            Key::Tab if modifiers == Modifiers::NONE => self.decode(b"\t"),
+            Key::Backspace if modifiers == Modifiers::NONE => self.decode(&[0x7f]),
+            Key::Escape if modifiers == Modifiers::NONE => {
+                self.handle_keycode(KeyCode::Escape);
+            }
+            Key::Left if modifiers == ctrl => self.cursor = self.word_left(),
+            Key::Right if modifiers == ctrl => self.cursor = self.word_right(),
+            _ => self.queue.push_back(InputEvent::Chord(chord)),
+        }
+    }
+
+    /// Return the position of the start of the word to the left of the
+    /// cursor.
+    fn word_left(&self) -> usize {
+        let c = self.cursor.min(self.value.len());
+        let before = self.value[..c].trim_end();
+        before.rfind(' ').map(|i| i + 1).unwrap_or(0)
+    }
+
+    /// Return the position of the end of the word to the right of the
+    /// cursor.
+    fn word_right(&self) -> usize {
+        let c = self.cursor.min(self.value.len());
+        let after = &self.value[c..];
+        let skipped = after.len() - after.trim_start().len();
+        after[skipped..]
+            .find(' ')
+            .map(|i| c + skipped + i)
+            .unwrap_or(self.value.len())
+    }
+
+    fn handle_keycode(&mut self, keycode: KeyCode) {
+        if self.capture {
+            let key = match keycode {
+                KeyCode::Enter | KeyCode::Linefeed => Key::Enter,
+                KeyCode::Escape => Key::Escape,
+                KeyCode::Backspace | KeyCode::CtrlH => Key::Backspace,
+                KeyCode::ArrowUp => Key::Up,
+                KeyCode::ArrowDown => Key::Down,
+                code => match code.printable() {
+                    Some(c) => Key::Char(c),
+                    // Control keys remain available to keybindings.
+                    None => {
                         self.queue.push_back(InputEvent::KeyCode(code));
+                        return;
                     }
+                },
+            };
+            self.queue.push_back(InputEvent::Captured(key));
+            return;
+        }
+        if self.search.is_some() && self.search_keycode(keycode) {
+            return;
+        }
+
+        match keycode {
+            KeyCode::Enter | KeyCode::Linefeed => {
+                self.queue.push_back(InputEvent::Line(self.value.clone()));
+                self.value = String::default();
+                self.cursor = 0;
+                self.undo.clear();
+                self.last_edit = Edit::Other;
+                self.history_index = None;
+                self.mode = Mode::Insert;
+                self.operator = None;
+            }
+            KeyCode::Escape if self.vi => {
+                if self.mode == Mode::Insert {
+                    self.mode = Mode::Normal;
+                    self.cursor = self.cursor.min(self.value.len()).saturating_sub(1);
+                }
+                self.operator = None;
+            }
+            KeyCode::ArrowUp => {
+                self.history_prev();
+            }
+            KeyCode::ArrowDown => {
+                self.history_next();
+            }
+            KeyCode::Backspace | KeyCode::CtrlH => {
+                self.remove_left(1);
+            }
+            KeyCode::Delete => {
+                self.remove_right(1);
+            }
+            KeyCode::ArrowLeft => {
+                self.cursor = self.cursor.max(1) - 1;
+            }
+            KeyCode::ArrowRight => {
+                self.cursor = (self.cursor + 1).min(self.value.len());
+            }
+            KeyCode::Home => {
+                self.cursor = 0;
+            }
+            KeyCode::End => {
+                self.cursor = self.value.len();
+            }
+            KeyCode::CtrlK => self.kill(self.cursor, self.value.len()),
+            KeyCode::CtrlW => self.kill(self.word_left(), self.cursor),
+            KeyCode::CtrlY => self.yank(),
+            KeyCode::CtrlR => self.start_search(),
+            code => {
+                if let Some(c) = code.printable() {
+                    self.handle_char(c);
+                } else {
+                    self.queue.push_back(InputEvent::KeyCode(code));
                 }
             }
         }
     }
 
+    /// Replace the input value with the previous (older) history entry.
+    fn history_prev(&mut self) {
+        if self.history.is_empty() {
+            return;
+        }
+        let index = match self.history_index {
+            None => {
+                self.draft = self.value.clone();
+                self.history.len() - 1
+            }
+            Some(index) => index.saturating_sub(1),
+        };
+        self.history_index = Some(index);
+        self.value = self.history[index].clone();
+        self.cursor = self.value.len();
+    }
+
+    /// Replace the input value with the next (newer) history entry, or the
+    /// original input value if the newest entry is displayed.
+    fn history_next(&mut self) {
+        match self.history_index {
+            Some(index) if index + 1 < self.history.len() => {
+                self.history_index = Some(index + 1);
+                self.value = self.history[index + 1].clone();
+            }
+            Some(_) => {
+                self.history_index = None;
+                self.value = std::mem::take(&mut self.draft);
+            }
+            None => return,
+        }
+        self.cursor = self.value.len();
+    }
+
+    /// Add the given line to the history, unless it repeats the newest
+    /// entry.
+    pub fn push_history(&mut self, line: String) {
+        if self.history.last() != Some(&line) {
+            self.history.push(line);
+        }
+    }
+
+    /// Start a reverse search of the history.
+    fn start_search(&mut self) {
+        self.record_edit(Edit::Other);
+        self.search = Some(Search {
+            query: String::new(),
+            index: None,
+            failed: false,
+            original: self.value.clone(),
+        });
+    }
+
+    /// Return the query of the ongoing reverse search of the history, if
+    /// any, and whether it has no (further) match.
+    pub fn search(&self) -> Option<(&str, bool)> {
+        self.search
+            .as_ref()
+            .map(|search| (search.query.as_str(), search.failed))
+    }
+
+    /// Handle a key pressed during a reverse search of the history.
+    ///
+    /// Returns false if the key ends the search (accepting the displayed
+    /// match) and is to be handled as usual.
+    fn search_keycode(&mut self, keycode: KeyCode) -> bool {
+        let Some(search) = self.search.as_mut() else {
+            return false;
+        };
+
+        match keycode {
+            // Ctrl+R again finds the next older match.
+            KeyCode::CtrlR => {
+                let before = search.index.unwrap_or(self.history.len());
+                self.find_match(before);
+            }
+            KeyCode::Backspace | KeyCode::CtrlH => {
+                search.query.pop();
+                self.find_match(self.history.len());
+            }
+            // Escape (or Ctrl+G) abandons the search.
+            KeyCode::Escape | KeyCode::CtrlG => {
+                self.value = search.original.clone();
+                self.cursor = self.value.len();
+                self.search = None;
+            }
+            code => match code.printable() {
+                Some(c) => self.search_char(c),
+                None => {
+                    self.end_search();
+                    return false;
+                }
+            },
+        }
+
+        true
+    }
+
+    /// Add the given character to the query of the reverse search, keeping
+    /// the displayed match if it still matches.
+    fn search_char(&mut self, c: char) {
+        let Some(search) = self.search.as_mut() else {
+            return;
+        };
+        search.query.push(c);
+        let before = search
+            .index
+            .map(|index| index + 1)
+            .unwrap_or(self.history.len());
+        self.find_match(before);
+    }
+
+    /// Display the newest history entry before the given position which
+    /// contains the query of the reverse search.
+    fn find_match(&mut self, before: usize) {
+        let Some(search) = self.search.as_mut() else {
+            return;
+        };
+        if search.query.is_empty() {
+            search.index = None;
+            search.failed = false;
+            self.value = search.original.clone();
+            self.cursor = self.value.len();
+            return;
+        }
+
+        let before = before.min(self.history.len());
+        match self.history[..before]
+            .iter()
+            .rposition(|entry| entry.contains(&search.query))
+        {
+            Some(index) => {
+                search.index = Some(index);
+                search.failed = false;
+                self.value = self.history[index].clone();
+                self.cursor = self.value.find(&search.query).unwrap_or(0);
+            }
+            // The previous match remains displayed.
+            None => search.failed = true,
+        }
+    }
+
+    /// End the reverse search of the history, leaving the displayed match in
+    /// the input line. The history is then navigated from the match.
+    fn end_search(&mut self) {
+        let Some(search) = self.search.take() else {
+            return;
+        };
+        if let Some(index) = search.index {
+            if self.history_index.is_none() {
+                self.draft = search.original;
+            }
+            self.history_index = Some(index);
+        }
+    }
+
     pub fn next_event(&mut self) -> Option<InputEvent> {
         self.queue.pop_front()
     }
 
+    /// Enable or disable vi-style modal editing.
+    pub fn set_vi(&mut self, enabled: bool) {
+        if !enabled {
+            self.mode = Mode::Insert;
+            self.operator = None;
+        }
+        self.vi = enabled;
+    }
+
+    /// Capture keys, emitting them as events rather than editing the input
+    /// line, or stop capturing keys.
+    pub fn set_capture(&mut self, enabled: bool) {
+        self.capture = enabled;
+    }
+
+    /// Determine whether the input line is in vi normal mode.
+    pub fn is_normal_mode(&self) -> bool {
+        self.vi && self.mode == Mode::Normal
+    }
+
+    /// Insert the given character, or interpret it as a command in vi normal
+    /// mode.
+    fn handle_char(&mut self, c: char) {
+        if self.capture {
+            self.queue.push_back(InputEvent::Captured(Key::Char(c)));
+        } else if self.search.is_some() {
+            self.search_char(c);
+        } else if self.is_normal_mode() {
+            self.normal_command(c);
+        } else {
+            self.put_str(&c.to_string());
+        }
+    }
+
+    /// Perform the vi normal mode command bound to the given character.
+    fn normal_command(&mut self, c: char) {
+        let len = self.value.len();
+        // In normal mode, the cursor rests on the last character rather than
+        // after it.
+        let last = len.saturating_sub(1);
+
+        match (self.operator.take(), c) {
+            (Some('d'), 'd') => self.kill_line(),
+            (Some(_), _) => {}
+            (None, 'd') => self.operator = Some('d'),
+            (None, 'h') => self.cursor = self.cursor.saturating_sub(1),
+            (None, 'l') => self.cursor = (self.cursor + 1).min(last),
+            (None, 'k') => self.history_prev(),
+            (None, 'j') => self.history_next(),
+            (None, '0') | (None, '^') => self.cursor = 0,
+            (None, '$') => self.cursor = last,
+            (None, 'w') => self.cursor = (self.word_right() + 1).min(last),
+            (None, 'b') => self.cursor = self.word_left(),
+            (None, 'x') => self.remove_right(1),
+            (None, 'D') => self.kill(self.cursor, len),
+            (None, 'p') => self.yank(),
+            (None, 'u') => self.undo(),
+            (None, 'i') => self.mode = Mode::Insert,
+            (None, 'a') => {
+                self.cursor = (self.cursor + 1).min(len);
+                self.mode = Mode::Insert;
+            }
+            (None, 'I') => {
+                self.cursor = 0;
+                self.mode = Mode::Insert;
+            }
+            (None, 'A') => {
+                self.cursor = len;
+                self.mode = Mode::Insert;
+            }
+            _ => {}
+        }
+        self.cursor = self.cursor.min(self.value.len());
+    }
+
     fn put_str(&mut self, s: &str) {
+        self.record_edit(Edit::Insert);
         let c = self.cursor.min(self.value.len());
         self.value = self.value[0..c].to_string() + s + &self.value[c..];
         self.cursor = (self.cursor + 1).min(self.value.len());
     }
 
     pub fn set_value(&mut self, input: &str) {
+        if input != self.value {
+            self.record_edit(Edit::Other);
+        }
         self.value = input.to_string();
         self.cursor = self.cursor.min(self.value.len());
     }
 
     pub fn remove_left(&mut self, n: usize) {
+        self.record_edit(Edit::Remove);
         let len = self.value.len();
         let c = self.cursor;
         self.value = self.value[0..c.max(n) - n].to_string() + &self.value[c.min(len)..];
@@ -75,6 +688,7 @@ impl Input {
     }
 
     pub fn remove_right(&mut self, n: usize) {
+        self.record_edit(Edit::Remove);
         let len = self.value.len();
         let c = self.cursor;
         self.value = self.value[0..c].to_string() + &self.value[(c + n).min(len)..];
@@ -83,4 +697,84 @@ impl Input {
     pub fn set_cursor(&mut self, cursor: usize) {
         self.cursor = cursor;
     }
+
+    /// Record the input value and cursor prior to an edit of the given
+    /// kind, so that the edit may be undone.
+    fn record_edit(&mut self, edit: Edit) {
+        if edit == Edit::Other || edit != self.last_edit {
+            self.undo.push((self.value.clone(), self.cursor));
+            if self.undo.len() > UNDO_LEN {
+                self.undo.remove(0);
+            }
+        }
+        self.last_edit = edit;
+    }
+
+    /// Restore the input value and cursor prior to the most recent edit.
+    pub fn undo(&mut self) {
+        if let Some((value, cursor)) = self.undo.pop() {
+            self.value = value;
+            self.cursor = cursor;
+        }
+        self.last_edit = Edit::Other;
+    }
+
+    /// Remove the text between the given positions, adding it to the kill
+    /// ring.
+    fn kill(&mut self, start: usize, end: usize) {
+        let end = end.min(self.value.len());
+        let start = start.min(end);
+        if start == end {
+            return;
+        }
+
+        self.record_edit(Edit::Other);
+        self.kill_ring.push(self.value[start..end].to_string());
+        if self.kill_ring.len() > KILL_RING_LEN {
+            self.kill_ring.remove(0);
+        }
+        self.value.replace_range(start..end, "");
+        self.cursor = start;
+    }
+
+    /// Remove the entire input value, adding it to the kill ring.
+    pub fn kill_line(&mut self) {
+        self.kill(0, self.value.len());
+    }
+
+    /// Insert the most recently killed text at the cursor.
+    fn yank(&mut self) {
+        let Some(text) = self.kill_ring.last().cloned() else {
+            return;
+        };
+
+        self.record_edit(Edit::Other);
+        let c = self.cursor.min(self.value.len());
+        self.value.insert_str(c, &text);
+        self.cursor = c + text.len();
+    }
+
+    /// Render the input value with the cursor highlighted, scrolled
+    /// horizontally so that the cursor remains visible within the given
+    /// width.
+    ///
+    /// Returns the rendered value and whether any text is hidden to the left
+    /// of the visible portion.
+    pub fn render(&self, width: usize) -> (String, bool) {
+        let width = width.max(1);
+        let len = self.value.len();
+        let c = self.cursor.min(len);
+        let n = (c + 1).min(len);
+
+        // The cursor occupies a column even when positioned after the last
+        // character.
+        let start = (c + 1).saturating_sub(width);
+        let end = (start + width).min(len);
+
+        let s = if n > c { &self.value[c..n] } else { " " };
+        let rendered =
+            self.value[start..c].to_string() + "\x1b[7m" + s + "\x1b[27m" + &self.value[n..end];
+
+        (rendered, start > 0)
+    }
 }
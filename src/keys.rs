@@ -0,0 +1,260 @@
+//! Keybindings.
+//!
+//! Actions are bound to key chords, which may be customised in the `[keys]`
+//! section of the configuration file. Chords are written as a key name
+//! preceded by any number of modifiers, separated by `-` (e.g. `ctrl-n`,
+//! `alt-right`, `ctrl-shift-up`, `pageup`).
+
+use std::collections::{BTreeMap, HashMap};
+
+use terminal_keycode::KeyCode;
+
+use crate::{
+    i18n::tr_args,
+    input::{Chord, Key, Modifiers},
+};
+
+/// An action which may be bound to a key chord.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// Make the next window active.
+    NextWindow,
+    /// Make the previous window active.
+    PrevWindow,
+    /// Scroll the active window back by a page.
+    ScrollUp,
+    /// Scroll the active window forward by a page.
+    ScrollDown,
+    /// Clear the input line, adding its contents to the kill ring.
+    ClearInput,
+    /// Display the members of the channel of the active window.
+    ToggleMembers,
+    /// Select a post of the active window in order to act on it.
+    SelectMessage,
+    /// Move the active window one position towards the start of the window
+    /// list.
+    MoveWindowLeft,
+    /// Move the active window one position towards the end of the window
+    /// list.
+    MoveWindowRight,
+    /// Open the window switcher, which lists the windows matching a query.
+    SwitchWindow,
+    /// Complete the command name or argument before the cursor.
+    Complete,
+    /// Repaint the entire screen.
+    Redraw,
+    /// Suspend cabin, returning to the shell.
+    Suspend,
+}
+
+impl Action {
+    const ALL: [Action; 13] = [
+        Action::NextWindow,
+        Action::PrevWindow,
+        Action::ScrollUp,
+        Action::ScrollDown,
+        Action::ClearInput,
+        Action::ToggleMembers,
+        Action::SelectMessage,
+        Action::MoveWindowLeft,
+        Action::MoveWindowRight,
+        Action::SwitchWindow,
+        Action::Complete,
+        Action::Redraw,
+        Action::Suspend,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Action::NextWindow => "next-window",
+            Action::PrevWindow => "prev-window",
+            Action::ScrollUp => "scroll-up",
+            Action::ScrollDown => "scroll-down",
+            Action::ClearInput => "clear-input",
+            Action::ToggleMembers => "toggle-members",
+            Action::SelectMessage => "select-message",
+            Action::MoveWindowLeft => "move-window-left",
+            Action::MoveWindowRight => "move-window-right",
+            Action::SwitchWindow => "switch-window",
+            Action::Complete => "complete",
+            Action::Redraw => "redraw",
+            Action::Suspend => "suspend",
+        }
+    }
+
+    fn parse(name: &str) -> Result<Self, String> {
+        Action::ALL
+            .into_iter()
+            .find(|action| action.name() == name)
+            .ok_or_else(|| tr_args("unknown action: {}", &[&name]))
+    }
+
+    /// The chord bound to the action if not configured otherwise.
+    fn default_chord(&self) -> Chord {
+        let ctrl = Modifiers {
+            ctrl: true,
+            ..Modifiers::NONE
+        };
+        let ctrl_shift = Modifiers {
+            ctrl: true,
+            shift: true,
+            ..Modifiers::NONE
+        };
+        let (modifiers, key) = match self {
+            Action::NextWindow => (ctrl, Key::Char('n')),
+            Action::PrevWindow => (ctrl, Key::Char('p')),
+            Action::ScrollUp => (Modifiers::NONE, Key::PageUp),
+            Action::ScrollDown => (Modifiers::NONE, Key::PageDown),
+            Action::ClearInput => (ctrl, Key::Char('u')),
+            Action::ToggleMembers => (ctrl, Key::Char('o')),
+            Action::SelectMessage => (ctrl, Key::Char('x')),
+            Action::MoveWindowLeft => (ctrl_shift, Key::Left),
+            Action::MoveWindowRight => (ctrl_shift, Key::Right),
+            Action::SwitchWindow => (ctrl, Key::Char('t')),
+            Action::Complete => (Modifiers::NONE, Key::Tab),
+            Action::Redraw => (ctrl, Key::Char('l')),
+            Action::Suspend => (ctrl, Key::Char('z')),
+        };
+
+        Chord { modifiers, key }
+    }
+}
+
+/// A mapping of key chords to actions.
+pub struct Keybindings(HashMap<Chord, Action>);
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        Keybindings(
+            Action::ALL
+                .into_iter()
+                .map(|action| (action.default_chord(), action))
+                .collect(),
+        )
+    }
+}
+
+impl Keybindings {
+    /// Bind the chords defined in the `[keys]` section of the configuration
+    /// file, replacing the default chords of the configured actions.
+    ///
+    /// Returns a description of each invalid entry; valid entries are
+    /// applied regardless.
+    pub fn configure(&mut self, keys: &BTreeMap<String, String>) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (name, chord) in keys {
+            match Action::parse(name).and_then(|action| Ok((action, parse_chord(chord)?))) {
+                Ok((action, chord)) => {
+                    self.0.retain(|_, bound| *bound != action);
+                    self.0.insert(chord, action);
+                }
+                Err(err) => errors.push(err),
+            }
+        }
+
+        errors
+    }
+
+    /// Return the action bound to the given chord, if any.
+    pub fn action(&self, chord: &Chord) -> Option<Action> {
+        self.0.get(chord).copied()
+    }
+
+    /// Return the action bound to the given legacy key code, if any.
+    pub fn keycode_action(&self, keycode: &KeyCode) -> Option<Action> {
+        self.action(&keycode_to_chord(keycode)?)
+    }
+}
+
+/// Parse a chord such as `ctrl-shift-left`.
+fn parse_chord(chord: &str) -> Result<Chord, String> {
+    let invalid = || tr_args("invalid key chord: {}", &[&chord]);
+
+    let mut parts: Vec<&str> = chord.split('-').collect();
+    let name = parts
+        .pop()
+        .filter(|name| !name.is_empty())
+        .ok_or_else(invalid)?;
+
+    let mut modifiers = Modifiers::NONE;
+    for part in parts {
+        match part.to_lowercase().as_str() {
+            "ctrl" | "c" => modifiers.ctrl = true,
+            "alt" | "meta" | "m" => modifiers.alt = true,
+            "shift" | "s" => modifiers.shift = true,
+            _ => return Err(invalid()),
+        }
+    }
+
+    let key = match name.to_lowercase().as_str() {
+        "enter" | "return" => Key::Enter,
+        "tab" => Key::Tab,
+        "backspace" => Key::Backspace,
+        "escape" | "esc" => Key::Escape,
+        "delete" | "del" => Key::Delete,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "space" => Key::Char(' '),
+        _ => {
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Key::Char(c.to_ascii_lowercase()),
+                _ => return Err(invalid()),
+            }
+        }
+    };
+
+    Ok(Chord { modifiers, key })
+}
+
+/// Convert a key code reported by the legacy decoder to the equivalent
+/// chord.
+fn keycode_to_chord(keycode: &KeyCode) -> Option<Chord> {
+    let ctrl = |c| Chord {
+        modifiers: Modifiers {
+            ctrl: true,
+            ..Modifiers::NONE
+        },
+        key: Key::Char(c),
+    };
+    let key = |key| Chord {
+        modifiers: Modifiers::NONE,
+        key,
+    };
+
+    Some(match keycode {
+        KeyCode::CtrlA => ctrl('a'),
+        KeyCode::CtrlB => ctrl('b'),
+        KeyCode::CtrlC => ctrl('c'),
+        KeyCode::CtrlD => ctrl('d'),
+        KeyCode::CtrlE => ctrl('e'),
+        KeyCode::CtrlF => ctrl('f'),
+        KeyCode::CtrlG => ctrl('g'),
+        KeyCode::CtrlK => ctrl('k'),
+        KeyCode::CtrlL => ctrl('l'),
+        KeyCode::CtrlN => ctrl('n'),
+        KeyCode::CtrlO => ctrl('o'),
+        KeyCode::CtrlP => ctrl('p'),
+        KeyCode::CtrlQ => ctrl('q'),
+        KeyCode::CtrlR => ctrl('r'),
+        KeyCode::CtrlS => ctrl('s'),
+        KeyCode::CtrlT => ctrl('t'),
+        KeyCode::CtrlU => ctrl('u'),
+        KeyCode::CtrlV => ctrl('v'),
+        KeyCode::CtrlW => ctrl('w'),
+        KeyCode::CtrlX => ctrl('x'),
+        KeyCode::CtrlY => ctrl('y'),
+        KeyCode::CtrlZ => ctrl('z'),
+        KeyCode::Tab => key(Key::Tab),
+        KeyCode::Escape => key(Key::Escape),
+        KeyCode::PageUp => key(Key::PageUp),
+        KeyCode::PageDown => key(Key::PageDown),
+        _ => return None,
+    })
+}
@@ -1,6 +1,40 @@
 pub mod app;
+mod awaylog;
+pub mod bridge;
+pub mod client;
+mod complete;
+mod config;
+pub mod control;
+pub mod daemon;
+mod dial;
+mod exec;
+mod feeds;
 mod hex;
+mod history;
+pub mod i18n;
+pub mod import;
 pub mod input;
-mod time;
+mod keys;
+#[cfg(feature = "matrix")]
+mod matrix;
+mod metrics;
+mod migrate;
+mod nicks;
+mod notes;
+mod offenders;
+mod paths;
+mod publish;
+mod redact;
+mod search;
+mod session;
+pub mod settings;
+pub mod store;
+mod stun;
+pub mod time;
+pub mod transport;
+mod tty;
 pub mod ui;
 mod utils;
+mod webhooks;
+#[cfg(feature = "websocket")]
+mod websocket;
@@ -1,12 +1,21 @@
-use std::{env, io};
+use std::{
+    env, io,
+    path::{Path, PathBuf},
+};
 
 use async_std::task;
 use cable::Channel;
 use cable_core::MemoryStore;
 use futures::channel::mpsc;
+use log::error;
 use raw_tty::IntoRawMode;
 
-use cabin::{app::App, ui};
+use cabin::{
+    app::App,
+    client, control, daemon,
+    i18n::{self, tr_args},
+    import, store, ui,
+};
 
 type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
 
@@ -14,23 +23,94 @@ fn main() -> Result<(), Error> {
     // Initialise the logger.
     env_logger::init();
 
+    // Select the locale of user-facing strings. The configuration file may
+    // override this once loaded.
+    i18n::set_locale_from_env();
+
     // Parse the arguments.
-    let (_args, _argv) = argmap::parse(env::args());
+    let (args, argv) = argmap::parse(env::args());
+
+    // Attach to a running daemon rather than starting a new instance.
+    if args.get(1).map(String::as_str) == Some("attach") {
+        control::attach()?;
+        return Ok(());
+    }
+
+    // Import the history of a JavaScript cabal client into a cabal.
+    if args.get(1).map(String::as_str) == Some("import") {
+        let (Some(path), Some(cabal)) = (args.get(2), args.get(3)) else {
+            return Err(i18n::usage("cabin import FILE CABAL").into());
+        };
+        let count = task::block_on(import::run(Path::new(path), cabal))?;
+        println!(
+            "{}",
+            tr_args("imported {} messages into cabal {}", &[&count, cabal])
+        );
+        return Ok(());
+    }
+
+    // Print the location of the control socket, e.g. for scripts.
+    if argv.contains_key("socket-path") {
+        println!("{}", control::socket_path().display());
+        return Ok(());
+    }
+
+    let detached = argv.contains_key("daemon");
+    let accessible = argv.contains_key("accessible");
+    let session_path = argv
+        .get("session")
+        .and_then(|values| values.last())
+        .map(PathBuf::from)
+        .unwrap_or_else(daemon::session_path);
 
     // Launch the application, resize the UI to match the terminal dimensions
     // and accept input via stdin.
     task::block_on(async move {
         let (close_channel_sender, close_channel_receiver) = mpsc::unbounded::<Channel>();
 
+        // A daemon has no terminal to query for its dimensions.
+        let size = if detached {
+            (80, 24)
+        } else {
+            ui::get_term_size()
+        };
+        // Each cabal is restored from (and saved to) its own directory. A
+        // store which cannot be read is set aside by `store::open()`, so the
+        // cabal starts empty rather than overwriting it.
         let mut app = App::new(
-            ui::get_term_size(),
-            Box::new(|_name| Box::<MemoryStore>::default()),
+            size,
+            Box::new(|address| {
+                let cabal_store = task::block_on(store::open::<MemoryStore>(address))
+                    .unwrap_or_else(|err| {
+                        error!(
+                            "Failed to open store in {}: {}",
+                            client::store_dir(address).display(),
+                            err
+                        );
+                        MemoryStore::default()
+                    });
+                Box::new(cabal_store)
+            }),
             close_channel_sender,
-        );
+        )
+        .with_persistent_stores();
+
+        // Run without a terminal, e.g. as a systemd service.
+        if detached {
+            app.run_daemon(&session_path, close_channel_receiver)
+                .await?;
+            return Ok(());
+        }
+
+        // Append lines for screen readers rather than repainting the screen.
+        app.ui.lock().await.accessible = accessible;
 
         let ui = app.ui.clone();
         task::spawn(async move { ui::resizer(ui).await });
 
+        let ui = app.ui.clone();
+        task::spawn(async move { ui::flusher(ui).await });
+
         app.run(
             Box::new(io::stdin().into_raw_mode().unwrap()),
             close_channel_receiver,
@@ -0,0 +1,209 @@
+//! A reference bridge endpoint for Matrix rooms.
+//!
+//! Messages are sent and received via the client-server API of a homeserver,
+//! using a long-polling `/sync` request to receive new messages. Only plain
+//! text (`m.text`) messages are bridged.
+
+use std::time::Duration;
+
+use async_std::{prelude::*, task};
+use futures::channel::mpsc::{self, UnboundedSender};
+use log::{error, warn};
+use serde_json::{json, Value};
+
+use crate::{
+    bridge::{BridgeMessage, Endpoint},
+    time::{Clock, SystemClock},
+};
+
+/// The number of milliseconds for which a `/sync` request is held open by
+/// the homeserver if no new events are available.
+const SYNC_TIMEOUT: u64 = 30_000;
+
+/// The delay before retrying a failed request.
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// Connect to the given room of the given homeserver.
+pub fn connect(homeserver: &str, room: &str, access_token: &str) -> Endpoint {
+    let (outbound, mut outbound_receiver) = mpsc::unbounded::<BridgeMessage>();
+    let (inbound_sender, inbound) = mpsc::unbounded();
+
+    let api = Api {
+        base: format!("{}/_matrix/client/v3", homeserver.trim_end_matches('/')),
+        room: room.to_owned(),
+        authorization: format!("Bearer {}", access_token),
+        // Transaction IDs must not repeat for the same access token, so they
+        // are scoped to this run of the process by its start time.
+        txn_prefix: format!(
+            "cabin-{}-{}",
+            std::process::id(),
+            SystemClock.now().unwrap_or_default()
+        ),
+    };
+
+    // Deliver outbound messages to the room.
+    let sender = api.clone();
+    task::spawn(async move {
+        let mut txn = 0;
+        while let Some(msg) = outbound_receiver.next().await {
+            txn += 1;
+            let api = sender.clone();
+            let body = format!("<{}> {}", msg.author, msg.text);
+            let result = task::spawn_blocking(move || api.send(txn, &body)).await;
+            if let Err(err) = result {
+                error!("Failed to send message to Matrix room: {}", err);
+            }
+        }
+    });
+
+    // Receive messages from the room.
+    task::spawn(async move { api.sync(inbound_sender).await });
+
+    Endpoint { outbound, inbound }
+}
+
+/// The parameters of requests made to the client-server API.
+#[derive(Clone)]
+struct Api {
+    /// The base URL of the client-server API.
+    base: String,
+    room: String,
+    /// The value of the authorization header.
+    authorization: String,
+    /// The prefix of the transaction IDs of sent messages, unique to this
+    /// run.
+    txn_prefix: String,
+}
+
+impl Api {
+    /// Send a text message to the room with the given transaction ID.
+    fn send(&self, txn: u64, body: &str) -> Result<(), ureq::Error> {
+        let url = format!(
+            "{}/rooms/{}/send/m.room.message/{}-{}",
+            self.base,
+            encode(&self.room),
+            self.txn_prefix,
+            txn
+        );
+        ureq::put(&url)
+            .set("Authorization", &self.authorization)
+            .send_json(json!({ "msgtype": "m.text", "body": body }))?;
+
+        Ok(())
+    }
+
+    /// Perform a blocking GET request of the given path and parameters.
+    fn get(&self, path: &str, params: &[(&str, &str)]) -> Result<Value, String> {
+        let mut request = ureq::get(&format!("{}{}", self.base, path))
+            .set("Authorization", &self.authorization)
+            .timeout(Duration::from_millis(SYNC_TIMEOUT) * 2);
+        for (key, value) in params {
+            request = request.query(key, value);
+        }
+
+        request
+            .call()
+            .map_err(|err| err.to_string())?
+            .into_json()
+            .map_err(|err| err.to_string())
+    }
+
+    /// Long-poll the homeserver for new messages in the room, sending those
+    /// authored by other users to the given sender.
+    async fn sync(self, sender: UnboundedSender<BridgeMessage>) {
+        let api = self.clone();
+        let user_id = match task::spawn_blocking(move || api.get("/account/whoami", &[])).await {
+            Ok(whoami) => whoami["user_id"].as_str().unwrap_or_default().to_owned(),
+            Err(err) => {
+                error!("Failed to authenticate with Matrix homeserver: {}", err);
+                return;
+            }
+        };
+
+        let filter = json!({ "room": { "rooms": [self.room.as_str()] } }).to_string();
+        let mut since: Option<String> = None;
+        loop {
+            let api = self.clone();
+            let filter = filter.clone();
+            let params_since = since.clone();
+            let response = task::spawn_blocking(move || {
+                let timeout = SYNC_TIMEOUT.to_string();
+                let mut params = vec![("filter", filter.as_str())];
+                // The initial sync only establishes the position from which
+                // new messages are received.
+                match &params_since {
+                    Some(since) => {
+                        params.push(("since", since.as_str()));
+                        params.push(("timeout", timeout.as_str()));
+                    }
+                    None => params.push(("timeout", "0")),
+                }
+                api.get("/sync", &params)
+            })
+            .await;
+
+            let response = match response {
+                Ok(response) => response,
+                Err(err) => {
+                    warn!("Matrix sync failed: {}", err);
+                    task::sleep(RETRY_DELAY).await;
+                    continue;
+                }
+            };
+
+            if since.is_some() {
+                let events = response["rooms"]["join"][self.room.as_str()]["timeline"]["events"]
+                    .as_array()
+                    .cloned()
+                    .unwrap_or_default();
+                for event in events {
+                    let sender_id = event["sender"].as_str().unwrap_or_default();
+                    if event["type"].as_str() != Some("m.room.message")
+                        || event["content"]["msgtype"].as_str() != Some("m.text")
+                        || sender_id == user_id
+                    {
+                        continue;
+                    }
+                    let msg = BridgeMessage {
+                        author: display_name(sender_id),
+                        text: event["content"]["body"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_owned(),
+                    };
+                    if sender.unbounded_send(msg).is_err() {
+                        return;
+                    }
+                }
+            }
+
+            since = response["next_batch"]
+                .as_str()
+                .map(|batch| batch.to_owned());
+        }
+    }
+}
+
+/// Return the localpart of the given Matrix user ID (e.g. `alice` for
+/// `@alice:example.org`).
+fn display_name(user_id: &str) -> String {
+    user_id
+        .trim_start_matches('@')
+        .split(':')
+        .next()
+        .unwrap_or(user_id)
+        .to_owned()
+}
+
+/// Percent-encode the given string for use as a URL path segment.
+fn encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            b => format!("%{:02X}", b),
+        })
+        .collect()
+}
@@ -0,0 +1,245 @@
+//! Counters exposed in the Prometheus text format.
+//!
+//! The counters are process-wide and always maintained; they are only served
+//! over HTTP (at `/metrics`) if a metrics address is set in the
+//! configuration file. This is mostly of interest to operators of always-on
+//! relay nodes.
+
+use std::{
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use async_std::{net, prelude::*, task};
+use futures::io::{AsyncRead, AsyncWrite};
+use log::{debug, warn};
+
+/// A monotonically increasing (or, for gauges, fluctuating) counter.
+pub struct Counter {
+    name: &'static str,
+    help: &'static str,
+    kind: &'static str,
+    value: AtomicU64,
+}
+
+impl Counter {
+    const fn new(name: &'static str, help: &'static str, kind: &'static str) -> Self {
+        Self {
+            name,
+            help,
+            kind,
+            value: AtomicU64::new(0),
+        }
+    }
+
+    /// Increase the counter by the given amount.
+    pub fn add(&self, n: u64) {
+        self.value.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Increase the counter by one.
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    /// Decrease the counter by one (gauges only).
+    fn dec(&self) {
+        self.value.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Return the current value of the counter.
+    pub fn get(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+pub static CONNECTIONS: Counter = Counter::new(
+    "cabin_connections_total",
+    "Peer connections established or accepted.",
+    "counter",
+);
+pub static ACTIVE_CONNECTIONS: Counter = Counter::new(
+    "cabin_connections_active",
+    "Currently open peer connections.",
+    "gauge",
+);
+pub static BYTES_IN: Counter = Counter::new(
+    "cabin_received_bytes_total",
+    "Bytes received from peers.",
+    "counter",
+);
+pub static BYTES_OUT: Counter =
+    Counter::new("cabin_sent_bytes_total", "Bytes sent to peers.", "counter");
+pub static POSTS_PUBLISHED: Counter = Counter::new(
+    "cabin_posts_published_total",
+    "Text posts published by the local peer.",
+    "counter",
+);
+pub static POSTS_RECEIVED: Counter = Counter::new(
+    "cabin_channel_posts_total",
+    "Posts yielded by open channels, including those of other peers.",
+    "counter",
+);
+
+const COUNTERS: [&Counter; 6] = [
+    &CONNECTIONS,
+    &ACTIVE_CONNECTIONS,
+    &BYTES_IN,
+    &BYTES_OUT,
+    &POSTS_PUBLISHED,
+    &POSTS_RECEIVED,
+];
+
+/// Render all counters in the Prometheus text exposition format.
+pub fn render() -> String {
+    COUNTERS
+        .iter()
+        .map(|counter| {
+            format!(
+                "# HELP {name} {}\n# TYPE {name} {}\n{name} {}\n",
+                counter.help,
+                counter.kind,
+                counter.get(),
+                name = counter.name,
+            )
+        })
+        .collect()
+}
+
+/// Serve the counters at `/metrics` on the given address (host:port).
+pub async fn serve(addr: &str) -> io::Result<()> {
+    let listener = net::TcpListener::bind(addr).await?;
+
+    task::spawn(async move {
+        let mut incoming = listener.incoming();
+        while let Some(stream) = incoming.next().await {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            task::spawn(async move {
+                if let Err(err) = respond(&mut stream).await {
+                    debug!("Metrics request failed: {}", err);
+                }
+            });
+        }
+        warn!("Metrics listener closed");
+    });
+
+    Ok(())
+}
+
+/// Read a single HTTP request from the stream and write the response.
+async fn respond(stream: &mut net::TcpStream) -> io::Result<()> {
+    // Only the request line is of interest; the remainder of the request is
+    // ignored.
+    let mut buf = [0; 1024];
+    let n = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let mut request_line = request.lines().next().unwrap_or_default().split(' ');
+
+    let (status, body) = match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", render()),
+        _ => ("404 Not Found", "not found\n".to_string()),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+
+    stream.write_all(response.as_bytes()).await
+}
+
+/// Decrements the active connection gauge when the last clone of a
+/// connection is dropped.
+struct ConnectionGuard;
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        ACTIVE_CONNECTIONS.dec();
+    }
+}
+
+/// A peer connection whose traffic is counted.
+#[derive(Clone)]
+pub struct Metered<T> {
+    inner: T,
+    /// Set once reading from or writing to the connection has failed.
+    failed: Arc<AtomicBool>,
+    _guard: Arc<ConnectionGuard>,
+}
+
+impl<T> Metered<T> {
+    pub fn new(inner: T) -> Self {
+        CONNECTIONS.inc();
+        ACTIVE_CONNECTIONS.inc();
+
+        Self {
+            inner,
+            failed: Arc::default(),
+            _guard: Arc::new(ConnectionGuard),
+        }
+    }
+
+    /// Determine whether reading from or writing to the connection has
+    /// failed (e.g. because it was reset or timed out), as opposed to the
+    /// messages of the peer failing to decode.
+    pub fn io_failed(&self) -> bool {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    /// Record the failure of the given operation, if it failed.
+    fn record<R>(&self, poll: &Poll<io::Result<R>>) {
+        if let Poll::Ready(Err(_)) = poll {
+            self.failed.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Metered<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(n)) = poll {
+            BYTES_IN.add(n as u64);
+        }
+        self.record(&poll);
+        poll
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Metered<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(n)) = poll {
+            BYTES_OUT.add(n as u64);
+        }
+        self.record(&poll);
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let poll = Pin::new(&mut self.inner).poll_flush(cx);
+        self.record(&poll);
+        poll
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let poll = Pin::new(&mut self.inner).poll_close(cx);
+        self.record(&poll);
+        poll
+    }
+}
@@ -0,0 +1,167 @@
+//! Versioned migrations of persisted TOML files, such as the configuration
+//! file.
+//!
+//! A file records the version of its format in a top-level `version` key;
+//! files without one are at version 1. When the format of a file changes, a
+//! migration from the previous version is appended to its list of
+//! migrations, so that the files of older installs are upgraded at startup
+//! rather than failing to parse. The original file is kept as a backup (e.g.
+//! `config.toml.v1.bak`) before the upgraded file is written.
+
+use std::{
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use toml::{Table, Value};
+
+/// A change to the format of a file, from the previous version.
+pub struct Migration {
+    /// A description of the change, reported once applied.
+    pub description: &'static str,
+    /// Apply the change to the parsed file.
+    pub apply: fn(&mut Table) -> Result<(), String>,
+}
+
+/// The migrations of the configuration file, in order: the first upgrades
+/// version 1 to version 2, and so on.
+pub const CONFIG: &[Migration] = &[];
+
+/// Return the current version of a file with the given migrations.
+pub fn current_version(migrations: &[Migration]) -> i64 {
+    migrations.len() as i64 + 1
+}
+
+/// Return the path of the backup of the given file at the given version.
+fn backup_path(path: &Path, version: i64) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(format!(".v{}.bak", version));
+    path.with_file_name(name)
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Upgrade the TOML file at the given path to the current version by
+/// applying the given migrations, if the file exists and is older.
+///
+/// Returns a description of each applied migration, followed by the path of
+/// the backup of the original file. Files written by a newer version of
+/// cabin are not modified; an error is returned instead.
+pub fn upgrade(path: &Path, migrations: &[Migration]) -> io::Result<Vec<String>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut table: Table =
+        toml::from_str(&contents).map_err(|err| invalid_data(err.to_string()))?;
+
+    let version = match table.get("version") {
+        None => 1,
+        Some(Value::Integer(version)) if *version >= 1 => *version,
+        Some(value) => return Err(invalid_data(format!("invalid version {}", value))),
+    };
+    let current = current_version(migrations);
+    if version > current {
+        return Err(invalid_data(format!(
+            "version {} was written by a newer version of cabin (at most version {} is supported)",
+            version, current
+        )));
+    }
+    if version == current {
+        return Ok(Vec::new());
+    }
+
+    let pending = &migrations[(version - 1) as usize..];
+    for (migration, to) in pending.iter().zip(version + 1..) {
+        (migration.apply)(&mut table)
+            .map_err(|err| invalid_data(format!("failed to migrate to version {}: {}", to, err)))?;
+    }
+    table.insert("version".to_string(), Value::Integer(current));
+    let upgraded = toml::to_string(&table).map_err(|err| invalid_data(err.to_string()))?;
+
+    // The original file is backed up before it is replaced, so that nothing
+    // is lost if the upgraded file is written incompletely.
+    let backup = backup_path(path, version);
+    fs::copy(path, &backup)?;
+    fs::write(path, upgraded)?;
+
+    let mut applied: Vec<String> = pending
+        .iter()
+        .map(|migration| migration.description.to_string())
+        .collect();
+    applied.push(format!(
+        "upgraded {} from version {} to {}; the original was kept as {}",
+        path.display(),
+        version,
+        current,
+        backup.display()
+    ));
+
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rename the `nick` key to `name`.
+    fn rename_nick(table: &mut Table) -> Result<(), String> {
+        let nick = table.remove("nick").ok_or("missing nick")?;
+        table.insert("name".to_string(), nick);
+        Ok(())
+    }
+
+    const MIGRATIONS: &[Migration] = &[Migration {
+        description: "renamed nick to name",
+        apply: rename_nick,
+    }];
+
+    /// Return the path of a file in a new, empty temporary directory.
+    fn temp_file(test: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("cabin-migrate-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("file.toml")
+    }
+
+    #[test]
+    fn older_files_are_upgraded_and_backed_up() {
+        let path = temp_file("upgrade");
+        fs::write(&path, "nick = \"dweller\"\n").unwrap();
+
+        let applied = upgrade(&path, MIGRATIONS).unwrap();
+        assert_eq!(applied.len(), 2);
+        assert_eq!(applied[0], "renamed nick to name");
+
+        let table: Table = toml::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(table.get("name"), Some(&Value::from("dweller")));
+        assert_eq!(table.get("nick"), None);
+        assert_eq!(table.get("version"), Some(&Value::Integer(2)));
+        assert_eq!(
+            fs::read_to_string(backup_path(&path, 1)).unwrap(),
+            "nick = \"dweller\"\n"
+        );
+
+        // The upgraded file is current, so it is left alone.
+        assert!(upgrade(&path, MIGRATIONS).unwrap().is_empty());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn newer_files_are_not_modified() {
+        let path = temp_file("newer");
+        fs::write(&path, "version = 3\n").unwrap();
+
+        let err = upgrade(&path, MIGRATIONS).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "version = 3\n");
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
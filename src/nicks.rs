@@ -0,0 +1,50 @@
+//! Nickname caching.
+
+use std::collections::HashMap;
+
+use async_std::sync::{Arc, Mutex};
+use cable::Nickname;
+use cable_core::Store;
+
+use crate::ui::{Addr, PublicKey};
+
+/// A cache of peer nicknames, keyed by cabal address and public key.
+///
+/// Rendering a busy channel or a long member list otherwise requires a store
+/// round trip for every post or member. Only known nicknames are cached;
+/// entries must be invalidated when a `post/info` is published or received
+/// for the associated public key.
+#[derive(Clone, Default)]
+pub struct NickCache(Arc<Mutex<HashMap<(Addr, PublicKey), Nickname>>>);
+
+impl NickCache {
+    /// Return the nickname of the given peer, querying the store if the
+    /// nickname has not been cached.
+    pub async fn get<S: Store>(
+        &self,
+        address: &Addr,
+        store: &S,
+        public_key: &PublicKey,
+    ) -> Option<Nickname> {
+        let key = (address.to_owned(), *public_key);
+        if let Some(nick) = self.0.lock().await.get(&key) {
+            return Some(nick.to_owned());
+        }
+
+        let nick = store
+            .get_peer_name_and_hash(public_key)
+            .await
+            .map(|(nick, _hash)| nick)?;
+        self.0.lock().await.insert(key, nick.clone());
+
+        Some(nick)
+    }
+
+    /// Remove the cached nickname of the given peer.
+    pub async fn invalidate(&self, address: &Addr, public_key: &PublicKey) {
+        self.0
+            .lock()
+            .await
+            .remove(&(address.to_owned(), *public_key));
+    }
+}
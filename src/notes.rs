@@ -0,0 +1,56 @@
+//! Persistence of private local notes about peers.
+//!
+//! Notes (e.g. "Dan from the meetup") are never published; they are written
+//! to `notes.toml` in the data directory, keyed by the hex-encoded public key
+//! of the peer.
+//!
+//! Notes are not kept in a cable store, which holds only the posts of a
+//! single cabal: a peer may belong to several cabals, and a note stored
+//! alongside posts could be mistaken for (or synced as) one.
+
+use std::{collections::BTreeMap, fs, io, path::PathBuf};
+
+use crate::{hex, paths, ui::PublicKey};
+
+/// Return the path of the notes file.
+fn path() -> PathBuf {
+    paths::data_dir().join("notes.toml")
+}
+
+/// Load the persisted notes, keyed by public key.
+///
+/// Returns no notes if none have been persisted. Entries whose key is not a
+/// valid public key are skipped.
+pub fn load() -> io::Result<Vec<(PublicKey, String)>> {
+    let contents = match fs::read_to_string(path()) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let notes: BTreeMap<String, String> =
+        toml::from_str(&contents).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+    Ok(notes
+        .into_iter()
+        .filter_map(|(key, note)| {
+            let public_key = hex::from(&key).and_then(|key| PublicKey::try_from(key).ok())?;
+            Some((public_key, note))
+        })
+        .collect())
+}
+
+/// Persist the given notes, replacing any previously persisted notes.
+pub fn save<'a>(notes: impl Iterator<Item = (&'a PublicKey, &'a String)>) -> io::Result<()> {
+    let path = path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let notes: BTreeMap<String, &String> = notes
+        .map(|(public_key, note)| (hex::to(public_key), note))
+        .collect();
+    let contents =
+        toml::to_string(&notes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+    fs::write(path, contents)
+}
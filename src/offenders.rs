@@ -0,0 +1,159 @@
+//! Screening of incoming connections from clients which do not speak cable.
+//!
+//! Listeners reachable from the internet are routinely hit by HTTP scanners
+//! and other clients which do not speak cable. Their connections fail as
+//! soon as their first bytes are decoded; the client is identified from
+//! those bytes, and addresses which fail repeatedly are ignored for a while.
+
+use std::{
+    collections::HashMap,
+    io,
+    net::IpAddr,
+    pin::Pin,
+    sync::{Arc, Mutex as SyncMutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use async_std::sync::Mutex;
+use futures::io::{AsyncRead, AsyncWrite};
+
+/// A connection which fails within this time of being accepted is taken to
+/// be from a client which does not speak cable.
+pub const INSTANT_FAILURE: Duration = Duration::from_secs(5);
+
+/// The number of failed connections after which an address is ignored.
+const OFFENCE_LIMIT: usize = 3;
+
+/// The time for which failed connections from an address are remembered,
+/// and for which the address is ignored once the limit is reached.
+pub const OFFENCE_COOLDOWN: Duration = Duration::from_secs(600);
+
+/// The number of bytes recorded from the start of each connection.
+const PREFIX_LEN: usize = 16;
+
+/// The beginnings of HTTP requests (including the HTTP/2 preface).
+const HTTP_PREFIXES: [&[u8]; 9] = [
+    b"GET ",
+    b"POST ",
+    b"HEAD ",
+    b"PUT ",
+    b"DELETE ",
+    b"OPTIONS ",
+    b"CONNECT ",
+    b"PATCH ",
+    b"PRI * ",
+];
+
+/// A connection whose first bytes read are recorded.
+#[derive(Clone)]
+pub struct Sniffed<T> {
+    inner: T,
+    prefix: Arc<SyncMutex<Vec<u8>>>,
+}
+
+impl<T> Sniffed<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            prefix: Arc::new(SyncMutex::new(Vec::with_capacity(PREFIX_LEN))),
+        }
+    }
+
+    /// Return the first bytes read from the connection.
+    pub fn prefix(&self) -> Vec<u8> {
+        self.prefix.lock().unwrap().clone()
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Sniffed<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(n)) = poll {
+            let mut prefix = self.prefix.lock().unwrap();
+            let len = (PREFIX_LEN - prefix.len()).min(n);
+            prefix.extend_from_slice(&buf[..len]);
+        }
+        poll
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Sniffed<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+/// Describe the client which sent the given first bytes of a failed
+/// connection (e.g. "an http client").
+pub fn classify(prefix: &[u8]) -> &'static str {
+    if prefix.is_empty() {
+        "a client which sent nothing"
+    } else if HTTP_PREFIXES
+        .iter()
+        .any(|method| prefix.starts_with(method))
+    {
+        "an http client"
+    } else if prefix.starts_with(&[0x16, 0x03]) {
+        "a tls client"
+    } else if prefix.starts_with(b"SSH-") {
+        "an ssh client"
+    } else {
+        "a client sending undecodable data"
+    }
+}
+
+/// The failed connections of a single address.
+struct Offender {
+    count: usize,
+    last: Instant,
+}
+
+/// The addresses from which connections have recently failed.
+#[derive(Clone, Default)]
+pub struct Offenders(Arc<Mutex<HashMap<IpAddr, Offender>>>);
+
+impl Offenders {
+    /// Determine whether connections from the given address are ignored.
+    pub async fn is_ignored(&self, ip: &IpAddr) -> bool {
+        let mut offenders = self.0.lock().await;
+        offenders.retain(|_, offender| offender.last.elapsed() < OFFENCE_COOLDOWN);
+
+        offenders
+            .get(ip)
+            .is_some_and(|offender| offender.count >= OFFENCE_LIMIT)
+    }
+
+    /// Record a failed connection from the given address, returning `true`
+    /// if connections from the address are now ignored.
+    pub async fn record(&self, ip: IpAddr) -> bool {
+        let mut offenders = self.0.lock().await;
+        let offender = offenders.entry(ip).or_insert(Offender {
+            count: 0,
+            last: Instant::now(),
+        });
+        if offender.last.elapsed() >= OFFENCE_COOLDOWN {
+            offender.count = 0;
+        }
+        offender.count += 1;
+        offender.last = Instant::now();
+
+        offender.count >= OFFENCE_LIMIT
+    }
+}
@@ -0,0 +1,66 @@
+//! Filesystem locations used by cabin.
+
+use std::{env, path::PathBuf};
+
+/// Return the directory in which cabin stores local data (such as input
+/// history).
+///
+/// Respects `XDG_DATA_HOME`, falling back to `~/.local/share/cabin` and then
+/// to the current directory if no home directory is defined.
+pub fn data_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+        PathBuf::from(dir).join("cabin")
+    } else if let Some(home) = env::var_os("HOME").filter(|home| !home.is_empty()) {
+        PathBuf::from(home).join(".local/share/cabin")
+    } else {
+        PathBuf::from(".cabin")
+    }
+}
+
+/// Return the directory from which cabin reads its configuration file.
+///
+/// Respects `XDG_CONFIG_HOME`, falling back to `~/.config/cabin` and then
+/// to the current directory if no home directory is defined.
+pub fn config_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        PathBuf::from(dir).join("cabin")
+    } else if let Some(home) = env::var_os("HOME").filter(|home| !home.is_empty()) {
+        PathBuf::from(home).join(".config/cabin")
+    } else {
+        PathBuf::from(".cabin")
+    }
+}
+
+/// Return the directory in which cabin stores state which should persist
+/// across restarts of the daemon (such as its session).
+///
+/// Respects `STATE_DIRECTORY` (as set by systemd for units with a
+/// `StateDirectory=`) and `XDG_STATE_HOME`, falling back to
+/// `~/.local/state/cabin` and then to the current directory if no home
+/// directory is defined.
+pub fn state_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("STATE_DIRECTORY").filter(|dir| !dir.is_empty()) {
+        PathBuf::from(dir)
+    } else if let Some(dir) = env::var_os("XDG_STATE_HOME").filter(|dir| !dir.is_empty()) {
+        PathBuf::from(dir).join("cabin")
+    } else if let Some(home) = env::var_os("HOME").filter(|home| !home.is_empty()) {
+        PathBuf::from(home).join(".local/state/cabin")
+    } else {
+        PathBuf::from(".cabin")
+    }
+}
+
+/// Return the directory in which the daemon writes its pid file.
+///
+/// Respects `RUNTIME_DIRECTORY` (as set by systemd for units with a
+/// `RuntimeDirectory=`) and `XDG_RUNTIME_DIR`, falling back to the state
+/// directory.
+pub fn runtime_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("RUNTIME_DIRECTORY").filter(|dir| !dir.is_empty()) {
+        PathBuf::from(dir)
+    } else if let Some(dir) = env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+        PathBuf::from(dir).join("cabin")
+    } else {
+        state_dir()
+    }
+}
@@ -0,0 +1,156 @@
+//! Rendering of channel history as static HTML pages.
+//!
+//! A channel is published as one page per day on which posts were made,
+//! along with an index page linking to each day. Pages are self-contained
+//! (styles are inlined) so that the directory can be served by any static
+//! web server.
+
+use std::{fs, io, path::Path};
+
+use owo_colors::AnsiColors;
+
+use crate::{hex, time, ui::PublicKey, utils};
+
+/// The styles shared by all pages.
+const STYLE: &str = "body { background: #1d1f21; color: #c5c8c6; font-family: monospace; \
+max-width: 60em; margin: 2em auto; padding: 0 1em; } \
+a { color: #81a2be; } h2 { border-bottom: 1px solid #373b41; } \
+.post { margin: 0.2em 0; white-space: pre-wrap; } .time { color: #707880; }";
+
+/// A text post to be published.
+pub struct Entry {
+    pub timestamp: u64,
+    pub author: PublicKey,
+    /// The nickname of the author, or their abbreviated public key.
+    pub name: String,
+    pub text: String,
+}
+
+/// Escape text for inclusion in HTML.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Return the CSS colour equivalent to the given terminal colour.
+fn css_colour(colour: AnsiColors) -> &'static str {
+    match colour {
+        AnsiColors::Red => "#cc6666",
+        AnsiColors::Green => "#8c9440",
+        AnsiColors::Yellow => "#de935f",
+        AnsiColors::Blue => "#5f819d",
+        AnsiColors::Magenta => "#85678f",
+        AnsiColors::Cyan => "#5e8d87",
+        AnsiColors::BrightRed => "#ff7b7b",
+        AnsiColors::BrightGreen => "#b5bd68",
+        AnsiColors::BrightYellow => "#f0c674",
+        AnsiColors::BrightBlue => "#81a2be",
+        AnsiColors::BrightMagenta => "#b294bb",
+        AnsiColors::BrightCyan => "#8abeb7",
+        _ => "#c5c8c6",
+    }
+}
+
+/// Wrap the given body in an HTML document with the given title.
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        body
+    )
+}
+
+/// Render a single post.
+fn render_entry(entry: &Entry) -> String {
+    // The reference of a reply is not meaningful to readers of the archive.
+    let text = match utils::parse_reply(&entry.text) {
+        Some((_hash, text)) => format!("↳ {}", text),
+        None => entry.text.to_owned(),
+    };
+
+    format!(
+        "<div class=\"post\"><span class=\"time\">[{}]</span> &lt;<span style=\"color: {}\" title=\"{}\">{}</span>&gt; {}</div>\n",
+        time::format(entry.timestamp),
+        css_colour(utils::public_key_to_colour(&entry.author)),
+        hex::to(&entry.author),
+        escape(&entry.name),
+        escape(&text)
+    )
+}
+
+/// Write the given posts of the channel to the given directory, creating it
+/// if necessary.
+///
+/// Posts are grouped by the day (in the local timezone) on which they were
+/// made. Returns the number of day pages written.
+pub fn publish(dir: &Path, channel: &str, topic: &str, entries: &[Entry]) -> io::Result<usize> {
+    fs::create_dir_all(dir)?;
+
+    let mut days: Vec<(String, Vec<&Entry>)> = Vec::new();
+    for entry in entries {
+        let day = time::format_day(entry.timestamp);
+        match days.last_mut() {
+            Some((last, posts)) if *last == day => posts.push(entry),
+            _ => days.push((day, vec![entry])),
+        }
+    }
+
+    let title = format!("#{}", channel);
+    let mut index = format!("<h1>{}</h1>\n", escape(&title));
+    if !topic.is_empty() {
+        index.push_str(&format!("<p>{}</p>\n", escape(topic)));
+    }
+    index.push_str("<ul>\n");
+
+    for (i, (day, posts)) in days.iter().enumerate() {
+        let mut body = format!(
+            "<p><a href=\"index.html\">{}</a></p>\n<h2>{}</h2>\n",
+            escape(&title),
+            day
+        );
+        for entry in posts {
+            body.push_str(&render_entry(entry));
+        }
+
+        // Link to the neighbouring days.
+        let mut nav = Vec::new();
+        if let Some((previous, _posts)) = i.checked_sub(1).and_then(|i| days.get(i)) {
+            nav.push(format!("<a href=\"{0}.html\">&larr; {0}</a>", previous));
+        }
+        if let Some((next, _posts)) = days.get(i + 1) {
+            nav.push(format!("<a href=\"{0}.html\">{0} &rarr;</a>", next));
+        }
+        if !nav.is_empty() {
+            body.push_str(&format!("<p>{}</p>\n", nav.join(" | ")));
+        }
+
+        fs::write(
+            dir.join(format!("{}.html", day)),
+            page(&format!("{} {}", title, day), &body),
+        )?;
+        index.push_str(&format!(
+            "<li><a href=\"{0}.html\">{0}</a> ({1} posts)</li>\n",
+            day,
+            posts.len()
+        ));
+    }
+    index.push_str("</ul>\n");
+
+    fs::write(dir.join("index.html"), page(&title, &index))?;
+
+    Ok(days.len())
+}
@@ -0,0 +1,58 @@
+//! Redaction of sensitive input.
+//!
+//! Lines of input matching any of the `redact` patterns of the configuration
+//! file (e.g. `^/key import`) are never recorded in the input history, and
+//! only the command of such a line is echoed to the status window (which is
+//! logged when running as a daemon).
+
+use std::borrow::Cow;
+
+use regex::Regex;
+
+use crate::i18n::tr_args;
+
+/// The text echoed in place of the arguments of a redacted command.
+const REDACTED: &str = "[redacted]";
+
+/// The patterns of the lines of input to be redacted.
+#[derive(Debug, Default)]
+pub struct Redaction(Vec<Regex>);
+
+impl Redaction {
+    /// Add the given patterns, as defined by the `redact` entry of the
+    /// configuration file.
+    ///
+    /// Returns a description of each invalid pattern; valid patterns are
+    /// added regardless.
+    pub fn configure(&mut self, patterns: &[String]) -> Vec<String> {
+        let mut errors = Vec::new();
+        for pattern in patterns {
+            match Regex::new(pattern) {
+                Ok(regex) => self.0.push(regex),
+                Err(err) => errors.push(tr_args("invalid redact pattern {}: {}", &[pattern, &err])),
+            }
+        }
+
+        errors
+    }
+
+    /// Determine whether the given line of input is to be redacted.
+    pub fn matches(&self, line: &str) -> bool {
+        self.0.iter().any(|regex| regex.is_match(line))
+    }
+
+    /// Return the given line of input as it may be echoed: with everything
+    /// but the command replaced if the line is to be redacted.
+    pub fn echo<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        if !self.matches(line) {
+            return Cow::Borrowed(line);
+        }
+
+        match line.split_whitespace().next() {
+            Some(command) if command.starts_with('/') => {
+                Cow::Owned(format!("{} {}", command, REDACTED))
+            }
+            _ => Cow::Borrowed(REDACTED),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Full-text search of text posts.
+
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+};
+
+use async_std::sync::{Arc, Mutex};
+use cable::{Channel, Hash, Text, Timestamp};
+
+use crate::ui::{Addr, PublicKey};
+
+/// A text post held by the index.
+#[derive(Clone, Debug)]
+pub struct Document {
+    pub channel: Channel,
+    pub timestamp: Timestamp,
+    pub author: PublicKey,
+    pub text: Text,
+}
+
+/// The inverted index of the text posts of a single cabal.
+#[derive(Default)]
+struct Index {
+    documents: HashMap<Hash, Document>,
+    /// The hashes of the posts containing each term.
+    postings: HashMap<String, HashSet<Hash>>,
+}
+
+/// An inverted index of text posts, keyed by cabal address.
+///
+/// The index is updated incrementally as posts are read from the store or
+/// received from peers, so that searching does not require a scan of every
+/// stored post.
+#[derive(Clone, Default)]
+pub struct SearchIndex(Arc<Mutex<HashMap<Addr, Index>>>);
+
+/// Split the given text into lowercase alphanumeric terms.
+fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(str::to_lowercase)
+}
+
+impl SearchIndex {
+    /// Add a text post of the given cabal to the index.
+    pub async fn insert(&self, address: &Addr, hash: Hash, document: Document) {
+        let mut indexes = self.0.lock().await;
+        let index = indexes.entry(address.to_owned()).or_default();
+        if index.documents.contains_key(&hash) {
+            return;
+        }
+
+        for term in terms(&document.text) {
+            index.postings.entry(term).or_default().insert(hash);
+        }
+        index.documents.insert(hash, document);
+    }
+
+    /// Remove a post of the given cabal from the index.
+    pub async fn remove(&self, address: &Addr, hash: &Hash) {
+        let mut indexes = self.0.lock().await;
+        let Some(index) = indexes.get_mut(address) else {
+            return;
+        };
+        let Some(document) = index.documents.remove(hash) else {
+            return;
+        };
+
+        for term in terms(&document.text) {
+            if let Some(hashes) = index.postings.get_mut(&term) {
+                hashes.remove(hash);
+                if hashes.is_empty() {
+                    index.postings.remove(&term);
+                }
+            }
+        }
+    }
+
+    /// Remove all posts of the given cabal from the index.
+    pub async fn clear(&self, address: &Addr) {
+        self.0.lock().await.remove(address);
+    }
+
+    /// Return the number of posts of the given cabal held by the index.
+    pub async fn len(&self, address: &Addr) -> usize {
+        self.0
+            .lock()
+            .await
+            .get(address)
+            .map_or(0, |index| index.documents.len())
+    }
+
+    /// Return the posts of the given cabal containing every term of the
+    /// query, most recent first, up to the given limit.
+    ///
+    /// Terms match whole words, case-insensitively.
+    pub async fn search(&self, address: &Addr, query: &str, limit: usize) -> Vec<Document> {
+        let indexes = self.0.lock().await;
+        let Some(index) = indexes.get(address) else {
+            return Vec::new();
+        };
+
+        let mut matches: Option<HashSet<Hash>> = None;
+        for term in terms(query) {
+            let hashes = index.postings.get(&term).cloned().unwrap_or_default();
+            matches = Some(match matches {
+                Some(matches) => matches.intersection(&hashes).copied().collect(),
+                None => hashes,
+            });
+        }
+
+        let mut documents: Vec<Document> = matches
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|hash| index.documents.get(hash).cloned())
+            .collect();
+        documents.sort_by_key(|document| Reverse(document.timestamp));
+        documents.truncate(limit);
+
+        documents
+    }
+}
@@ -0,0 +1,89 @@
+//! Session state export and import.
+//!
+//! A session captures the known cabals (including aliases and joined
+//! channels), peer connections and settings as a portable TOML document,
+//! allowing a setup to be replicated on another machine with
+//! `/session load FILE`.
+
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+/// A single cabal entry in a session document.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CabalEntry {
+    /// The hex-encoded cabal address (key).
+    pub address: String,
+    /// An optional local alias for the cabal.
+    pub alias: Option<String>,
+    /// How far back channels are requested when joined (`all` or a duration
+    /// such as `1d`), if other than the default of two weeks.
+    pub backfill: Option<String>,
+    /// The channels which have been joined for this cabal.
+    #[serde(default)]
+    pub channels: Vec<String>,
+    /// Hex-encoded hashes of pinned posts, keyed by channel.
+    #[serde(default)]
+    pub pins: BTreeMap<String, Vec<String>>,
+    /// Channels omitted from the channel list.
+    #[serde(default)]
+    pub hidden: Vec<String>,
+    /// TCP addresses (host:port) on which to listen for connections to this
+    /// cabal.
+    #[serde(default)]
+    pub listen: Vec<String>,
+    /// TCP addresses (host:port) of peers to connect to for this cabal.
+    #[serde(default)]
+    pub connect: Vec<String>,
+}
+
+/// A channel window in a session document, listed in display order.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowEntry {
+    /// The hex-encoded cabal address (key).
+    pub address: String,
+    /// The channel displayed by the window.
+    pub channel: String,
+}
+
+/// The portable session document.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    /// The hex-encoded address of the active cabal.
+    pub active: Option<String>,
+    /// Hex-encoded public keys of blocked peers.
+    #[serde(default)]
+    pub blocked: Vec<String>,
+    /// Ignored post types, keyed by the hex-encoded public key of the author.
+    #[serde(default)]
+    pub ignored: BTreeMap<String, Vec<String>>,
+    /// User-configurable settings.
+    #[serde(default)]
+    pub settings: Settings,
+    /// All known cabals.
+    #[serde(default)]
+    pub cabals: Vec<CabalEntry>,
+    /// The channel windows of all cabals, in the order in which they are
+    /// displayed.
+    #[serde(default)]
+    pub windows: Vec<WindowEntry>,
+}
+
+impl Session {
+    /// Read and parse a session document from the given file path.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+
+        toml::from_str(&contents).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Serialize the session document and write it to the given file path.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let contents =
+            toml::to_string(self).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        fs::write(path, contents)
+    }
+}
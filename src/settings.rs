@@ -0,0 +1,404 @@
+//! User-configurable settings.
+//!
+//! Settings are modified at runtime with the `/set KEY VALUE` command and are
+//! included in saved session documents.
+
+use std::env;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    i18n::tr_args,
+    time::{self, Clock},
+};
+
+/// Determines whether newly created channel windows become active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Autofocus {
+    /// Newly created windows never become active.
+    Never,
+    /// Windows created by an interactive `/join` become active; those
+    /// created by restoring a session do not.
+    Command,
+    /// Newly created windows always become active.
+    Always,
+}
+
+impl Autofocus {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "never" => Ok(Autofocus::Never),
+            "command" => Ok(Autofocus::Command),
+            "always" => Ok(Autofocus::Always),
+            _ => Err(tr_args(
+                "expected never, command or always but received {}",
+                &[&value],
+            )),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Autofocus::Never => "never",
+            Autofocus::Command => "command",
+            Autofocus::Always => "always",
+        }
+    }
+}
+
+/// Determines which lines of input are recorded in the input history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryMode {
+    /// Record all commands and messages.
+    All,
+    /// Record commands but not messages.
+    Commands,
+    /// Record nothing.
+    Off,
+}
+
+impl HistoryMode {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "all" => Ok(HistoryMode::All),
+            "commands" => Ok(HistoryMode::Commands),
+            "off" => Ok(HistoryMode::Off),
+            _ => Err(tr_args(
+                "expected all, commands or off but received {}",
+                &[&value],
+            )),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            HistoryMode::All => "all",
+            HistoryMode::Commands => "commands",
+            HistoryMode::Off => "off",
+        }
+    }
+
+    /// Determine whether the given line of input should be recorded.
+    pub fn records(&self, line: &str) -> bool {
+        match self {
+            _ if line.trim().is_empty() => false,
+            HistoryMode::All => true,
+            HistoryMode::Commands => line.starts_with('/'),
+            HistoryMode::Off => false,
+        }
+    }
+}
+
+/// Determines how colours are assigned to peers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Palette {
+    /// Each peer is assigned a colour derived from their public key.
+    Default,
+    /// Peers are assigned high-contrast colours, chosen such that the most
+    /// frequent posters of each channel do not share a colour.
+    Contrast,
+}
+
+impl Palette {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "default" => Ok(Palette::Default),
+            "contrast" => Ok(Palette::Contrast),
+            _ => Err(tr_args(
+                "expected default or contrast but received {}",
+                &[&value],
+            )),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Palette::Default => "default",
+            Palette::Contrast => "contrast",
+        }
+    }
+}
+
+/// Determines which IP versions `/listen PORT` accepts connections over when
+/// no host is given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListenMode {
+    /// Listen on both the IPv6 and IPv4 wildcard addresses.
+    Dual,
+    /// Listen on the IPv4 wildcard address only.
+    Ipv4,
+    /// Listen on the IPv6 wildcard address only.
+    Ipv6,
+}
+
+impl ListenMode {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "dual" => Ok(ListenMode::Dual),
+            "ipv4" => Ok(ListenMode::Ipv4),
+            "ipv6" => Ok(ListenMode::Ipv6),
+            _ => Err(tr_args(
+                "expected dual, ipv4 or ipv6 but received {}",
+                &[&value],
+            )),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            ListenMode::Dual => "dual",
+            ListenMode::Ipv4 => "ipv4",
+            ListenMode::Ipv6 => "ipv6",
+        }
+    }
+
+    /// Return the wildcard addresses to listen on.
+    ///
+    /// The IPv6 address comes first: on systems where an IPv6 socket also
+    /// accepts IPv4 connections, the IPv4 address is then already in use.
+    pub fn wildcards(&self) -> &'static [&'static str] {
+        match self {
+            ListenMode::Dual => &["[::]", "0.0.0.0"],
+            ListenMode::Ipv4 => &["0.0.0.0"],
+            ListenMode::Ipv6 => &["[::]"],
+        }
+    }
+}
+
+/// A daily period, in local time, during which notifications are suppressed.
+///
+/// Both times are given in minutes since midnight. A period whose end is
+/// before its start spans midnight. Periods are (de)serialized in
+/// `HH:MM-HH:MM` form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct QuietHours {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl QuietHours {
+    /// Parse a period in `HH:MM-HH:MM` form.
+    fn parse(value: &str) -> Result<Self, String> {
+        let err = || {
+            tr_args(
+                "expected a period such as 22:00-08:00 or off but received {}",
+                &[&value],
+            )
+        };
+        let (start, end) = value.split_once(['-', '–']).ok_or_else(err)?;
+        let start = time::parse_minute_of_day(start.trim()).ok_or_else(err)?;
+        let end = time::parse_minute_of_day(end.trim()).ok_or_else(err)?;
+
+        Ok(QuietHours { start, end })
+    }
+
+    /// Determine whether the given minute of the day falls within the
+    /// period.
+    pub fn contains(&self, minute: u32) -> bool {
+        if self.start <= self.end {
+            minute >= self.start && minute < self.end
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+}
+
+impl TryFrom<String> for QuietHours {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, String> {
+        QuietHours::parse(&value)
+    }
+}
+
+impl From<QuietHours> for String {
+    fn from(quiet: QuietHours) -> String {
+        format!(
+            "{}-{}",
+            time::format_minute_of_day(quiet.start),
+            time::format_minute_of_day(quiet.end)
+        )
+    }
+}
+
+/// All user-configurable settings and their values.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Emit a terminal bell when the local peer is mentioned.
+    pub bell: bool,
+    /// Path of a file to which the unread / mention state is written, for
+    /// consumption by tmux (or screen) status lines.
+    pub status_file: Option<String>,
+    /// The number of seconds without input after which the UI is considered
+    /// idle and rendering is throttled. A value of 0 disables throttling.
+    pub idle: u64,
+    /// Display the peers who have likely synced the latest post of the local
+    /// peer in the channel header.
+    pub receipts: bool,
+    /// The number of seconds after a change of active window during which
+    /// sending a post requires confirmation. A value of 0 disables the guard.
+    pub guard: u64,
+    /// Whether newly created channel windows become active.
+    pub autofocus: Autofocus,
+    /// Which lines of input are recorded (and persisted) in the input
+    /// history.
+    pub history: HistoryMode,
+    /// Use vi-style modal editing for the input line.
+    pub vi: bool,
+    /// The number of seconds after which the time range request of each
+    /// joined channel is renewed, so that history obtained by peers after
+    /// the channel was joined is received. A value of 0 disables renewal.
+    pub refresh: u64,
+    /// Render colours and other text attributes. Disabled by default if the
+    /// `NO_COLOR` environment variable is set.
+    pub color: bool,
+    /// How colours are assigned to peers.
+    pub palette: Palette,
+    /// The daily period during which bells and mention notifications are
+    /// suppressed, if any.
+    pub quiet: Option<QuietHours>,
+    /// The number of posts which may be sent within a second before sending
+    /// is paused and further posts require confirmation. A value of 0
+    /// disables the protection.
+    pub flood: usize,
+    /// Display the notes set with `/note` after the names of peers.
+    pub notes: bool,
+    /// Display command output longer than a screenful of the status window
+    /// one page at a time.
+    pub pager: bool,
+    /// Which IP versions `/listen PORT` accepts connections over.
+    pub listen: ListenMode,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            bell: false,
+            status_file: None,
+            idle: 60,
+            receipts: false,
+            guard: 0,
+            autofocus: Autofocus::Command,
+            history: HistoryMode::All,
+            vi: false,
+            refresh: 0,
+            color: env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()),
+            palette: Palette::Default,
+            quiet: None,
+            flood: 5,
+            notes: false,
+            pager: true,
+            listen: ListenMode::Dual,
+        }
+    }
+}
+
+impl Settings {
+    /// Update the setting identified by the given key with the given value.
+    ///
+    /// Returns a description of the problem if the key is unknown or the
+    /// value is invalid.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "bell" => self.bell = parse_bool(value)?,
+            "receipts" => self.receipts = parse_bool(value)?,
+            "vi" => self.vi = parse_bool(value)?,
+            "notes" => self.notes = parse_bool(value)?,
+            "pager" => self.pager = parse_bool(value)?,
+            "statusfile" => {
+                self.status_file = match value {
+                    "off" => None,
+                    path => Some(path.to_owned()),
+                }
+            }
+            "idle" => self.idle = parse_seconds(value)?,
+            "guard" => self.guard = parse_seconds(value)?,
+            "autofocus" => self.autofocus = Autofocus::parse(value)?,
+            "history" => self.history = HistoryMode::parse(value)?,
+            "refresh" => self.refresh = parse_seconds(value)?,
+            "color" => self.color = parse_bool(value)?,
+            "palette" => self.palette = Palette::parse(value)?,
+            "listen" => self.listen = ListenMode::parse(value)?,
+            "flood" => {
+                self.flood = value
+                    .parse()
+                    .map_err(|_| tr_args("expected a number of posts but received {}", &[&value]))?
+            }
+            "quiet" => {
+                self.quiet = match value {
+                    "off" => None,
+                    period => Some(QuietHours::parse(period)?),
+                }
+            }
+            _ => return Err(tr_args("unknown setting: {}", &[&key])),
+        }
+
+        Ok(())
+    }
+
+    /// Return the key and formatted value of every setting.
+    pub fn list(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("bell", format_bool(self.bell)),
+            (
+                "statusfile",
+                self.status_file
+                    .clone()
+                    .unwrap_or_else(|| "off".to_string()),
+            ),
+            ("idle", self.idle.to_string()),
+            ("receipts", format_bool(self.receipts)),
+            ("guard", self.guard.to_string()),
+            ("autofocus", self.autofocus.as_str().to_string()),
+            ("history", self.history.as_str().to_string()),
+            ("vi", format_bool(self.vi)),
+            ("refresh", self.refresh.to_string()),
+            ("color", format_bool(self.color)),
+            ("palette", self.palette.as_str().to_string()),
+            ("flood", self.flood.to_string()),
+            ("notes", format_bool(self.notes)),
+            ("pager", format_bool(self.pager)),
+            ("listen", self.listen.as_str().to_string()),
+            (
+                "quiet",
+                self.quiet
+                    .map(String::from)
+                    .unwrap_or_else(|| "off".to_string()),
+            ),
+        ]
+    }
+
+    /// Determine whether the current local time, as read from the given
+    /// clock, falls within the quiet hours, if set.
+    pub fn is_quiet(&self, clock: &dyn Clock) -> bool {
+        self.quiet
+            .is_some_and(|quiet| quiet.contains(time::local_minute_of_day(clock)))
+    }
+}
+
+/// Parse an on / off setting value.
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value {
+        "on" | "true" | "yes" => Ok(true),
+        "off" | "false" | "no" => Ok(false),
+        _ => Err(tr_args("expected on or off but received {}", &[&value])),
+    }
+}
+
+/// Parse a setting value defined as a number of seconds.
+fn parse_seconds(value: &str) -> Result<u64, String> {
+    value
+        .parse()
+        .map_err(|_| tr_args("expected a number of seconds but received {}", &[&value]))
+}
+
+/// Format an on / off setting value.
+fn format_bool(value: bool) -> String {
+    if value { "on" } else { "off" }.to_string()
+}
@@ -0,0 +1,172 @@
+//! Persistence of the store of each cabal.
+//!
+//! The store of a cabal is saved to its own directory (see
+//! `client::store_dir()`), so that the posts of different cabals are never
+//! mixed: the keypair of the local peer is written to `keypair` and the posts
+//! of every known channel to `posts`. Each post is written in its cable
+//! encoding, preceded by its length as a big-endian `u32`.
+//!
+//! Posts which do not belong to a channel (e.g. nicknames) are not saved;
+//! they are requested from peers again after a restart.
+
+use std::{
+    fs,
+    io::{self, Write},
+    os::unix::fs::OpenOptionsExt,
+    path::Path,
+    sync::Mutex,
+};
+
+use async_std::prelude::*;
+use cable::{post::Post, ChannelOptions, FromBytes, ToBytes};
+use cable_core::Store;
+use log::warn;
+
+use crate::{client, hex, ui::Addr};
+
+/// The name of the file holding the keypair of the local peer.
+const KEYPAIR_FILE: &str = "keypair";
+
+/// The name of the file holding the posts of the cabal.
+const POSTS_FILE: &str = "posts";
+
+/// Held while the files of a store are written, as a store may be saved
+/// periodically and on exit at the same time.
+static WRITING: Mutex<()> = Mutex::new(());
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Return a new store for the cabal with the given address, holding the
+/// keypair and posts saved by `save()`.
+///
+/// Returns an empty store if nothing has been saved for the cabal. If the
+/// saved files cannot be read, they are renamed with a `.bak` suffix (so that
+/// saving a new store does not replace them) and the error is returned.
+pub async fn open<S: Store + Default>(address: &Addr) -> io::Result<S> {
+    let dir = client::store_dir(address);
+    let mut store = S::default();
+    match load(&dir, &mut store).await {
+        Ok(()) => Ok(store),
+        Err(err) => {
+            for name in [KEYPAIR_FILE, POSTS_FILE] {
+                let path = dir.join(name);
+                if path.exists() {
+                    fs::rename(&path, path.with_extension("bak"))?;
+                }
+            }
+            Err(err)
+        }
+    }
+}
+
+/// Insert the keypair and posts saved in the given directory into the store.
+async fn load<S: Store>(dir: &Path, store: &mut S) -> io::Result<()> {
+    let path = dir.join(KEYPAIR_FILE);
+    match fs::read_to_string(&path) {
+        Ok(contents) => {
+            let mut keys = contents.lines().filter_map(hex::from);
+            let keypair = keys
+                .next()
+                .and_then(|public_key| public_key.try_into().ok())
+                .zip(
+                    keys.next()
+                        .and_then(|secret_key| secret_key.try_into().ok()),
+                )
+                .ok_or_else(|| {
+                    invalid_data(format!("{} is not a valid keypair", path.display()))
+                })?;
+            store.set_keypair(keypair).await;
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+    }
+
+    let path = dir.join(POSTS_FILE);
+    let contents = match fs::read(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    let mut rest = contents.as_slice();
+    while let Some((len, tail)) = rest.split_first_chunk::<4>() {
+        let len = u32::from_be_bytes(*len) as usize;
+        if tail.len() < len {
+            break;
+        }
+        let (bytes, tail) = tail.split_at(len);
+        match Post::from_bytes(bytes) {
+            Ok(post) => {
+                if let Err(err) = store.insert_post(&post).await {
+                    warn!("Failed to restore post: {}", err);
+                }
+            }
+            Err(err) => warn!("Skipped undecodable post: {}", err),
+        }
+        rest = tail;
+    }
+    if !rest.is_empty() {
+        return Err(invalid_data(format!(
+            "{} ends with a truncated post",
+            path.display()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Save the keypair and the posts of every known channel held by the store
+/// of the cabal with the given address.
+///
+/// The files are replaced atomically, so an interrupted save leaves the
+/// previous files intact.
+pub async fn save<S: Store>(address: &Addr, store: &S) -> io::Result<()> {
+    let keypair = store.get_keypair().await;
+
+    let mut posts = Vec::new();
+    let mut reader = store.clone();
+    for channel in store.get_channels().await.unwrap_or_default() {
+        let opts = ChannelOptions {
+            channel,
+            time_start: 0,
+            time_end: 0,
+            limit: 0,
+        };
+        let mut stream = reader.get_posts(&opts).await;
+        while let Some(Ok(post)) = stream.next().await {
+            match post.to_bytes() {
+                Ok(bytes) => {
+                    posts.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+                    posts.extend_from_slice(&bytes);
+                }
+                Err(err) => warn!("Skipped unencodable post: {}", err),
+            }
+        }
+    }
+
+    let _writing = WRITING.lock().unwrap_or_else(|err| err.into_inner());
+    let dir = client::store_dir(address);
+    fs::create_dir_all(&dir)?;
+    if let Some((public_key, secret_key)) = keypair {
+        let contents = format!("{}\n{}\n", hex::to(&public_key), hex::to(&secret_key));
+        replace(&dir.join(KEYPAIR_FILE), contents.as_bytes())?;
+    }
+    replace(&dir.join(POSTS_FILE), &posts)
+}
+
+/// Replace the file at the given path with the given contents, which only
+/// the current user may read.
+fn replace(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let temp = path.with_extension("tmp");
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&temp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+
+    fs::rename(temp, path)
+}
@@ -0,0 +1,228 @@
+//! Detection of the address at which the local peer is reachable.
+//!
+//! The public IP address is discovered with a STUN binding request (RFC 5389)
+//! to the server set in the configuration file. STUN reports the address of
+//! the UDP mapping created by the request; it matches the address of TCP
+//! listeners unless the NAT assigns addresses per protocol, and says nothing
+//! about whether the listening port is forwarded.
+
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use async_std::{future, net::UdpSocket};
+
+/// The time to wait for a response from the STUN server.
+const STUN_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// The magic cookie included in every STUN message.
+const MAGIC_COOKIE: u32 = 0x2112_a442;
+
+const BINDING_REQUEST: u16 = 0x0001;
+const BINDING_RESPONSE: u16 = 0x0101;
+const MAPPED_ADDRESS: u16 = 0x0001;
+const XOR_MAPPED_ADDRESS: u16 = 0x0020;
+
+/// Query the given STUN server (host:port) for the public IP address of the
+/// local peer.
+pub async fn public_ip(server: &str) -> io::Result<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    socket.connect(server).await?;
+
+    let transaction = transaction_id();
+    let mut request = Vec::with_capacity(20);
+    request.extend_from_slice(&BINDING_REQUEST.to_be_bytes());
+    request.extend_from_slice(&0u16.to_be_bytes());
+    request.extend_from_slice(&MAGIC_COOKIE.to_be_bytes());
+    request.extend_from_slice(&transaction);
+    socket.send(&request).await?;
+
+    let mut buf = [0; 512];
+    let len = future::timeout(STUN_TIMEOUT, socket.recv(&mut buf))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "no response from stun server"))??;
+
+    parse_response(&buf[..len], &transaction)
+        .map(|addr| addr.ip())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid stun response"))
+}
+
+/// Return the IP address of the interface used to reach the internet.
+///
+/// No packets are sent: connecting a UDP socket only selects a route.
+pub async fn local_ip() -> io::Result<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    socket.connect("192.0.2.1:9").await?;
+
+    Ok(socket.local_addr()?.ip())
+}
+
+/// Generate a transaction ID which is unlikely to repeat.
+fn transaction_id() -> [u8; 12] {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+
+    let mut id = [0; 12];
+    id.copy_from_slice(&nanos.to_be_bytes()[4..]);
+    id[..4].copy_from_slice(&std::process::id().to_be_bytes());
+    id
+}
+
+/// Parse the mapped address from a binding response to the request with the
+/// given transaction ID.
+fn parse_response(msg: &[u8], transaction: &[u8; 12]) -> Option<SocketAddr> {
+    if msg.len() < 20
+        || u16::from_be_bytes([msg[0], msg[1]]) != BINDING_RESPONSE
+        || msg[4..8] != MAGIC_COOKIE.to_be_bytes()
+        || &msg[8..20] != transaction
+    {
+        return None;
+    }
+
+    let mut mapped = None;
+    let mut attrs = &msg[20..];
+    while attrs.len() >= 4 {
+        let kind = u16::from_be_bytes([attrs[0], attrs[1]]);
+        let len = u16::from_be_bytes([attrs[2], attrs[3]]) as usize;
+        let value = attrs.get(4..4 + len)?;
+        match kind {
+            XOR_MAPPED_ADDRESS => return parse_address(value, Some(&msg[4..20])),
+            MAPPED_ADDRESS => mapped = parse_address(value, None),
+            _ => (),
+        }
+        // Attributes are padded to a multiple of four bytes.
+        attrs = attrs.get(4 + len.next_multiple_of(4)..).unwrap_or_default();
+    }
+
+    mapped
+}
+
+/// Parse an address attribute, XOR-ed with the given magic cookie and
+/// transaction ID if given.
+fn parse_address(value: &[u8], xor: Option<&[u8]>) -> Option<SocketAddr> {
+    let mask = |i: usize| xor.map_or(0, |xor| xor[i]);
+    let port = u16::from_be_bytes([value.get(2)? ^ mask(0), value.get(3)? ^ mask(1)]);
+    let ip = match value.get(1)? {
+        0x01 => {
+            let mut octets = [0; 4];
+            for (i, octet) in octets.iter_mut().enumerate() {
+                *octet = value.get(4 + i)? ^ mask(i);
+            }
+            IpAddr::V4(Ipv4Addr::from(octets))
+        }
+        0x02 => {
+            let mut octets = [0; 16];
+            for (i, octet) in octets.iter_mut().enumerate() {
+                *octet = value.get(4 + i)? ^ mask(i);
+            }
+            IpAddr::V6(Ipv6Addr::from(octets))
+        }
+        _ => return None,
+    };
+
+    Some(SocketAddr::new(ip, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The transaction ID and addresses of the sample responses of RFC 5769.
+    const TRANSACTION: [u8; 12] = [
+        0xb7, 0xe7, 0xa7, 0x01, 0xbc, 0x34, 0xd6, 0x86, 0xfa, 0x87, 0xdf, 0xae,
+    ];
+
+    /// Build a binding response with the given attributes.
+    fn response(attrs: &[u8]) -> Vec<u8> {
+        let mut msg = Vec::new();
+        msg.extend_from_slice(&BINDING_RESPONSE.to_be_bytes());
+        msg.extend_from_slice(&(attrs.len() as u16).to_be_bytes());
+        msg.extend_from_slice(&MAGIC_COOKIE.to_be_bytes());
+        msg.extend_from_slice(&TRANSACTION);
+        msg.extend_from_slice(attrs);
+        msg
+    }
+
+    #[test]
+    fn parses_xor_mapped_ipv4_address() {
+        // SOFTWARE ("test vector"), padded to a multiple of four bytes.
+        let software = [
+            0x80, 0x22, 0x00, 0x0b, 0x74, 0x65, 0x73, 0x74, 0x20, 0x76, 0x65, 0x63, 0x74, 0x6f,
+            0x72, 0x20,
+        ];
+        let xor_mapped = [
+            0x00, 0x20, 0x00, 0x08, 0x00, 0x01, 0xa1, 0x47, 0xe1, 0x12, 0xa6, 0x43,
+        ];
+        let msg = response(&[&software[..], &xor_mapped[..]].concat());
+
+        assert_eq!(
+            parse_response(&msg, &TRANSACTION),
+            Some("192.0.2.1:32853".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn parses_xor_mapped_ipv6_address() {
+        let msg = response(&[
+            0x00, 0x20, 0x00, 0x14, 0x00, 0x02, 0xa1, 0x47, 0x01, 0x13, 0xa9, 0xfa, 0xa5, 0xd3,
+            0xf1, 0x79, 0xbc, 0x25, 0xf4, 0xb5, 0xbe, 0xd2, 0xb9, 0xd9,
+        ]);
+
+        assert_eq!(
+            parse_response(&msg, &TRANSACTION),
+            Some(
+                "[2001:db8:1234:5678:11:2233:4455:6677]:32853"
+                    .parse()
+                    .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn falls_back_to_mapped_address() {
+        let msg = response(&[
+            0x00, 0x01, 0x00, 0x08, 0x00, 0x01, 0x80, 0x55, 0xc0, 0x00, 0x02, 0x01,
+        ]);
+
+        assert_eq!(
+            parse_response(&msg, &TRANSACTION),
+            Some("192.0.2.1:32853".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_responses() {
+        let msg = response(&[
+            0x00, 0x20, 0x00, 0x08, 0x00, 0x01, 0xa1, 0x47, 0xe1, 0x12, 0xa6, 0x43,
+        ]);
+
+        for len in 0..msg.len() {
+            assert_eq!(parse_response(&msg[..len], &TRANSACTION), None, "{}", len);
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_magic_cookie_and_transaction() {
+        let mut msg = response(&[
+            0x00, 0x20, 0x00, 0x08, 0x00, 0x01, 0xa1, 0x47, 0xe1, 0x12, 0xa6, 0x43,
+        ]);
+        assert!(parse_response(&msg, &TRANSACTION).is_some());
+        assert_eq!(parse_response(&msg, &[0; 12]), None);
+
+        msg[4] ^= 0xff;
+        assert_eq!(parse_response(&msg, &TRANSACTION), None);
+    }
+
+    #[test]
+    fn rejects_unknown_address_family() {
+        let msg = response(&[
+            0x00, 0x20, 0x00, 0x08, 0x00, 0x03, 0xa1, 0x47, 0xe1, 0x12, 0xa6, 0x43,
+        ]);
+
+        assert_eq!(parse_response(&msg, &TRANSACTION), None);
+    }
+}
@@ -1,30 +1,213 @@
 //! Time-related helper functions.
+//!
+//! The current time is read from a `Clock` held by the application and the
+//! UI, so that tests and simulations can give them a `FakeClock` to render
+//! windows and calculate time ranges reproducibly.
 
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use cable::Error;
-use chrono::{Local, LocalResult, TimeZone};
+use chrono::{DateTime, Days, Local, LocalResult, NaiveDateTime, NaiveTime, TimeZone, Timelike};
 
-/// Return the current system time in seconds since the Unix epoch.
-pub fn now() -> Result<u64, Error> {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)?
-        .as_millis()
-        .try_into()?;
+/// A source of the current time.
+pub trait Clock: Send + Sync {
+    /// Return the current time in milliseconds since the Unix epoch.
+    fn now(&self) -> Result<u64, Error>;
+}
+
+/// The clock of the system, used unless another clock is given.
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Result<u64, Error> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)?
+            .as_millis()
+            .try_into()?;
+
+        Ok(now)
+    }
+}
+
+/// A clock which only advances when told to.
+#[derive(Debug, Default)]
+pub struct FakeClock(AtomicU64);
+
+impl FakeClock {
+    /// Create a clock reading the given time (in milliseconds since the Unix
+    /// epoch).
+    pub fn new(now: u64) -> Self {
+        FakeClock(AtomicU64::new(now))
+    }
+
+    /// Set the time read by the clock.
+    pub fn set(&self, now: u64) {
+        self.0.store(now, Ordering::SeqCst);
+    }
+
+    /// Advance the clock by the given number of milliseconds.
+    pub fn advance(&self, millis: u64) {
+        self.0.fetch_add(millis, Ordering::SeqCst);
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Result<u64, Error> {
+        Ok(self.0.load(Ordering::SeqCst))
+    }
+}
 
-    Ok(now)
+/// Return the current time in the local timezone, as read from the given
+/// clock.
+fn local_now(clock: &dyn Clock) -> DateTime<Local> {
+    clock
+        .now()
+        .ok()
+        .and_then(|now| Local.timestamp_millis_opt(now as i64).single())
+        .unwrap_or_else(Local::now)
 }
 
-/// Return the time defining two weeks before the current system time.
+/// Return the time defining two weeks before the current time of the given
+/// clock.
 ///
 /// Used to calculate the start time for channel time range
 /// requests.
-pub fn two_weeks_ago() -> Result<u64, Error> {
-    let two_weeks_ago = now()? - 1_209_600_000;
+pub fn two_weeks_ago(clock: &dyn Clock) -> Result<u64, Error> {
+    let two_weeks_ago = clock.now()?.saturating_sub(TWO_WEEKS);
 
     Ok(two_weeks_ago)
 }
 
+/// Two weeks in milliseconds.
+const TWO_WEEKS: u64 = 1_209_600_000;
+
+/// The units of durations, with their length in milliseconds, largest
+/// first.
+const UNITS: [(char, u64); 5] = [
+    ('w', 604_800_000),
+    ('d', 86_400_000),
+    ('h', 3_600_000),
+    ('m', 60_000),
+    ('s', 1_000),
+];
+
+/// How far back the posts of a channel are requested when it is joined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backfill {
+    /// The entire history of the channel.
+    All,
+    /// The posts of the given number of milliseconds before the current
+    /// time.
+    Duration(u64),
+}
+
+impl Default for Backfill {
+    fn default() -> Self {
+        Backfill::Duration(TWO_WEEKS)
+    }
+}
+
+impl Backfill {
+    /// Parse a backfill horizon: `all` or a duration (e.g. `1d`).
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "all" => Some(Backfill::All),
+            duration => parse_duration(duration).map(Backfill::Duration),
+        }
+    }
+
+    /// Return the start time of a channel time range request (in
+    /// milliseconds since the Unix epoch) reaching back to the horizon from
+    /// the current time of the given clock.
+    pub fn start(&self, clock: &dyn Clock) -> Result<u64, Error> {
+        match self {
+            // A start time of zero requests the entire history.
+            Backfill::All => Ok(0),
+            Backfill::Duration(duration) => Ok(clock.now()?.saturating_sub(*duration)),
+        }
+    }
+}
+
+impl std::fmt::Display for Backfill {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Backfill::All => write!(f, "all"),
+            Backfill::Duration(duration) => write!(f, "{}", format_duration(*duration)),
+        }
+    }
+}
+
+/// Parse a duration made up of numbers with units of `s`, `m`, `h`, `d` and
+/// `w` (e.g. `2h` or `1h30m`), returning it in milliseconds.
+pub fn parse_duration(value: &str) -> Option<u64> {
+    let mut total: u64 = 0;
+    let mut digits = String::new();
+    for c in value.trim().chars() {
+        match c {
+            '0'..='9' => digits.push(c),
+            _ if !digits.is_empty() => {
+                let (_unit, millis) = UNITS.iter().find(|(unit, _millis)| *unit == c)?;
+                let duration = digits.parse::<u64>().ok()?.checked_mul(*millis)?;
+                total = total.checked_add(duration)?;
+                digits.clear();
+            }
+            _ => return None,
+        }
+    }
+    if total == 0 || !digits.is_empty() {
+        return None;
+    }
+
+    Some(total)
+}
+
+/// Format a duration in milliseconds in the largest unit which divides it
+/// evenly (e.g. `2w` or `90m`).
+pub fn format_duration(millis: u64) -> String {
+    match UNITS
+        .iter()
+        .find(|(_unit, length)| millis >= *length && millis.is_multiple_of(*length))
+    {
+        Some((unit, length)) => format!("{}{}", millis / length, unit),
+        None => format!("{}ms", millis),
+    }
+}
+
+/// Format the given timestamp (represented in milliseconds since the Unix
+/// epoch) as a date, hour and minutes relative to the local timezone.
+pub fn format_date(timestamp: u64) -> String {
+    if let LocalResult::Single(date_time) = Local.timestamp_millis_opt(timestamp as i64) {
+        format!("{}", date_time.format("%Y-%m-%d %H:%M"))
+    } else {
+        String::from("XXXX-XX-XX XX:XX")
+    }
+}
+
+/// Format the given timestamp (represented in milliseconds since the Unix
+/// epoch) as a date relative to the local timezone.
+pub fn format_day(timestamp: u64) -> String {
+    if let LocalResult::Single(date_time) = Local.timestamp_millis_opt(timestamp as i64) {
+        format!("{}", date_time.format("%Y-%m-%d"))
+    } else {
+        String::from("XXXX-XX-XX")
+    }
+}
+
+/// Format the given timestamp (represented in milliseconds since the Unix
+/// epoch) in full ISO 8601 form, with milliseconds and the offset of the
+/// local timezone.
+pub fn format_iso(timestamp: u64) -> String {
+    if let LocalResult::Single(date_time) = Local.timestamp_millis_opt(timestamp as i64) {
+        format!("{}", date_time.format("%Y-%m-%dT%H:%M:%S%.3f%:z"))
+    } else {
+        String::from("XXXX-XX-XXTXX:XX:XX.XXX")
+    }
+}
+
 /// Format the given timestamp (represented in milliseconds since the Unix
 /// epoch) as hour and minutes relative to the local timezone.
 pub fn format(timestamp: u64) -> String {
@@ -36,3 +219,158 @@ pub fn format(timestamp: u64) -> String {
         String::from("XX:XX")
     }
 }
+
+/// Describe the time elapsed since the given timestamp (represented in
+/// milliseconds since the Unix epoch) until the current time of the given
+/// clock in the largest whole unit, e.g. `3 hours ago`.
+pub fn format_ago(timestamp: u64, clock: &dyn Clock) -> String {
+    let elapsed = clock.now().unwrap_or(timestamp).saturating_sub(timestamp) / 1000;
+    if elapsed < 60 {
+        return String::from("just now");
+    }
+
+    format!("{} ago", format_span(elapsed))
+}
+
+/// Describe the given number of seconds in the largest whole unit, e.g.
+/// `3 hours`.
+pub fn format_span(seconds: u64) -> String {
+    let (count, unit) = match seconds {
+        0..=59 => return String::from("less than a minute"),
+        60..=3599 => (seconds / 60, "minute"),
+        3600..=86_399 => (seconds / 3600, "hour"),
+        _ => (seconds / 86_400, "day"),
+    };
+
+    format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" })
+}
+
+/// Return the hour of the day of the given timestamp (represented in
+/// milliseconds since the Unix epoch) in the local timezone.
+pub fn local_hour(timestamp: u64) -> Option<u32> {
+    match Local.timestamp_millis_opt(timestamp as i64) {
+        LocalResult::Single(date_time) => Some(date_time.hour()),
+        _ => None,
+    }
+}
+
+/// Return the number of minutes since midnight in the local timezone, as
+/// read from the given clock.
+pub fn local_minute_of_day(clock: &dyn Clock) -> u32 {
+    let now = local_now(clock);
+
+    now.hour() * 60 + now.minute()
+}
+
+/// Parse a time of day in `HH:MM` form, returning the number of minutes
+/// since midnight.
+pub fn parse_minute_of_day(value: &str) -> Option<u32> {
+    let time = NaiveTime::parse_from_str(value, "%H:%M").ok()?;
+
+    Some(time.hour() * 60 + time.minute())
+}
+
+/// Format a number of minutes since midnight in `HH:MM` form.
+pub fn format_minute_of_day(minute: u32) -> String {
+    format!("{:02}:{:02}", minute / 60, minute % 60)
+}
+
+/// Parse a point in time, returning it in milliseconds since the Unix epoch.
+///
+/// The time may be relative to the current time (e.g. `in 2h` or
+/// `in 1h30m`, with units of `s`, `m`, `h`, `d` and `w`), a time of day in the
+/// local timezone (`HH:MM`, the next occurrence of which is returned) or a
+/// date and time in the local timezone (`YYYY-MM-DD HH:MM`). Relative times
+/// are taken from the current time of the given clock.
+pub fn parse_due(value: &str, clock: &dyn Clock) -> Option<u64> {
+    let value = value.trim();
+
+    if let Some(duration) = value.strip_prefix("in ") {
+        return clock.now().ok()?.checked_add(parse_duration(duration)?);
+    }
+
+    let date_time = if let Ok(date_time) = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M") {
+        date_time
+    } else {
+        let time = NaiveTime::parse_from_str(value, "%H:%M").ok()?;
+        let now = local_now(clock).naive_local();
+        let today = now.date().and_time(time);
+        if today > now {
+            today
+        } else {
+            today.checked_add_days(Days::new(1))?
+        }
+    };
+
+    let due = Local.from_local_datetime(&date_time).earliest()?;
+
+    due.timestamp_millis().try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_times_follow_the_clock() {
+        let clock = FakeClock::new(1_700_000_000_000);
+
+        assert_eq!(parse_due("in 2h", &clock), Some(1_700_007_200_000));
+        assert_eq!(
+            Backfill::Duration(60_000).start(&clock).unwrap(),
+            1_699_999_940_000
+        );
+        assert_eq!(Backfill::All.start(&clock).unwrap(), 0);
+        assert_eq!(two_weeks_ago(&clock).unwrap(), 1_698_790_400_000);
+
+        clock.advance(3 * 3_600_000);
+        assert_eq!(format_ago(1_700_000_000_000, &clock), "3 hours ago");
+        clock.set(1_700_000_030_000);
+        assert_eq!(format_ago(1_700_000_000_000, &clock), "just now");
+    }
+
+    #[test]
+    fn durations_are_parsed_in_milliseconds() {
+        assert_eq!(parse_duration("2h"), Some(7_200_000));
+        assert_eq!(parse_duration("1h30m"), Some(5_400_000));
+        assert_eq!(parse_duration(" 1w "), Some(604_800_000));
+        assert_eq!(
+            parse_duration("99999999999d"),
+            Some(8_639_999_999_913_600_000)
+        );
+
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("0s"), None);
+        assert_eq!(parse_duration("h"), None);
+        assert_eq!(parse_duration("2"), None);
+        assert_eq!(parse_duration("2y"), None);
+        assert_eq!(parse_duration("-2h"), None);
+        assert_eq!(parse_duration("２h"), None);
+        assert_eq!(parse_duration("2時"), None);
+    }
+
+    #[test]
+    fn overflowing_durations_are_rejected() {
+        // The number, its product with the unit and the sum of the parts
+        // each overflow.
+        assert_eq!(parse_duration("99999999999999999999s"), None);
+        assert_eq!(parse_duration("999999999999d"), None);
+        assert_eq!(parse_duration("15000000000000000s15000000000000000s"), None);
+    }
+
+    #[test]
+    fn due_times_past_the_end_of_time_are_rejected() {
+        let clock = FakeClock::new(u64::MAX - 1);
+
+        assert_eq!(parse_due("in 99999999999d", &clock), None);
+        assert_eq!(parse_due("in 1s", &clock), None);
+    }
+
+    #[test]
+    fn time_ranges_start_no_earlier_than_the_epoch() {
+        let clock = FakeClock::new(60_000);
+
+        assert_eq!(two_weeks_ago(&clock).unwrap(), 0);
+        assert_eq!(Backfill::Duration(TWO_WEEKS).start(&clock).unwrap(), 0);
+    }
+}
@@ -0,0 +1,190 @@
+//! In-memory transport between peers of the same process.
+//!
+//! `memory_pair()` returns the two ends of a duplex byte stream which can be
+//! passed to `CableManager::listen()`, so that peers can sync without
+//! sockets: in examples, tutorials and tests, or with `/connect loopback`.
+
+use std::{
+    collections::VecDeque,
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use futures::io::{AsyncRead, AsyncWrite};
+
+/// The address given to `/connect` to connect to an in-process peer.
+pub const LOOPBACK: &str = "loopback";
+
+/// The bytes in flight in one direction of a memory stream.
+#[derive(Default)]
+struct Pipe {
+    buf: VecDeque<u8>,
+    /// Whether the writing end has been closed (or dropped), after which
+    /// reads return end-of-file once the buffer is drained.
+    closed: bool,
+    /// The task waiting to read from the pipe, if any.
+    reader: Option<Waker>,
+}
+
+impl Pipe {
+    fn close(&mut self) {
+        self.closed = true;
+        if let Some(waker) = self.reader.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// One end of a memory stream, which closes both directions when dropped.
+struct End {
+    incoming: Arc<Mutex<Pipe>>,
+    outgoing: Arc<Mutex<Pipe>>,
+}
+
+impl Drop for End {
+    fn drop(&mut self) {
+        self.incoming.lock().unwrap().close();
+        self.outgoing.lock().unwrap().close();
+    }
+}
+
+/// One end of an in-memory duplex byte stream.
+///
+/// Clones share the same end; the stream is closed once every clone of an
+/// end has been dropped (or either end is closed). Writes are buffered
+/// without limit.
+#[derive(Clone)]
+pub struct MemoryStream(Arc<End>);
+
+/// Create a pair of linked memory streams: bytes written to one are read
+/// from the other.
+pub fn memory_pair() -> (MemoryStream, MemoryStream) {
+    let a = Arc::new(Mutex::new(Pipe::default()));
+    let b = Arc::new(Mutex::new(Pipe::default()));
+
+    (
+        MemoryStream(Arc::new(End {
+            incoming: a.clone(),
+            outgoing: b.clone(),
+        })),
+        MemoryStream(Arc::new(End {
+            incoming: b,
+            outgoing: a,
+        })),
+    )
+}
+
+impl AsyncRead for MemoryStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut pipe = self.0.incoming.lock().unwrap();
+        if !pipe.buf.is_empty() {
+            let len = buf.len().min(pipe.buf.len());
+            for (byte, received) in buf.iter_mut().zip(pipe.buf.drain(..len)) {
+                *byte = received;
+            }
+            Poll::Ready(Ok(len))
+        } else if pipe.closed {
+            Poll::Ready(Ok(0))
+        } else {
+            pipe.reader = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl AsyncWrite for MemoryStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut pipe = self.0.outgoing.lock().unwrap();
+        if pipe.closed {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+        pipe.buf.extend(buf);
+        if let Some(waker) = pipe.reader.take() {
+            waker.wake();
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.0.outgoing.lock().unwrap().close();
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use async_std::task;
+    use futures::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn bytes_round_trip_in_both_directions() {
+        task::block_on(async {
+            let (mut a, mut b) = memory_pair();
+
+            a.write_all(b"ping").await.unwrap();
+            let mut buf = [0; 4];
+            b.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"ping");
+
+            b.write_all(b"pong").await.unwrap();
+            a.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"pong");
+        })
+    }
+
+    #[test]
+    fn reads_wait_for_writes() {
+        task::block_on(async {
+            let (mut a, mut b) = memory_pair();
+
+            let reader = task::spawn(async move {
+                let mut buf = Vec::new();
+                b.read_to_end(&mut buf).await.unwrap();
+                buf
+            });
+            a.write_all(b"hello ").await.unwrap();
+            a.write_all(b"world").await.unwrap();
+            drop(a);
+
+            assert_eq!(reader.await, b"hello world");
+        })
+    }
+
+    #[test]
+    fn closing_an_end_closes_the_stream() {
+        task::block_on(async {
+            let (mut a, mut b) = memory_pair();
+
+            a.write_all(b"last").await.unwrap();
+            a.close().await.unwrap();
+
+            // Buffered bytes are still delivered before end-of-file.
+            let mut buf = Vec::new();
+            b.read_to_end(&mut buf).await.unwrap();
+            assert_eq!(buf, b"last");
+
+            // Dropping every clone of an end closes the other direction too.
+            let a_clone = a.clone();
+            drop(a);
+            drop(a_clone);
+            let err = b.write_all(b"gone").await.unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+        })
+    }
+}
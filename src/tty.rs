@@ -0,0 +1,33 @@
+//! Modes of the terminal, which are saved when cabin is suspended (Ctrl+Z)
+//! and restored when it is resumed, since the shell resets them in the
+//! meantime.
+
+use std::mem::MaybeUninit;
+
+use libc::{tcgetattr, tcsetattr, termios, STDIN_FILENO, TCSANOW};
+
+/// The modes of the terminal attached to stdin.
+pub struct Modes(termios);
+
+impl Modes {
+    /// Read the current modes of the terminal, if stdin is a terminal.
+    pub fn get() -> Option<Self> {
+        let mut modes = MaybeUninit::uninit();
+        // SAFETY: `tcgetattr` initialises the modes if it succeeds.
+        unsafe {
+            if tcgetattr(STDIN_FILENO, modes.as_mut_ptr()) == 0 {
+                Some(Modes(modes.assume_init()))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Apply the modes to the terminal.
+    pub fn set(&self) {
+        // SAFETY: the modes were initialised by `tcgetattr`.
+        unsafe {
+            tcsetattr(STDIN_FILENO, TCSANOW, &self.0);
+        }
+    }
+}
@@ -1,23 +1,243 @@
-use std::{collections::BTreeSet, io::Write};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    fs,
+    io::Write,
+    time::{Duration, Instant},
+};
 
-use async_std::sync::{Arc, Mutex};
-use cable::{Channel, Nickname, Text, Timestamp, Topic};
-use owo_colors::OwoColorize;
+use async_std::{
+    sync::{Arc, Mutex},
+    task,
+};
+use cable::{Channel, Hash, Nickname, Text, Timestamp, Topic};
+use futures::channel::mpsc::UnboundedSender;
+use log::{info, warn};
+use owo_colors::{AnsiColors, OwoColorize};
 use signal_hook::{
-    consts::SIGWINCH,
+    consts::{SIGCONT, SIGTSTP, SIGWINCH},
     iterator::{exfiltrator::WithOrigin, SignalsInfo},
+    low_level,
 };
 
-use crate::{hex, input::Input, time, utils};
+use crate::{
+    complete::{self, Completer, Completion},
+    hex, i18n,
+    input::{Input, DISABLE_KEYBOARD_PROTOCOL, ENABLE_KEYBOARD_PROTOCOL},
+    metrics,
+    settings::{Palette, Settings},
+    time::{self, Clock, SystemClock},
+    tty, utils,
+};
 
 pub type Addr = Vec<u8>;
 pub type PublicKey = [u8; 32];
 pub type TermSize = (u32, u32);
 
+/// A type of post which may be ignored for a single peer, without blocking
+/// the text posts of that peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Ignore {
+    /// Topic changes, which are not applied.
+    Topics,
+    /// Channel joins and leaves, which are not displayed.
+    Joins,
+    /// Nickname changes; the peer is displayed by public key instead.
+    Nicks,
+}
+
+impl Ignore {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "topics" => Some(Ignore::Topics),
+            "joins" => Some(Ignore::Joins),
+            "nicks" => Some(Ignore::Nicks),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Ignore::Topics => "topics",
+            Ignore::Joins => "joins",
+            Ignore::Nicks => "nicks",
+        }
+    }
+}
+
+/// The name of the virtual window which aggregates mentions of the local
+/// peer across all channels and cabals.
+pub const MENTIONS_WINDOW: &str = "!mentions";
+
+/// The name of the virtual window which summarises the recent activity of
+/// every channel window.
+pub const ACTIVITY_WINDOW: &str = "!activity";
+
+/// The name of the virtual window which logs the opening and closing of
+/// peer connections.
+pub const NETWORK_WINDOW: &str = "!network";
+
+/// The header of the active window while a post is being selected.
+const SELECTION_HELP: &str =
+    "j/k: move, c: copy, r: reply, d: delete, o: open url, i: info, esc: exit";
+
+/// A single line posted to a window.
+///
+/// Lines are ordered by their position, with ties broken by their index
+/// (insertion order), so that late-arriving posts are interleaved with the
+/// conversation rather than appended.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Line {
+    /// The time by which the line is ordered: the timestamp of the post, or
+    /// the time of arrival if the timestamp is in the future (due to the
+    /// clock of the author being ahead).
+    pub position: Timestamp,
+    /// The timestamp of the post (or status message).
+    pub timestamp: Timestamp,
+    /// The line index.
+    pub index: u64,
+    /// The public key of the post author; `None` for status messages.
+    pub author: Option<PublicKey>,
+    /// The nickname of the post author, if known.
+    pub nickname: Option<Nickname>,
+    /// The text of the line.
+    pub text: Text,
+    /// The hash of the post; `None` for status messages.
+    pub hash: Option<Hash>,
+    /// The category of a status message; `Level::Info` for posts.
+    pub level: Level,
+}
+
+/// The category of a status message, which determines the prefix and colour
+/// of its line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    /// General information, such as the output of a command.
+    #[default]
+    Info,
+    /// The echo of an entered command.
+    Command,
+    /// The opening and closing of connections.
+    Network,
+    /// Failures and invalid input.
+    Error,
+}
+
+impl Level {
+    /// Return the coloured prefix of status lines of this level.
+    fn prefix(&self) -> String {
+        match self {
+            Level::Info => format!("{}", "-status-".bright_green()),
+            Level::Command => format!("{}", "-command-".dimmed()),
+            Level::Network => format!("{}", "-network-".cyan()),
+            Level::Error => format!("{}", "-error-".red()),
+        }
+    }
+}
+
 /// A `BTreeSet` representing the data for each line posted to the UI.
-/// Includes a line index, timestamp, optional public key, optional nickname
-/// and text.
-type LinesSet = BTreeSet<(u64, Timestamp, Option<PublicKey>, Option<Nickname>, Text)>;
+type LinesSet = BTreeSet<Line>;
+
+/// The minimum interval between renders of the UI while idle.
+const IDLE_RENDER_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The time for which each notification is displayed in the flash line.
+const FLASH_DURATION: Duration = Duration::from_secs(4);
+
+/// The maximum number of notifications waiting to be displayed in the flash
+/// line; further notifications are dropped until the queue drains.
+const FLASH_QUEUE_LEN: usize = 3;
+
+/// The maximum number of lines kept by the status and network windows while
+/// headless, since they are then only relayed to attached clients and would
+/// otherwise grow for the lifetime of the daemon.
+const HEADLESS_WINDOW_LINES: usize = 1000;
+
+/// The maximum number of matching windows listed by the window switcher.
+const SWITCHER_ROWS: usize = 8;
+
+/// The interval over which recently arrived posts are counted by
+/// `/sync status`.
+pub const SYNC_INTERVAL: Duration = Duration::from_secs(300);
+
+/// The number of milliseconds by which a post timestamp may be ahead of the
+/// local clock before the post is considered to be from the future.
+const CLOCK_SKEW_TOLERANCE: u64 = 60_000;
+
+/// The number of consecutive posts from the future after which a warning
+/// about the clock of their author is written to the status window.
+const CLOCK_SKEW_WARNING_POSTS: usize = 3;
+
+/// Format a single line of a window for display.
+///
+/// The line is prefixed with the nickname of the post author if one is
+/// known, falling back to the abbreviated public key of the author, or a
+/// status marker if the line was not authored by a peer.
+///
+/// Replies to a post which is known locally are prefixed with a snippet of
+/// the referenced post.
+///
+/// Authors are coloured according to the given colour assignments, falling
+/// back to the colour derived from their public key. If notes are given, the
+/// note about the author (if any) is displayed after their name.
+fn format_line(
+    line: &Line,
+    replied_to: Option<&Line>,
+    colours: &HashMap<PublicKey, AnsiColors>,
+    notes: Option<&HashMap<PublicKey, String>>,
+) -> String {
+    // Posts from the future are displayed at their arrival position.
+    let timestamp = if line.timestamp > line.position {
+        format!("{} (future)", time::format(line.timestamp))
+    } else {
+        time::format(line.timestamp)
+    };
+    let text = match (utils::parse_reply(&line.text), replied_to) {
+        (Some((_hash, text)), Some(replied_to)) => {
+            // Avoid displaying the reference of a reply to a reply.
+            let replied_to_text = utils::parse_reply(&replied_to.text)
+                .map(|(_hash, text)| text)
+                .unwrap_or(&replied_to.text);
+            format!("↳ re: \"{}\" {}", utils::snippet(replied_to_text, 24), text)
+        }
+        _ => line.text.to_owned(),
+    };
+    let nickname = &line.nickname;
+
+    if let Some(public_key) = &line.author {
+        let colour = colours
+            .get(public_key)
+            .copied()
+            .unwrap_or_else(|| utils::public_key_to_colour(public_key));
+        let note = notes
+            .and_then(|notes| notes.get(public_key))
+            .map(|note| format!(" {}", format!("({})", note).dimmed()))
+            .unwrap_or_default();
+
+        // Display the nickname of the post author if one is known.
+        if let Some(name) = nickname {
+            format!("[{}] <{}{}> {}", timestamp, name.color(colour), note, text)
+        } else {
+            // Fallback to displaying the abbreviated public key of
+            // the author if no nickname is known.
+            let abbreviated_public_key = hex::to(&public_key[..4]);
+            format!(
+                "[{}] <{}{}> {}",
+                timestamp,
+                abbreviated_public_key.color(colour),
+                note,
+                text
+            )
+        }
+    } else {
+        // Echoed commands recede and errors stand out.
+        let text = match line.level {
+            Level::Command => format!("{}", text.dimmed()),
+            Level::Error => format!("{}", text.red()),
+            Level::Info | Level::Network => text,
+        };
+        format!("[{}] {} {}", timestamp, line.level.prefix(), text)
+    }
+}
 
 /// Determine the dimensions of the terminal.
 pub fn get_term_size() -> TermSize {
@@ -27,15 +247,67 @@ pub fn get_term_size() -> TermSize {
 }
 
 /// Resize the user interface to match the dimensions of the terminal.
+///
+/// Also restores the terminal before the process is suspended (`SIGTSTP`,
+/// e.g. by Ctrl+Z) and, once resumed (`SIGCONT`), returns the terminal to
+/// raw mode and repaints the entire screen.
 pub async fn resizer(ui: Arc<Mutex<Ui>>) {
-    let mut signals = SignalsInfo::<WithOrigin>::new(&vec![SIGWINCH]).unwrap();
+    let mut signals = SignalsInfo::<WithOrigin>::new(&vec![SIGWINCH, SIGTSTP, SIGCONT]).unwrap();
+    let mut modes = None;
     for info in &mut signals {
-        if info.signal == SIGWINCH {
-            ui.lock().await.resize(get_term_size())
+        match info.signal {
+            SIGWINCH => {
+                let mut ui = ui.lock().await;
+                ui.resize(get_term_size());
+                ui.update();
+            }
+            SIGTSTP => {
+                modes = tty::Modes::get();
+                ui.lock().await.suspend();
+                // Stop the process, as the default handler of the signal
+                // would; this returns once the process is resumed.
+                let _ = low_level::emulate_default_handler(SIGTSTP);
+            }
+            SIGCONT => {
+                if let Some(modes) = modes.take() {
+                    modes.set();
+                }
+                let mut ui = ui.lock().await;
+                ui.resize(get_term_size());
+                ui.redraw();
+            }
+            _ => {}
         }
     }
 }
 
+/// Periodically render any UI updates which were deferred while idle.
+pub async fn flusher(ui: Arc<Mutex<Ui>>) {
+    loop {
+        task::sleep(IDLE_RENDER_INTERVAL).await;
+        ui.lock().await.flush();
+    }
+}
+
+/// The state of the window switcher, which lists the windows matching a
+/// query as it is typed.
+#[derive(Debug, Default)]
+pub struct Switcher {
+    /// The text typed so far.
+    pub query: String,
+    /// The position of the highlighted window in the list of matches.
+    pub selected: usize,
+}
+
+/// The state of the UI while locked by `/lock`.
+#[derive(Debug, Default)]
+pub struct Lock {
+    /// The passphrase typed so far.
+    pub passphrase: String,
+    /// The number of incorrect passphrases entered.
+    pub failures: usize,
+}
+
 /// A single user-interface window.
 pub struct Window {
     /// The hex address of a cabal.
@@ -44,14 +316,63 @@ pub struct Window {
     pub channel: Channel,
     /// The channel topic.
     pub topic: Topic,
+    /// A local display label replacing the channel name in the header and
+    /// window list, set with `/win rename`.
+    pub label: Option<String>,
     /// The age of the most recent post(s) to be displayed.
     pub time_end: u64,
     /// The total number of posts which may be displayed.
     pub limit: usize,
-    /// The lines of the window (index, timestamp, author, nickname, text).
+    /// The lines of the window.
     pub lines: LinesSet,
+    /// The public key of the local peer for the cabal of this window.
+    pub local_public_key: Option<PublicKey>,
+    /// The timestamp of the most recent post by each author, along with the
+    /// nickname of the author at the time of posting.
+    pub latest_posts: HashMap<PublicKey, (Timestamp, Option<Nickname>)>,
+    /// Whether lines authored by blocked peers are revealed (dimmed) rather
+    /// than hidden.
+    pub show_hidden: bool,
+    /// The number of posts received since the window was last active.
+    pub unread: usize,
+    /// The number of posts mentioning the local peer received since the
+    /// window was last active.
+    pub mentions: usize,
+    /// The number of lines by which the window is scrolled back from the
+    /// most recent line.
+    pub scroll: usize,
+    /// The start time of the open (live) channel time range request, if
+    /// any.
+    pub request_start: Option<Timestamp>,
+    /// The number of known members of the channel, if known.
+    pub members: Option<usize>,
+    /// The times at which posts arrived via the open channel request within
+    /// the last `SYNC_INTERVAL`.
+    arrivals: VecDeque<Instant>,
+    /// The hashes of all posts inserted into the window, used to skip posts
+    /// which arrive more than once (e.g. from both the store and a peer).
+    hashes: HashSet<Hash>,
+    /// The colours assigned to authors by the contrast palette.
+    colours: HashMap<PublicKey, AnsiColors>,
+    /// The number of lines by each author, updated as lines are inserted
+    /// and removed, by which the contrast palette assigns colours.
+    line_counts: HashMap<PublicKey, usize>,
+    /// The palette by which `colours` were assigned, or `None` if lines
+    /// have since been inserted or removed.
+    coloured: Option<Palette>,
+    /// Formatted lines, cached by line index to avoid repeated formatting
+    /// on each update.
+    rendered: HashMap<u64, String>,
+    /// The indices of the lines replying to each post hash, so that their
+    /// cached snippets can be discarded once the replied-to post arrives.
+    replies: HashMap<Hash, Vec<u64>>,
     /// A line index counter to facilitate line insertions.
     line_index: u64,
+    /// The index of the first line not yet printed in accessible mode.
+    printed: u64,
+    /// The source of the current time, which positions lines and
+    /// timestamps status messages.
+    clock: Arc<dyn Clock>,
 }
 
 impl Window {
@@ -61,39 +382,497 @@ impl Window {
             address,
             channel,
             topic: String::new(),
+            label: None,
             time_end: 0,
             limit: 50,
             lines: BTreeSet::default(),
+            local_public_key: None,
+            latest_posts: HashMap::new(),
+            show_hidden: false,
+            unread: 0,
+            mentions: 0,
+            scroll: 0,
+            request_start: None,
+            members: None,
+            arrivals: VecDeque::new(),
+            hashes: HashSet::new(),
+            colours: HashMap::new(),
+            line_counts: HashMap::new(),
+            coloured: None,
+            rendered: HashMap::new(),
+            replies: HashMap::new(),
             line_index: 0,
+            printed: 0,
+            clock: Arc::new(SystemClock),
         }
     }
 
+    /// Read the current time from the given clock rather than the system
+    /// clock.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Write the message to the window.
     pub fn write(&mut self, msg: &str) {
-        self.insert(time::now().unwrap(), None, None, msg);
+        self.write_as(Level::Info, msg);
+    }
+
+    /// Write the message to the window as a status message of the given
+    /// level.
+    pub fn write_as(&mut self, level: Level, msg: &str) {
+        self.insert_line(self.clock.now().unwrap(), None, None, msg, None, level);
     }
 
     /// Insert a new line into the window using the given message timestamp,
-    /// name and text.
+    /// name, text and post hash.
     ///
     /// The name will be the public key of the post author if a name-defining
     /// `post/info` is not available.
+    ///
+    /// Returns `false` (without inserting the line) if a post with the same
+    /// hash has already been inserted.
     pub fn insert(
         &mut self,
         timestamp: Timestamp,
         author: Option<PublicKey>,
         nick: Option<Nickname>,
         text: &str,
-    ) {
+        hash: Option<Hash>,
+    ) -> bool {
+        self.insert_line(timestamp, author, nick, text, hash, Level::Info)
+    }
+
+    /// Insert a new line of the given level into the window, as `insert`.
+    fn insert_line(
+        &mut self,
+        timestamp: Timestamp,
+        author: Option<PublicKey>,
+        nick: Option<Nickname>,
+        text: &str,
+        hash: Option<Hash>,
+        level: Level,
+    ) -> bool {
+        if let Some(hash) = hash {
+            if !self.hashes.insert(hash) {
+                return false;
+            }
+        }
+
         let index = self.line_index;
         self.line_index += 1;
+
+        if let Some(hash) = hash {
+            // Replies rendered before this post arrived lack its snippet.
+            self.invalidate_replies(&hash);
+        }
+        if let Some((replied_to, _text)) = utils::parse_reply(text) {
+            self.replies.entry(replied_to).or_default().push(index);
+        }
+
+        if let Some(public_key) = author {
+            *self.line_counts.entry(public_key).or_default() += 1;
+            self.coloured = None;
+
+            let latest = self
+                .latest_posts
+                .entry(public_key)
+                .or_insert((timestamp, nick.clone()));
+            if timestamp >= latest.0 {
+                *latest = (timestamp, nick.clone());
+            }
+        }
+
+        let now = self.clock.now().unwrap_or(timestamp);
+        let position = if timestamp > now + CLOCK_SKEW_TOLERANCE {
+            now
+        } else {
+            timestamp
+        };
+        self.lines.insert(Line {
+            position,
+            index,
+            timestamp,
+            author,
+            nickname: nick,
+            text: text.to_string(),
+            hash,
+            level,
+        });
+
+        true
+    }
+
+    /// Record the arrival of a post via the open channel request.
+    pub fn record_arrival(&mut self) {
+        let now = Instant::now();
+        while let Some(arrival) = self.arrivals.front() {
+            if now.duration_since(*arrival) < SYNC_INTERVAL {
+                break;
+            }
+            self.arrivals.pop_front();
+        }
+        self.arrivals.push_back(now);
+    }
+
+    /// Return the number of posts which arrived within the last
+    /// `SYNC_INTERVAL`.
+    pub fn recent_arrivals(&self) -> usize {
+        self.arrivals
+            .iter()
+            .filter(|arrival| arrival.elapsed() < SYNC_INTERVAL)
+            .count()
+    }
+
+    /// Return the timestamps of the oldest and newest posts held in the
+    /// window, if any.
+    pub fn post_time_range(&self) -> Option<(Timestamp, Timestamp)> {
+        let mut posts = self.lines.iter().filter(|line| line.hash.is_some());
+        let oldest = posts.next()?.timestamp;
+        let newest = posts.next_back().map_or(oldest, |line| line.timestamp);
+
+        Some((oldest, newest))
+    }
+
+    /// Return the line of the Nth most recent post in the window, where 1 is
+    /// the most recent. Status messages are not counted.
+    pub fn nth_recent_post(&self, n: usize) -> Option<&Line> {
         self.lines
-            .insert((index, timestamp, author, nick, text.to_string()));
+            .iter()
+            .rev()
+            .filter(|line| line.hash.is_some())
+            .nth(n.checked_sub(1)?)
+    }
+
+    /// Return the names of peers who are likely to have synced the most
+    /// recent post of the local peer.
+    ///
+    /// This is a local-only approximation: a peer is considered to be synced
+    /// if they have posted to the channel at or after the time of the most
+    /// recent post by the local peer.
+    pub fn synced_peers(&self) -> Vec<String> {
+        let local_latest = match self
+            .local_public_key
+            .and_then(|public_key| self.latest_posts.get(&public_key))
+        {
+            Some((timestamp, _nick)) => *timestamp,
+            None => return Vec::new(),
+        };
+
+        let mut peers = self
+            .latest_posts
+            .iter()
+            .filter(|(public_key, (timestamp, _nick))| {
+                Some(**public_key) != self.local_public_key && *timestamp >= local_latest
+            })
+            .map(|(public_key, (_timestamp, nick))| {
+                nick.to_owned().unwrap_or_else(|| hex::to(&public_key[..4]))
+            })
+            .collect::<Vec<String>>();
+        peers.sort();
+
+        peers
     }
 
     pub fn update_topic(&mut self, topic: String) {
         self.topic = topic;
     }
+
+    /// Assign colours to the authors of the window according to the given
+    /// palette, discarding cached formatted lines if the assignments have
+    /// changed.
+    ///
+    /// The contrast palette assigns colours to authors in order of the
+    /// number of their posts in the window, so that the most frequent
+    /// posters do not share a colour.
+    ///
+    /// Assignments are only recalculated once lines have been inserted or
+    /// removed, or the palette has changed.
+    pub fn assign_colours(&mut self, palette: Palette) {
+        if self.coloured == Some(palette) {
+            return;
+        }
+        self.coloured = Some(palette);
+
+        let colours = match palette {
+            Palette::Default => HashMap::new(),
+            Palette::Contrast => {
+                // Ties are broken by public key so that assignments are
+                // stable between updates.
+                let mut authors = self.line_counts.iter().collect::<Vec<_>>();
+                authors.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+                let public_keys = authors
+                    .into_iter()
+                    .map(|(public_key, _count)| *public_key)
+                    .collect::<Vec<PublicKey>>();
+
+                utils::assign_colours(&public_keys)
+            }
+        };
+
+        if colours != self.colours {
+            self.colours = colours;
+            self.rendered.clear();
+        }
+    }
+
+    /// Return the formatted lines which fit within the given height (most
+    /// recent last), formatting and caching any lines which have not
+    /// previously been rendered.
+    ///
+    /// Lines authored by blocked peers are omitted, unless `show_hidden` is
+    /// set, in which case they are rendered dimmed. The line with the given
+    /// selected index (if any) is highlighted.
+    pub fn viewport(
+        &mut self,
+        height: usize,
+        blocked: &HashSet<PublicKey>,
+        notes: Option<&HashMap<PublicKey, String>>,
+        selected: Option<u64>,
+    ) -> Vec<String> {
+        let show_hidden = self.show_hidden;
+        let mut lines = self
+            .lines
+            .iter()
+            .rev()
+            .filter_map(|line| {
+                let is_blocked = line
+                    .author
+                    .map(|public_key| blocked.contains(&public_key))
+                    .unwrap_or(false);
+                if is_blocked && !show_hidden {
+                    return None;
+                }
+
+                let is_selected = selected == Some(line.index);
+                let line = self.rendered.entry(line.index).or_insert_with(|| {
+                    let replied_to = utils::parse_reply(&line.text).and_then(|(hash, _text)| {
+                        self.lines
+                            .iter()
+                            .find(|line| line.hash.as_ref() == Some(&hash))
+                    });
+                    format_line(line, replied_to, &self.colours, notes)
+                });
+
+                if is_selected {
+                    Some(format!("{}", line.reversed()))
+                } else if is_blocked {
+                    Some(format!("{}", line.dimmed()))
+                } else {
+                    Some(line.to_owned())
+                }
+            })
+            .skip(self.scroll)
+            .take(height)
+            .collect::<Vec<String>>();
+        lines.reverse();
+
+        lines
+    }
+
+    /// Return the lines inserted since this method was last called, in order
+    /// of insertion and formatted without colours, for accessible mode.
+    ///
+    /// Lines authored by blocked peers are omitted, unless `show_hidden` is
+    /// set, in which case they are marked as hidden.
+    pub fn take_unprinted(
+        &mut self,
+        blocked: &HashSet<PublicKey>,
+        notes: Option<&HashMap<PublicKey, String>>,
+    ) -> Vec<String> {
+        let mut lines = self
+            .lines
+            .iter()
+            .filter(|line| line.index >= self.printed)
+            .collect::<Vec<&Line>>();
+        lines.sort_by_key(|line| line.index);
+        self.printed = self.line_index;
+
+        lines
+            .into_iter()
+            .filter_map(|line| {
+                let is_blocked = line
+                    .author
+                    .map(|public_key| blocked.contains(&public_key))
+                    .unwrap_or(false);
+                if is_blocked && !self.show_hidden {
+                    return None;
+                }
+
+                let replied_to = utils::parse_reply(&line.text).and_then(|(hash, _text)| {
+                    self.lines
+                        .iter()
+                        .find(|line| line.hash.as_ref() == Some(&hash))
+                });
+                let text = utils::strip_ansi(&format_line(line, replied_to, &self.colours, notes));
+
+                if is_blocked {
+                    Some(format!("(hidden) {}", text))
+                } else {
+                    Some(text)
+                }
+            })
+            .collect()
+    }
+
+    /// Scroll such that the line of the post with the given hash is the
+    /// bottom-most visible line.
+    ///
+    /// Returns `false` if the post is not displayed in the window.
+    pub fn scroll_to(&mut self, hash: &Hash) -> bool {
+        match self
+            .lines
+            .iter()
+            .rev()
+            .position(|line| line.hash.as_ref() == Some(hash))
+        {
+            Some(position) => {
+                self.scroll = position;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Scroll (if necessary) such that the line with the given index is
+    /// visible within the given height.
+    pub fn reveal(&mut self, index: u64, height: usize) {
+        if let Some(position) = self.lines.iter().rev().position(|line| line.index == index) {
+            if position < self.scroll {
+                self.scroll = position;
+            } else if position >= self.scroll + height {
+                self.scroll = position + 1 - height;
+            }
+        }
+    }
+
+    /// Write a text post which failed to send, marked as awaiting `/retry N`.
+    ///
+    /// Returns the index of the line, so that it can be removed once the
+    /// post has been sent.
+    pub fn write_failed(&mut self, n: usize, text: &str) -> u64 {
+        let index = self.line_index;
+        self.write_as(Level::Error, &format!("failed ↻ (/retry {}) {}", n, text));
+        index
+    }
+
+    /// Remove the line with the given index.
+    pub fn remove_line(&mut self, index: u64) {
+        self.retain_lines(|line| line.index != index);
+        self.rendered.remove(&index);
+    }
+
+    /// Remove the oldest lines of the window, such that at most the given
+    /// number of lines remain.
+    pub fn trim(&mut self, max_lines: usize) {
+        let excess = self.lines.len().saturating_sub(max_lines);
+        if excess == 0 {
+            return;
+        }
+        let oldest: HashSet<u64> = self
+            .lines
+            .iter()
+            .take(excess)
+            .map(|line| line.index)
+            .collect();
+        self.retain_lines(|line| !oldest.contains(&line.index));
+        for index in &oldest {
+            self.rendered.remove(index);
+        }
+    }
+
+    /// Remove the line of the post with the given hash.
+    pub fn remove_post(&mut self, hash: &Hash) {
+        self.retain_lines(|line| line.hash.as_ref() != Some(hash));
+        self.invalidate_replies(hash);
+    }
+
+    /// Remove the lines for which the given predicate returns `false`,
+    /// updating the line counts of their authors.
+    fn retain_lines<F: FnMut(&Line) -> bool>(&mut self, mut keep: F) {
+        self.lines.retain(|line| {
+            if keep(line) {
+                return true;
+            }
+            if let Some(count) = line
+                .author
+                .and_then(|public_key| self.line_counts.get_mut(&public_key))
+            {
+                *count -= 1;
+                self.coloured = None;
+            }
+            false
+        });
+        self.line_counts.retain(|_public_key, count| *count > 0);
+    }
+
+    /// Discard the cached formatted lines of replies to the post with the
+    /// given hash.
+    fn invalidate_replies(&mut self, hash: &Hash) {
+        for index in self.replies.get(hash).into_iter().flatten() {
+            self.rendered.remove(index);
+        }
+    }
+
+    /// Scroll back by the given number of lines, stopping at the oldest
+    /// line.
+    pub fn scroll_up(&mut self, n: usize) {
+        self.scroll = (self.scroll + n).min(self.lines.len().saturating_sub(1));
+    }
+
+    /// Scroll forward by the given number of lines, stopping at the most
+    /// recent line.
+    pub fn scroll_down(&mut self, n: usize) {
+        self.scroll = self.scroll.saturating_sub(n);
+    }
+
+    /// Discard all cached formatted lines, causing them to be formatted
+    /// again on the next update (for example, after a change of colours).
+    pub fn invalidate(&mut self) {
+        self.rendered.clear();
+    }
+
+    /// Determine whether the window is virtual, meaning that it does not
+    /// display a cable channel (e.g. `!status`). The names of virtual windows
+    /// begin with `!`.
+    pub fn is_virtual(&self) -> bool {
+        self.channel.starts_with('!')
+    }
+
+    /// Remove the displayed lines from the window.
+    ///
+    /// Unlike `clear`, the hashes of the posts are retained, so that posts
+    /// sent again by the store or by peers are not displayed again.
+    pub fn clear_lines(&mut self) {
+        self.lines.clear();
+        self.rendered.clear();
+        self.line_counts.clear();
+        self.coloured = None;
+        self.scroll = 0;
+    }
+
+    /// Remove all lines from the window.
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.hashes.clear();
+        self.rendered.clear();
+        self.replies.clear();
+        self.line_counts.clear();
+        self.coloured = None;
+    }
+
+    /// Return the display name of the window (`!status`, `!pins:channel` or
+    /// `#channel`), or its label if one has been set.
+    pub fn name(&self) -> String {
+        if let Some(label) = &self.label {
+            label.to_owned()
+        } else if self.is_virtual() {
+            self.channel.to_owned()
+        } else {
+            format!("#{}", self.channel)
+        }
+    }
 }
 
 pub struct Ui {
@@ -103,8 +882,83 @@ pub struct Ui {
     pub diff: ansi_diff::Diff,
     pub size: TermSize,
     pub input: Input,
+    pub settings: Settings,
+    /// Public keys of peers whose posts are hidden.
+    pub blocked: HashSet<PublicKey>,
+    /// Private local notes about peers, set with `/note`.
+    pub notes: HashMap<PublicKey, String>,
+    /// The nickname of the local peer in each cabal, displayed in the
+    /// headers of its channel windows.
+    pub local_nicks: HashMap<Addr, Nickname>,
+    /// The local aliases of cabals, displayed in the input prompt.
+    pub aliases: HashMap<Addr, String>,
+    /// Types of post which are ignored, keyed by the public key of the
+    /// author.
+    pub ignored: BTreeMap<PublicKey, BTreeSet<Ignore>>,
     pub stdout: std::io::Stdout,
+    /// Whether the UI is running without a terminal (as a daemon), in which
+    /// case nothing is rendered and status messages are logged instead.
+    pub headless: bool,
+    /// The modes of the terminal when the UI was created, prior to entering
+    /// raw mode, which are restored while cabin is suspended.
+    cooked: Option<tty::Modes>,
+    /// The names of all commands, used to highlight unknown commands in the
+    /// input line and to complete command names.
+    commands: Vec<&'static str>,
+    /// The ongoing completion of the input line, if any.
+    completion: Option<Completion>,
+    /// The position of the selected post in the active window (where 1 is
+    /// the most recent post), if a post is being selected.
+    pub selection: Option<usize>,
+    /// The state of the window switcher, if open.
+    pub switcher: Option<Switcher>,
+    /// The state of the lock, if the UI is locked. Nothing but the
+    /// passphrase prompt is rendered while locked.
+    pub locked: Option<Lock>,
+    /// Whether command output is being paged in the status window, which is
+    /// scrolled back to the first undisplayed page.
+    pub paging: bool,
+    /// Whether the UI is rendered for screen readers, appending new lines
+    /// (prefixed with the name of their window) to the terminal without
+    /// colours rather than repainting the screen.
+    pub accessible: bool,
+    /// The index of the window displayed below the active window, if the
+    /// view is split.
+    split: Option<usize>,
+    /// The cabal address and channel of each post listed in the mentions
+    /// window, keyed by post hash.
+    mention_origins: HashMap<Hash, (Addr, Channel)>,
+    /// The number of consecutive posts from the future received from each
+    /// peer.
+    clock_skew: HashMap<PublicKey, usize>,
+    /// The timestamp and channel of the most recent post seen from each
+    /// peer, keyed by cabal address and public key.
+    last_seen: HashMap<(Addr, PublicKey), (Timestamp, Channel)>,
+    /// The most recently written terminal title.
+    title: String,
+    /// The path and contents of the most recently written status file.
+    status_line: Option<(String, String)>,
+    /// The time at which the active window was last changed.
+    active_changed: Instant,
+    /// The time at which input was last received.
+    last_input: Instant,
+    /// The time at which the UI was last rendered.
+    last_render: Instant,
+    /// Whether an update has been deferred because the UI is idle.
+    pending_update: bool,
+    /// The active window at the time of the last render in accessible mode.
+    printed_active: Option<usize>,
+    /// Senders of the lines relayed to the clients attached to the daemon.
+    attached: Vec<UnboundedSender<String>>,
+    /// Notifications to be displayed in the flash line above the input line,
+    /// along with the number of times each was repeated. The first is
+    /// currently displayed.
+    flash: VecDeque<(String, usize)>,
+    /// The time at which the current notification was first displayed.
+    flash_shown: Instant,
     tick: u64,
+    /// The source of the current time, shared with every window.
+    clock: Arc<dyn Clock>,
 }
 
 impl Ui {
@@ -113,26 +967,202 @@ impl Ui {
 
         Self {
             diff: ansi_diff::Diff::new(size),
+            cooked: tty::Modes::get(),
             size,
             active_window: 0,
             active_address: None,
             windows,
             input: Input::default(),
+            settings: Settings::default(),
+            blocked: HashSet::new(),
+            notes: HashMap::new(),
+            local_nicks: HashMap::new(),
+            aliases: HashMap::new(),
+            ignored: BTreeMap::new(),
             stdout: std::io::stdout(),
+            headless: false,
+            commands: Vec::new(),
+            completion: None,
+            selection: None,
+            switcher: None,
+            locked: None,
+            paging: false,
+            accessible: false,
+            split: None,
+            mention_origins: HashMap::new(),
+            clock_skew: HashMap::new(),
+            last_seen: HashMap::new(),
+            title: String::new(),
+            status_line: None,
+            active_changed: Instant::now(),
+            last_input: Instant::now(),
+            last_render: Instant::now(),
+            pending_update: false,
+            printed_active: None,
+            attached: Vec::new(),
+            flash: VecDeque::new(),
+            flash_shown: Instant::now(),
             tick: 0,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Set the names of all commands, so that input beginning with any other
+    /// command is highlighted.
+    pub fn with_commands(mut self, commands: impl IntoIterator<Item = &'static str>) -> Self {
+        self.commands = commands.into_iter().collect();
+        self
+    }
+
+    /// Read the current time from the given clock rather than the system
+    /// clock, in the UI and all of its windows.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.set_clock(clock);
+        self
+    }
+
+    /// Replace the source of the current time of the UI and all of its
+    /// windows.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        for window in self.windows.iter_mut() {
+            window.clock = clock.clone();
+        }
+        self.clock = clock;
+    }
+
+    /// Return the source of the current time.
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    /// Determine whether the input line begins with an unknown command.
+    ///
+    /// While the command is being typed it need only be a prefix of a known
+    /// command; once followed by arguments it must match exactly.
+    fn is_unknown_command(&self) -> bool {
+        let value = &self.input.value;
+        if !value.starts_with('/') || self.commands.is_empty() {
+            return false;
+        }
+
+        let name = value.split_whitespace().next().unwrap_or_default();
+        if value.len() > name.len() {
+            !self.commands.contains(&name)
+        } else {
+            !self
+                .commands
+                .iter()
+                .any(|command| command.starts_with(name))
         }
     }
 
     pub fn resize(&mut self, size: TermSize) {
+        self.size = size;
         self.diff.resize(size);
+        // Clear the screen on the next render, since previously rendered
+        // lines may have been reflowed by the terminal.
+        self.tick = 0;
+    }
+
+    /// Repaint the entire screen, e.g. after it was corrupted by the output
+    /// of another program.
+    pub fn redraw(&mut self) {
+        if self.headless {
+            return;
+        }
+        self.diff = ansi_diff::Diff::new(self.size);
+        for window in self.windows.iter_mut() {
+            window.invalidate();
+        }
+        // The screen is cleared by the next render.
+        self.tick = 0;
+        self.render();
     }
 
     pub fn get_size(&self) -> TermSize {
         self.size
     }
 
-    pub fn write_status(&mut self, msg: &str) {
-        self.windows.get_mut(0).unwrap().write(msg);
+    /// Write a message of the given level to the status window.
+    pub fn write_status(&mut self, level: Level, msg: &str) {
+        if self.headless {
+            match level {
+                Level::Error => warn!("{}", msg),
+                _ => info!("{}", msg),
+            }
+        }
+        let window = self.windows.get_mut(0).unwrap();
+        window.write_as(level, msg);
+        if self.headless {
+            window.trim(HEADLESS_WINDOW_LINES);
+        }
+        // The displayed page remains in place as new lines are written.
+        if self.paging {
+            self.windows[0].scroll += 1;
+        }
+    }
+
+    /// Write a message to the status window and, unless the status window is
+    /// displayed, to the flash line.
+    pub fn write_alert(&mut self, level: Level, msg: &str) {
+        self.write_status(level, msg);
+        if self.active_window != 0 && self.split_window() != Some(0) {
+            self.flash(msg);
+        }
+    }
+
+    /// Write a connection event to the network window.
+    pub fn write_network(&mut self, msg: &str) {
+        if self.headless {
+            info!("{}", msg);
+        }
+        let index = self.network_window_index();
+        self.windows[index].write_as(Level::Network, msg);
+        if self.headless {
+            self.windows[index].trim(HEADLESS_WINDOW_LINES);
+        }
+    }
+
+    /// Write a connection event to the network window and, unless the
+    /// network window is displayed, to the flash line.
+    pub fn write_network_alert(&mut self, msg: &str) {
+        self.write_network(msg);
+        let index = self.network_window_index();
+        if self.active_window != index && self.split_window() != Some(index) {
+            self.flash(msg);
+        }
+    }
+
+    /// Display a short-lived notification in the flash line above the input
+    /// line.
+    ///
+    /// Notifications are displayed one after another, each for
+    /// `FLASH_DURATION`. Repeats of a waiting notification are counted
+    /// rather than queued again.
+    pub fn flash(&mut self, msg: &str) {
+        if self.headless || self.accessible {
+            return;
+        }
+        if let Some((_msg, count)) = self.flash.iter_mut().find(|(queued, _)| queued == msg) {
+            *count += 1;
+        } else if self.flash.len() < FLASH_QUEUE_LEN {
+            if self.flash.is_empty() {
+                self.flash_shown = Instant::now();
+            }
+            self.flash.push_back((msg.to_string(), 1));
+        }
+    }
+
+    /// Remove the displayed notification from the flash line if it has been
+    /// displayed for long enough, returning `true` if it was removed.
+    fn expire_flash(&mut self) -> bool {
+        if self.flash.is_empty() || self.flash_shown.elapsed() < FLASH_DURATION {
+            return false;
+        }
+        self.flash.pop_front();
+        self.flash_shown = Instant::now();
+
+        true
     }
 
     pub fn write(&mut self, index: usize, msg: &str) {
@@ -148,7 +1178,444 @@ impl Ui {
     }
 
     pub fn set_active_index(&mut self, index: usize) {
-        self.active_window = index.min(self.windows.len().max(1) - 1);
+        let index = index.min(self.windows.len().max(1) - 1);
+        if index != self.active_window {
+            self.active_changed = Instant::now();
+            self.end_selection();
+            if self.paging {
+                self.end_pager();
+            }
+        }
+        self.active_window = index;
+
+        // Activity in the newly active window is now visible.
+        let window = self.get_active_window();
+        window.unread = 0;
+        window.mentions = 0;
+    }
+
+    /// Return the types of post which are ignored for the given peer.
+    pub fn ignored_types(&self, public_key: &PublicKey) -> BTreeSet<Ignore> {
+        self.ignored.get(public_key).cloned().unwrap_or_default()
+    }
+
+    /// Record a post from the given peer in the given channel, updating the
+    /// time at which the peer was last seen if the post is more recent.
+    pub fn record_seen(
+        &mut self,
+        address: &Addr,
+        public_key: PublicKey,
+        timestamp: Timestamp,
+        channel: &Channel,
+    ) {
+        let seen = self
+            .last_seen
+            .entry((address.to_owned(), public_key))
+            .or_insert((timestamp, channel.to_owned()));
+        if timestamp > seen.0 {
+            *seen = (timestamp, channel.to_owned());
+        }
+    }
+
+    /// Return the timestamp and channel of the most recent post seen from
+    /// the given peer of the given cabal.
+    pub fn last_seen(
+        &self,
+        address: &Addr,
+        public_key: &PublicKey,
+    ) -> Option<&(Timestamp, Channel)> {
+        self.last_seen.get(&(address.to_owned(), *public_key))
+    }
+
+    /// Return the index of the mentions window, creating the window if it
+    /// does not yet exist.
+    pub fn mentions_window_index(&mut self) -> usize {
+        let channel = MENTIONS_WINDOW.to_string();
+        self.get_window_index(&vec![], &channel).unwrap_or_else(|| {
+            let index = self.add_window(vec![], channel);
+            self.windows[index].update_topic(
+                "mentions across all channels; enter N to jump to the Nth most recent".to_string(),
+            );
+            index
+        })
+    }
+
+    /// Return the index of the activity window, creating the window if it
+    /// does not yet exist.
+    pub fn activity_window_index(&mut self) -> usize {
+        let channel = ACTIVITY_WINDOW.to_string();
+        self.get_window_index(&vec![], &channel).unwrap_or_else(|| {
+            let index = self.add_window(vec![], channel);
+            self.windows[index].update_topic("recent activity of all channels".to_string());
+            index
+        })
+    }
+
+    /// Return the index of the network window, creating the window if it
+    /// does not yet exist.
+    pub fn network_window_index(&mut self) -> usize {
+        let channel = NETWORK_WINDOW.to_string();
+        self.get_window_index(&vec![], &channel).unwrap_or_else(|| {
+            let index = self.add_window(vec![], channel);
+            self.windows[index].update_topic("peer connection events".to_string());
+            index
+        })
+    }
+
+    /// Replace the contents of the activity window (at the given index) with
+    /// a summary of each channel window: the unread and mention counts, and
+    /// the most recent post and its author.
+    ///
+    /// Channels are ordered by the time of their most recent post, with the
+    /// most recently active channel last.
+    fn refresh_activity(&mut self, index: usize) {
+        let summaries = self
+            .windows
+            .iter()
+            .filter(|window| !window.is_virtual())
+            .filter_map(|window| {
+                let line = window.nth_recent_post(1)?;
+                let counts = match (window.unread, window.mentions) {
+                    (0, _) => String::new(),
+                    (unread, 0) => format!(" [{} unread]", unread),
+                    (unread, mentions) => format!(" [{} unread, {} mentions]", unread, mentions),
+                };
+                let text = format!(
+                    "#{}{}: {}",
+                    window.channel,
+                    counts,
+                    utils::snippet(&line.text, 48)
+                );
+
+                Some((line.timestamp, line.author, line.nickname.clone(), text))
+            })
+            .collect::<Vec<_>>();
+
+        let window = &mut self.windows[index];
+        window.clear();
+        for (timestamp, author, nickname, text) in summaries {
+            window.insert(timestamp, author, nickname, &text, None);
+        }
+        if window.lines.is_empty() {
+            window.write("{ no channel activity }");
+        }
+    }
+
+    /// Record the timestamp of a post received from the given peer, writing a
+    /// warning to the status window if the clock of the peer appears to be
+    /// consistently ahead of the local clock.
+    pub fn record_timestamp(&mut self, public_key: PublicKey, name: &str, timestamp: Timestamp) {
+        let now = self.clock.now().unwrap_or(timestamp);
+        if timestamp <= now + CLOCK_SKEW_TOLERANCE {
+            self.clock_skew.remove(&public_key);
+            return;
+        }
+
+        let count = self.clock_skew.entry(public_key).or_default();
+        *count += 1;
+        if *count == CLOCK_SKEW_WARNING_POSTS {
+            self.write_status(
+                Level::Error,
+                &format!(
+                    "warning: the clock of {} appears to be {} minutes ahead",
+                    name,
+                    (timestamp - now) / 60_000
+                ),
+            );
+        }
+    }
+
+    /// Copy the line of the post with the given hash from the channel window
+    /// with the given index to the mentions window, prefixed with the channel
+    /// in which the post was made.
+    pub fn record_mention(&mut self, origin: usize, hash: &Hash) {
+        let window = &self.windows[origin];
+        let (address, channel) = (window.address.clone(), window.channel.clone());
+        let Some(line) = window
+            .lines
+            .iter()
+            .find(|line| line.hash.as_ref() == Some(hash))
+            .cloned()
+        else {
+            return;
+        };
+
+        let index = self.mentions_window_index();
+        self.windows[index].insert(
+            line.timestamp,
+            line.author,
+            line.nickname,
+            &format!("#{}: {}", channel, line.text),
+            line.hash,
+        );
+        self.mention_origins.insert(*hash, (address, channel));
+    }
+
+    /// Make the channel window of the Nth most recent mention active,
+    /// scrolled to the mentioning post.
+    pub fn jump_to_mention(&mut self, n: usize) -> Result<(), String> {
+        let index = self.mentions_window_index();
+        let hash = self.windows[index]
+            .nth_recent_post(n)
+            .and_then(|line| line.hash)
+            .ok_or_else(|| format!("no mention numbered {}", n))?;
+        let (address, channel) = self
+            .mention_origins
+            .get(&hash)
+            .cloned()
+            .ok_or_else(|| format!("the origin of mention {} is unknown", n))?;
+        let index = self
+            .get_window_index(&address, &channel)
+            .ok_or_else(|| format!("#{} is no longer joined", channel))?;
+
+        if !self.windows[index].scroll_to(&hash) {
+            return Err(format!("the post is no longer displayed in #{}", channel));
+        }
+        self.set_active_index(index);
+
+        Ok(())
+    }
+
+    /// Split the view, displaying the window with the given index below the
+    /// active window, or remove the split if `None` is given.
+    pub fn set_split(&mut self, index: Option<usize>) {
+        self.split = index;
+    }
+
+    /// Return the index of the window displayed below the active window, if
+    /// the view is split.
+    ///
+    /// A split view showing the active window twice is displayed unsplit.
+    pub fn split_window(&self) -> Option<usize> {
+        self.split
+            .filter(|index| *index != self.active_window && *index < self.windows.len())
+    }
+
+    /// Make the next window active, wrapping around to the first.
+    pub fn next_window(&mut self) {
+        let index = (self.active_window + 1) % self.windows.len().max(1);
+        self.set_active_index(index);
+    }
+
+    /// Make the previous window active, wrapping around to the last.
+    pub fn prev_window(&mut self) {
+        let len = self.windows.len().max(1);
+        let index = (self.active_window + len - 1) % len;
+        self.set_active_index(index);
+    }
+
+    /// Begin selecting the posts of the active window, starting with the
+    /// most recent post.
+    pub fn start_selection(&mut self) {
+        if self.get_active_window().nth_recent_post(1).is_some() {
+            self.selection = Some(1);
+            self.input.set_capture(true);
+        }
+    }
+
+    /// Stop selecting posts.
+    pub fn end_selection(&mut self) {
+        self.selection = None;
+        self.input.set_capture(false);
+    }
+
+    /// Open the window switcher.
+    pub fn start_switcher(&mut self) {
+        self.end_selection();
+        self.switcher = Some(Switcher::default());
+        self.input.set_capture(true);
+    }
+
+    /// Close the window switcher.
+    pub fn end_switcher(&mut self) {
+        self.switcher = None;
+        self.input.set_capture(false);
+    }
+
+    /// Lock the UI, hiding all windows until the passphrase is entered.
+    pub fn start_lock(&mut self) {
+        self.end_selection();
+        self.end_switcher();
+        self.locked = Some(Lock::default());
+        self.input.set_capture(true);
+    }
+
+    /// Unlock the UI.
+    pub fn end_lock(&mut self) {
+        self.locked = None;
+        self.input.set_capture(false);
+    }
+
+    /// Begin paging the given number of most recent lines of the status
+    /// window, if they are the output of a command which does not fit on the
+    /// screen, by scrolling back to the first line of the output.
+    pub fn start_pager(&mut self, lines: usize) {
+        let page = self.page_height();
+        if !self.settings.pager
+            || self.headless
+            || self.accessible
+            || self.locked.is_some()
+            || self.active_window != 0
+            || lines <= page
+        {
+            return;
+        }
+
+        self.end_selection();
+        self.windows[0].scroll = lines - page;
+        self.paging = true;
+        self.input.set_capture(true);
+    }
+
+    /// Display the given number of further lines of the paged output,
+    /// ending the pager once the most recent line is displayed.
+    pub fn page_down(&mut self, n: usize) {
+        self.windows[0].scroll_down(n);
+        if self.windows[0].scroll == 0 {
+            self.end_pager();
+        }
+    }
+
+    /// Stop paging and scroll the status window to the most recent line.
+    pub fn end_pager(&mut self) {
+        self.windows[0].scroll = 0;
+        self.paging = false;
+        self.input.set_capture(false);
+    }
+
+    /// Return the indices of the windows whose names (or labels) match the
+    /// given query, best match first.
+    ///
+    /// Ties are broken in favour of windows of the active cabal and then by
+    /// index. All windows match an empty query.
+    pub fn match_windows(&self, query: &str) -> Vec<usize> {
+        let query = query.trim_start_matches('#');
+        let mut matches: Vec<(usize, bool, usize)> = self
+            .windows
+            .iter()
+            .enumerate()
+            .filter_map(|(index, window)| {
+                let name = window.name();
+                let score = utils::fuzzy_score(query, name.trim_start_matches('#'))?;
+                let other_cabal =
+                    !window.is_virtual() && self.active_address.as_ref() != Some(&window.address);
+                Some((score, other_cabal, index))
+            })
+            .collect();
+        matches.sort();
+
+        matches.into_iter().map(|(_, _, index)| index).collect()
+    }
+
+    /// Return the line of the selected post, if any.
+    pub fn selected_post(&self) -> Option<&Line> {
+        self.windows[self.active_window].nth_recent_post(self.selection?)
+    }
+
+    /// Select the post which is the given number of posts older (or, if
+    /// negative, newer) than the selected post, scrolling the active window
+    /// such that it is visible.
+    pub fn move_selection(&mut self, offset: isize) {
+        let Some(n) = self.selection else {
+            return;
+        };
+        let n = n.saturating_add_signed(offset).max(1);
+        let height = self.page_height();
+        let window = self.get_active_window();
+        if let Some(index) = window.nth_recent_post(n).map(|line| line.index) {
+            window.reveal(index, height);
+            self.selection = Some(n);
+        }
+    }
+
+    /// Copy the given text to the clipboard of the terminal (OSC 52).
+    pub fn copy_to_clipboard(&mut self, text: &str) {
+        write!(
+            self.stdout,
+            "\x1b]52;c;{}\x07",
+            utils::base64(text.as_bytes())
+        )
+        .unwrap();
+        self.stdout.flush().unwrap();
+    }
+
+    /// Return the number of lines scrolled by a page up or page down: the
+    /// height of the window, less one line of context.
+    pub fn page_height(&self) -> usize {
+        (self.size.1 as usize).saturating_sub(3).max(1)
+    }
+
+    /// Determine whether the active window was changed within the number of
+    /// seconds defined by the `guard` setting.
+    pub fn active_recently_changed(&self) -> bool {
+        self.settings.guard > 0
+            && self.active_changed.elapsed() < Duration::from_secs(self.settings.guard)
+    }
+
+    /// Record the arrival of a new post in the window at the given index.
+    ///
+    /// Increments the unread (and, if applicable, mention) counters of the
+    /// window if it is not displayed. Outside quiet hours, mentions are also
+    /// flashed and, if the `bell` setting is enabled, emit a terminal bell.
+    pub fn mark_activity(&mut self, index: usize, mention: bool) {
+        if index == self.active_window || Some(index) == self.split_window() {
+            return;
+        }
+
+        if let Some(window) = self.windows.get_mut(index) {
+            window.unread += 1;
+            if mention {
+                window.mentions += 1;
+            }
+        }
+        // Notifications are suppressed during quiet hours.
+        if !mention || self.settings.is_quiet(self.clock.as_ref()) {
+            return;
+        }
+
+        if let Some(window) = self.windows.get(index) {
+            let msg = format!("mentioned in {}", window.name());
+            self.flash(&msg);
+        }
+
+        if self.settings.bell && !self.headless {
+            write!(self.stdout, "\x07").unwrap();
+        }
+    }
+
+    /// Return the total number of unread posts across all windows.
+    pub fn unread_count(&self) -> usize {
+        self.windows.iter().map(|w| w.unread).sum()
+    }
+
+    /// Return the total number of unread mentions across all windows.
+    pub fn mention_count(&self) -> usize {
+        self.windows.iter().map(|w| w.mentions).sum()
+    }
+
+    /// Write the terminal title (OSC 0) if it has changed since the last
+    /// update.
+    ///
+    /// The title includes the name of the active window and the number of
+    /// unread posts and mentions in all other windows.
+    fn update_title(&mut self) {
+        let name = self.windows[self.active_window].name();
+        let (unread, mentions) = (self.unread_count(), self.mention_count());
+
+        let title = match (unread, mentions) {
+            // The active channel is not revealed while locked.
+            _ if self.locked.is_some() => "cabin".to_string(),
+            (0, _) => format!("cabin: {}", name),
+            (unread, 0) => format!("cabin: {} [{} unread]", name, unread),
+            (unread, mentions) => {
+                format!("cabin: {} [{} unread, {} mentions]", name, unread, mentions)
+            }
+        };
+
+        if title != self.title {
+            write!(self.stdout, "\x1b]0;{}\x07", title).unwrap();
+            self.title = title;
+        }
     }
 
     pub fn get_active_address(&self) -> Option<&Addr> {
@@ -160,7 +1627,8 @@ impl Ui {
     }
 
     pub fn add_window(&mut self, address: Addr, channel: Channel) -> usize {
-        self.windows.push(Window::new(address, channel));
+        self.windows
+            .push(Window::new(address, channel).with_clock(self.clock.clone()));
         self.windows.len() - 1
     }
 
@@ -183,94 +1651,290 @@ impl Ui {
     pub fn move_window(&mut self, src: usize, dst: usize) {
         let w = self.windows.remove(src);
         self.windows.insert(dst, w);
+
+        // The active window and the watched window of a split view follow
+        // the move.
+        let follow = |index| match index {
+            index if index == src => dst,
+            index if src < index && index <= dst => index - 1,
+            index if dst <= index && index < src => index + 1,
+            index => index,
+        };
+        self.active_window = follow(self.active_window);
+        if let Some(split) = self.split {
+            self.split = Some(follow(split));
+        }
+    }
+
+    /// Arrange the windows of the given cabal channels in the given order,
+    /// following the status window. Windows which are not listed keep their
+    /// relative order and follow the arranged windows.
+    pub fn arrange_windows(&mut self, order: &[(Addr, Channel)]) {
+        let mut dst = 1;
+        for (address, channel) in order {
+            if let Some(src) = self.get_window_index(address, channel) {
+                if src >= dst {
+                    self.move_window(src, dst);
+                    dst += 1;
+                }
+            }
+        }
     }
 
     pub fn remove_window(&mut self, index: usize) {
         self.windows.remove(index);
+        self.split = match self.split {
+            Some(split) if split == index => None,
+            Some(split) if split > index => Some(split - 1),
+            split => split,
+        };
         if index < self.active_window {
             self.active_window = self.active_window.min(1) - 1;
         }
     }
 
-    pub fn update(&mut self) {
-        // Get the active window.
-        // TODO: Handle the error case properly.
-        let window = self.windows.get(self.active_window).unwrap();
-
-        let mut lines = window
-            .lines
+    /// Return a single line summarising the unread posts and mentions of
+    /// every window, suitable for display in a tmux status line.
+    ///
+    /// Each window with unread posts is listed as `#channel:UNREAD`, with
+    /// the number of mentions appended in parentheses if any exist (e.g.
+    /// `#dev:4(@1) #random:12`). The line is empty if there is no activity.
+    pub fn status_line(&self) -> String {
+        self.windows
             .iter()
-            .map(|(_index, timestamp, author, nickname, line)| {
-                if let Some(public_key) = author {
-                    let colour = utils::public_key_to_colour(public_key);
-
-                    // Display the nickname of the post author if one is known.
-                    if let Some(name) = nickname {
-                        format!(
-                            "[{}] <{}> {}",
-                            time::format(*timestamp),
-                            name.color(colour),
-                            line
-                        )
-                    } else {
-                        // Fallback to displaying the abbreviated public key of
-                        // the author if no nickname is known.
-                        let abbreviated_public_key = hex::to(&public_key[..4]);
-                        format!(
-                            "[{}] <{}> {}",
-                            time::format(*timestamp),
-                            abbreviated_public_key.color(colour),
-                            line
-                        )
-                    }
+            .filter(|w| w.unread > 0)
+            .map(|w| {
+                if w.mentions > 0 {
+                    format!("{}:{}(@{})", w.name(), w.unread, w.mentions)
                 } else {
-                    format!(
-                        "[{}] {} {}",
-                        time::format(*timestamp),
-                        "-status-".bright_green(),
-                        line
-                    )
+                    format!("{}:{}", w.name(), w.unread)
                 }
             })
-            .collect::<Vec<String>>();
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
 
-        for _ in lines.len()..(self.size.1 as usize) - 2 {
-            lines.push(String::default());
+    /// Write the status line to the file defined by the `statusfile`
+    /// setting, if it has changed since the last update.
+    ///
+    /// A file newly named by the setting is written even if the status line
+    /// has not changed.
+    fn update_status_file(&mut self) {
+        let Some(path) = self.settings.status_file.clone() else {
+            self.status_line = None;
+            return;
+        };
+        let line = self.status_line();
+        let written = self
+            .status_line
+            .as_ref()
+            .is_some_and(|(written_path, written_line)| {
+                *written_path == path && *written_line == line
+            });
+        if !written {
+            if let Err(err) = fs::write(&path, format!("{}\n", line)) {
+                warn!("Failed to write status file {}: {}", path, err);
+            }
+            self.status_line = Some((path, line));
         }
+    }
+
+    /// Record that input has been received, ending any idle period.
+    pub fn record_input(&mut self) {
+        self.last_input = Instant::now();
+    }
 
-        let input = {
-            let c = self.input.cursor.min(self.input.value.len());
-            let n = (c + 1).min(self.input.value.len());
-            let s = if n > c { &self.input.value[c..n] } else { " " };
-            self.input.value[0..c].to_string() + "\x1b[7m" + s + "\x1b[0m" + &self.input.value[n..]
+    /// Determine whether the UI is idle, meaning that no input has been
+    /// received for the number of seconds defined by the `idle` setting.
+    fn is_idle(&self) -> bool {
+        self.settings.idle > 0
+            && self.last_input.elapsed() >= Duration::from_secs(self.settings.idle)
+    }
+
+    /// Render the UI if an update was deferred while idle or the flash line
+    /// has expired.
+    pub fn flush(&mut self) {
+        if self.expire_flash() {
+            self.pending_update = true;
+        }
+        if self.pending_update && !self.headless {
+            self.render();
+        }
+    }
+
+    /// Update the UI.
+    ///
+    /// While idle, renders are throttled to one per `IDLE_RENDER_INTERVAL`;
+    /// intermediate updates (such as a burst of incoming posts) are coalesced
+    /// and rendered by the `flusher` task.
+    pub fn update(&mut self) {
+        if self.headless {
+            self.relay();
+            return;
+        }
+        if self.is_idle() && self.last_render.elapsed() < IDLE_RENDER_INTERVAL {
+            self.pending_update = true;
+        } else {
+            self.render();
+        }
+    }
+
+    /// Render the active window, input line and terminal title.
+    fn render(&mut self) {
+        self.pending_update = false;
+        self.last_render = Instant::now();
+
+        if let Some(failures) = self.locked.as_ref().map(|lock| lock.failures) {
+            self.update_title();
+            self.render_lock(failures);
+            return;
+        }
+
+        if self.accessible {
+            self.update_status_file();
+            self.render_linear();
+            return;
+        }
+
+        self.update_title();
+        self.update_status_file();
+
+        self.expire_flash();
+
+        // The rows above the input line (and the flash line, if a
+        // notification is displayed) are shared by the active window and, if
+        // the view is split, the watched window below it. Each pane consists
+        // of a header followed by as many lines as fit.
+        let flash = self.flash.front().map(|(msg, count)| {
+            let msg = if *count > 1 {
+                format!("{} (x{})", msg, count)
+            } else {
+                msg.to_owned()
+            };
+            let msg: String = msg.chars().take(self.size.0 as usize).collect();
+            format!("{}", msg.reversed())
+        });
+        let rows = (self.size.1 as usize).saturating_sub(1 + flash.iter().count());
+        let panes = match self.split_window() {
+            Some(watched) => {
+                let top = rows / 2;
+                vec![(self.active_window, top), (watched, rows - top)]
+            }
+            None => vec![(self.active_window, rows)],
+        };
+
+        // The activity window is refreshed whenever it is displayed.
+        for (index, _) in &panes {
+            if self.windows[*index].channel == ACTIVITY_WINDOW {
+                self.refresh_activity(*index);
+            }
+        }
+
+        let mut screen = Vec::with_capacity(rows);
+        for (index, pane_rows) in panes {
+            // Only the lines which fit between the header and the bottom of
+            // the pane are formatted and rendered.
+            let height = pane_rows.saturating_sub(1);
+
+            let selected = if index == self.active_window {
+                self.selected_post().map(|line| line.index)
+            } else {
+                None
+            };
+
+            // TODO: Handle the error case properly.
+            let window = self.windows.get_mut(index).unwrap();
+            window.assign_colours(self.settings.palette);
+            let notes = self.settings.notes.then_some(&self.notes);
+            let mut lines = window.viewport(height, &self.blocked, notes, selected);
+
+            for _ in lines.len()..height {
+                lines.push(String::default());
+            }
+
+            screen.push(self.header(index));
+            screen.extend(lines);
+        }
+
+        // The window switcher lists the matching windows at the bottom of
+        // the screen, above the input line.
+        if let Some(switcher) = &self.switcher {
+            let matches = self.match_windows(&switcher.query);
+            let shown = matches.len().min(SWITCHER_ROWS).min(rows.saturating_sub(1));
+            let start = screen.len() - shown;
+            for (i, index) in matches.iter().take(shown).enumerate() {
+                let entry = format!("{:>3} {}", index, self.windows[*index].name());
+                screen[start + i] = if i == switcher.selected {
+                    format!("{}", entry.reversed())
+                } else {
+                    entry
+                };
+            }
+        }
+        screen.extend(flash);
+
+        // The input line is scrolled horizontally to fit the width of the
+        // terminal (less the width of the prompt), with the prompt indicating
+        // the window to which a message would be posted and whether text is
+        // hidden to the left of the cursor.
+        // A reverse search of the history is displayed in place of the
+        // prompt.
+        let search = self.search_prompt();
+        let context = search.clone().unwrap_or_else(|| self.prompt_context());
+        let width = (self.size.0 as usize).saturating_sub(context.chars().count() + 3);
+        let (input, scrolled) = self.input.render(width);
+        let prompt = match search {
+            Some(_) => ':',
+            None if scrolled => '<',
+            None => '>',
+        };
+        // Unknown commands are highlighted before they are sent.
+        let input = if self.is_unknown_command() {
+            format!("{}", input.red())
+        } else {
+            input
+        };
+        // The prompt is highlighted in vi normal mode.
+        let prompt = if self.input.is_normal_mode() {
+            format!("{} {}", context, prompt.reversed())
+        } else {
+            format!("{} {}", context, prompt)
+        };
+
+        let frame = match &self.switcher {
+            Some(switcher) => format!(
+                "{}\n{} {}",
+                screen.join("\n"),
+                i18n::tr("switch to:").reversed(),
+                switcher.query
+            ),
+            None if self.paging => format!(
+                "{}\n{}",
+                screen.join("\n"),
+                i18n::tr("-- more (space) --").reversed()
+            ),
+            None => format!("{}\n{} {}", screen.join("\n"), prompt, &input),
+        };
+        // Colours and other attributes are removed from the rendered frame
+        // if disabled.
+        let frame = if self.settings.color {
+            frame
+        } else {
+            utils::strip_ansi(&frame)
         };
 
         write!(
             self.stdout,
             "{}{}",
-            if self.tick == 0 { "\x1bc\x1b[?25l" } else { "" }, // clear, turn off cursor
+            // Clear, turn off cursor and enable the keyboard protocol (which
+            // is reset by clearing).
+            if self.tick == 0 {
+                format!("\x1bc\x1b[?25l{}", ENABLE_KEYBOARD_PROTOCOL)
+            } else {
+                String::new()
+            },
             self.diff
-                .update(&format!(
-                    "[{}] {}\n{}\n> {}",
-                    // Display the channel name (!status or other).
-                    if window.channel == "!status" {
-                        format!("{}", window.channel.bright_green())
-                    } else {
-                        format!("#{}", &window.channel)
-                    },
-                    // Display the active cabal address.
-                    if window.channel == "!status" && self.active_address.is_some() {
-                        let addr = self.active_address.as_ref().unwrap();
-                        format!("cabal://{}", hex::to(addr))
-                    } else if window.channel == "!status" {
-                        "".to_string()
-                    } else {
-                        // Display the channel topic.
-                        window.topic.to_string()
-                    },
-                    lines.join("\n"),
-                    &input,
-                ))
+                .update(&frame)
                 .split('\n')
                 .collect::<Vec<&str>>()
                 .join("\r\n"),
@@ -280,7 +1944,429 @@ impl Ui {
         self.tick += 1;
     }
 
+    /// Render a blank screen with the passphrase prompt of the lock.
+    ///
+    /// In accessible mode only the prompt is printed; new lines are printed
+    /// once the UI is unlocked.
+    fn render_lock(&mut self, failures: usize) {
+        let prompt = match failures {
+            0 => i18n::tr("locked; enter the passphrase to unlock:").to_string(),
+            n => format!(
+                "{} ({})",
+                i18n::tr("incorrect passphrase; enter the passphrase to unlock:"),
+                n
+            ),
+        };
+
+        let output = if self.accessible {
+            format!("\r\x1b[K{} ", prompt)
+        } else {
+            let rows = (self.size.1 as usize).saturating_sub(1);
+            let frame = format!("{}{}", "\n".repeat(rows), prompt);
+            self.diff
+                .update(&frame)
+                .split('\n')
+                .collect::<Vec<&str>>()
+                .join("\r\n")
+        };
+        write!(self.stdout, "{}", output).unwrap();
+        self.stdout.flush().unwrap();
+        self.tick += 1;
+    }
+
+    /// Set or remove the note about the given peer, formatting the lines of
+    /// every window again if notes are displayed.
+    pub fn set_note(&mut self, public_key: PublicKey, note: Option<String>) {
+        match note {
+            Some(note) => self.notes.insert(public_key, note),
+            None => self.notes.remove(&public_key),
+        };
+        if self.settings.notes {
+            self.invalidate_windows();
+        }
+    }
+
+    /// Discard the cached formatted lines of every window.
+    pub fn invalidate_windows(&mut self) {
+        for window in self.windows.iter_mut() {
+            window.invalidate();
+        }
+    }
+
+    /// Attach a client to the daemon, relaying the lines inserted into the
+    /// status and channel windows from now on.
+    pub fn attach(&mut self, sender: UnboundedSender<String>) {
+        // Lines inserted while no client was attached are not relayed.
+        if self.attached.is_empty() {
+            for window in self.windows.iter_mut() {
+                window.take_unprinted(&self.blocked, None);
+            }
+        }
+        let _ = sender.unbounded_send(format!(
+            "attached to cabin ({} windows); /quit to detach",
+            self.windows.len()
+        ));
+        self.attached.push(sender);
+    }
+
+    /// Send the lines inserted since the last relay to each attached client,
+    /// each prefixed with the name of its window. Clients which have
+    /// detached are removed.
+    fn relay(&mut self) {
+        if self.attached.is_empty() {
+            return;
+        }
+
+        let notes = self.settings.notes.then_some(&self.notes);
+        let mut lines = Vec::new();
+        for (index, window) in self.windows.iter_mut().enumerate() {
+            // Virtual windows other than the status window repeat the posts
+            // of channel windows.
+            if window.is_virtual() && index != 0 {
+                continue;
+            }
+            let name = window.name();
+            for line in window.take_unprinted(&self.blocked, notes) {
+                lines.push(format!("{}: {}", name, line));
+            }
+        }
+
+        self.attached.retain(|sender| {
+            lines
+                .iter()
+                .all(|line| sender.unbounded_send(line.to_owned()).is_ok())
+        });
+    }
+
+    /// Render in accessible mode: print the lines inserted since the last
+    /// render, each prefixed with the name of its window, followed by the
+    /// input line.
+    ///
+    /// Virtual windows other than the status window repeat the posts of
+    /// channel windows, so their lines are only printed while they are
+    /// active. A change of the active window is announced with its header.
+    fn render_linear(&mut self) {
+        let mut output = String::new();
+        if self.tick == 0 {
+            output.push_str(ENABLE_KEYBOARD_PROTOCOL);
+        }
+        // Clear the input line, which is printed again below the new lines.
+        output.push_str("\r\x1b[K");
+
+        let active = self.active_window;
+        if self.printed_active != Some(active) {
+            self.printed_active = Some(active);
+            // The activity window is refreshed whenever it becomes active.
+            if self.windows[active].channel == ACTIVITY_WINDOW {
+                self.refresh_activity(active);
+            }
+            let header = utils::strip_ansi(&self.header(active));
+            output.push_str(&format!("{} {}\r\n", i18n::tr("now viewing"), header));
+        }
+
+        let notes = self.settings.notes.then_some(&self.notes);
+        for index in 0..self.windows.len() {
+            let window = &mut self.windows[index];
+            if window.is_virtual() && index != 0 && index != active {
+                continue;
+            }
+            let name = window.name();
+            for line in window.take_unprinted(&self.blocked, notes) {
+                output.push_str(&format!("{}: {}\r\n", name, line));
+            }
+        }
+
+        let search = self.search_prompt();
+        let context = search.clone().unwrap_or_else(|| self.prompt_context());
+        let width = (self.size.0 as usize).saturating_sub(context.chars().count() + 3);
+        let (input, _scrolled) = self.input.render(width);
+        let prompt = if search.is_some() { ':' } else { '>' };
+        output.push_str(&format!("{} {} {}", context, prompt, input));
+
+        write!(self.stdout, "{}", output).unwrap();
+        self.stdout.flush().unwrap();
+        self.tick += 1;
+    }
+
+    /// Format the header line of the window with the given index.
+    /// Complete the word before the cursor of the input line: a command name
+    /// or the argument of a command with a registered completer.
+    ///
+    /// If the input line is unchanged since the previous completion, the
+    /// completed word is replaced by the next candidate instead.
+    pub fn complete(&mut self) {
+        let value = self.input.value.clone();
+        let cursor = self.input.cursor.min(value.len());
+
+        let completion = match self.completion.take() {
+            Some(mut completion) if completion.value == value => {
+                completion.current = (completion.current + 1) % completion.candidates.len();
+                completion
+            }
+            _ => {
+                let before = &value[..cursor];
+                let start = before.rfind(' ').map(|i| i + 1).unwrap_or(0);
+                let word = before[start..].trim_start_matches('"');
+                let words: Vec<&str> = before[..start].split_whitespace().collect();
+                let candidates = match complete::completer(&words) {
+                    Some(completer) => self.candidates(completer),
+                    None if words.is_empty() && word.starts_with('/') => {
+                        self.commands.iter().map(|name| name.to_string()).collect()
+                    }
+                    None => return,
+                };
+                let candidates = complete::matching(word, candidates);
+                if candidates.is_empty() {
+                    return;
+                }
+                Completion {
+                    start,
+                    candidates,
+                    current: 0,
+                    value: String::new(),
+                }
+            }
+        };
+
+        // Arguments containing spaces are quoted. A unique candidate is
+        // followed by a space, ready for the next argument.
+        let candidate = &completion.candidates[completion.current];
+        let mut replacement = if candidate.contains(' ') {
+            format!("\"{}\"", candidate)
+        } else {
+            candidate.to_owned()
+        };
+        if completion.candidates.len() == 1 {
+            replacement.push(' ');
+        }
+        let value = format!(
+            "{}{}{}",
+            &value[..completion.start],
+            replacement,
+            &value[cursor..]
+        );
+        self.input.set_value(&value);
+        self.input.set_cursor(completion.start + replacement.len());
+
+        if completion.candidates.len() > 1 {
+            self.completion = Some(Completion {
+                value,
+                ..completion
+            });
+        }
+    }
+
+    /// Return the candidates of the given completer.
+    fn candidates(&self, completer: Completer) -> Vec<String> {
+        let active = self.active_address.as_ref();
+        let cabal_windows = self
+            .windows
+            .iter()
+            .filter(|window| Some(&window.address) == active && !window.is_virtual());
+
+        match completer {
+            Completer::Cabals => self
+                .aliases
+                .values()
+                .cloned()
+                .chain(
+                    self.windows
+                        .iter()
+                        .filter(|window| !window.address.is_empty())
+                        .map(|window| hex::to(&window.address)),
+                )
+                .chain(active.map(|address| hex::to(address)))
+                .collect(),
+            Completer::Windows => self
+                .windows
+                .iter()
+                .enumerate()
+                .flat_map(|(index, window)| [index.to_string(), window.name()])
+                .collect(),
+            Completer::Channels => cabal_windows.map(|window| window.channel.clone()).collect(),
+            Completer::Peers => cabal_windows
+                .flat_map(|window| window.latest_posts.values())
+                .filter_map(|(_timestamp, nickname)| nickname.clone())
+                .collect(),
+        }
+    }
+
+    /// Return the window to which a message would be posted, as displayed
+    /// before the input prompt (e.g. `[#dev]`, or `[myco/#dev]` if the cabal
+    /// of the window has an alias).
+    fn prompt_context(&self) -> String {
+        let window = &self.windows[self.active_window];
+        match self.aliases.get(&window.address) {
+            Some(alias) if !window.is_virtual() => format!("[{}/{}]", alias, window.name()),
+            _ => format!("[{}]", window.name()),
+        }
+    }
+
+    /// Describe the ongoing reverse search of the input history, if any, as
+    /// displayed in place of the prompt (e.g. ``(reverse-i-search)`join'``).
+    fn search_prompt(&self) -> Option<String> {
+        self.input.search().map(|(query, failed)| {
+            let label = if failed {
+                i18n::tr("failing reverse-i-search")
+            } else {
+                i18n::tr("reverse-i-search")
+            };
+            format!("({})`{}'", label, query)
+        })
+    }
+
+    /// Describe the local peer and its surroundings for the header of the
+    /// channel window with the given index.
+    fn channel_details(&self, index: usize) -> String {
+        let window = &self.windows[index];
+        let mut details = Vec::new();
+        if let Some(nick) = self.local_nicks.get(&window.address) {
+            details.push(format!("<{}>", nick));
+        }
+        if let Some(members) = window.members {
+            details.push(format!("{} {}", i18n::tr("members:"), members));
+        }
+        details.push(format!(
+            "{} {}",
+            i18n::tr("connections:"),
+            metrics::ACTIVE_CONNECTIONS.get()
+        ));
+        let elsewhere: usize = self
+            .windows
+            .iter()
+            .enumerate()
+            .filter(|(i, _window)| *i != index)
+            .map(|(_i, window)| window.mentions)
+            .sum();
+        if elsewhere > 0 {
+            details.push(format!("{} {}", i18n::tr("mentions elsewhere:"), elsewhere));
+        }
+
+        details.join(" · ")
+    }
+
+    fn header(&self, index: usize) -> String {
+        let window = self.windows.get(index).unwrap();
+
+        format!(
+            "[{}]{} {}",
+            // Display the channel name (!status or other) or label.
+            if window.is_virtual() {
+                format!("{}", window.name().bright_green())
+            } else {
+                window.name()
+            },
+            // Display the number of lines scrolled back, if any.
+            if window.scroll > 0 {
+                format!(" [+{}]", window.scroll)
+            } else {
+                String::new()
+            },
+            // Display the active cabal address.
+            if window.channel == "!status" && self.active_address.is_some() {
+                let addr = self.active_address.as_ref().unwrap();
+                format!("cabal://{}", hex::to(addr))
+            } else if window.channel == "!status" {
+                "".to_string()
+            } else if index == self.active_window && self.selection.is_some() {
+                // Describe the actions available in selection mode.
+                i18n::tr(SELECTION_HELP).to_string()
+            } else {
+                // Display the local nickname and the counts of members,
+                // connections and mentions in other windows, then the
+                // channel topic, followed by the peers who have likely
+                // synced the latest post of the local peer (if enabled).
+                let synced = window.synced_peers();
+                let topic = if self.settings.receipts && !synced.is_empty() {
+                    format!("{} (synced: {})", window.topic, synced.join(", "))
+                } else {
+                    window.topic.to_string()
+                };
+                format!("{} {}", self.channel_details(index).dimmed(), topic)
+            },
+        )
+    }
+
+    /// Restore the terminal (cooked mode, with the cursor visible) before
+    /// cabin is suspended.
+    fn suspend(&mut self) {
+        self.finish();
+        self.stdout.flush().unwrap();
+        if let Some(cooked) = &self.cooked {
+            cooked.set();
+        }
+    }
+
     pub fn finish(&mut self) {
-        write!(self.stdout, "\x1bc").unwrap();
+        if self.headless {
+            return;
+        }
+        if self.accessible {
+            write!(self.stdout, "{}\r\n", DISABLE_KEYBOARD_PROTOCOL).unwrap();
+            return;
+        }
+        write!(self.stdout, "{}\x1bc", DISABLE_KEYBOARD_PROTOCOL).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::FakeClock;
+
+    #[test]
+    fn window_reads_the_time_from_its_clock() {
+        let clock = Arc::new(FakeClock::new(1_700_000_000_000));
+        let mut window = Window::new(vec![], "test".to_string()).with_clock(clock.clone());
+
+        window.write("hello");
+        clock.advance(1_000);
+        // A post from an hour in the future is positioned at its arrival.
+        let future = 1_700_003_600_000;
+        window.insert(future, Some([1; 32]), None, "hi", Some([2; 32]));
+
+        let lines = window.lines.iter().collect::<Vec<&Line>>();
+        assert_eq!(lines[0].timestamp, 1_700_000_000_000);
+        assert_eq!(lines[1].position, 1_700_000_001_000);
+        assert_eq!(lines[1].timestamp, future);
+    }
+
+    #[test]
+    fn contrast_colours_follow_line_counts() {
+        let mut window = Window::new(vec![], "test".to_string());
+        let (alice, bob) = ([1; 32], [2; 32]);
+        window.insert(1, Some(alice), None, "a", Some([10; 32]));
+        window.insert(2, Some(bob), None, "b", Some([11; 32]));
+        window.insert(3, Some(bob), None, "b", Some([12; 32]));
+
+        window.assign_colours(Palette::Contrast);
+        assert_eq!(window.colours, utils::assign_colours(&[bob, alice]));
+
+        window.remove_post(&[11; 32]);
+        window.remove_post(&[12; 32]);
+        window.assign_colours(Palette::Contrast);
+        assert_eq!(window.colours, utils::assign_colours(&[alice]));
+        assert!(!window.line_counts.contains_key(&bob));
+    }
+
+    #[test]
+    fn trimming_keeps_the_newest_lines() {
+        let clock = Arc::new(FakeClock::new(1_700_000_000_000));
+        let mut window = Window::new(vec![], "test".to_string()).with_clock(clock.clone());
+        for i in 0..5 {
+            window.write(&format!("line {}", i));
+            clock.advance(1_000);
+        }
+
+        window.trim(2);
+        let texts = window
+            .lines
+            .iter()
+            .map(|line| line.text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["line 3", "line 4"]);
+
+        window.trim(2);
+        assert_eq!(window.lines.len(), 2);
     }
 }
@@ -1,5 +1,21 @@
+use std::{
+    collections::HashMap,
+    env, io,
+    process::{Command, Stdio},
+    thread,
+};
+
+use cable::Hash;
 use owo_colors::AnsiColors;
 
+use crate::hex;
+
+/// The prefix used to mark a text post as a reply to another post.
+///
+/// By convention, a reply begins with `[re:HASH] ` where `HASH` is the
+/// hex-encoded hash of the replied-to post.
+const REPLY_PREFIX: &str = "[re:";
+
 fn pick_colour(num: u64) -> AnsiColors {
     match num {
         1 => AnsiColors::Red,
@@ -18,12 +34,395 @@ fn pick_colour(num: u64) -> AnsiColors {
     }
 }
 
+/// High-contrast colours assigned to peers by `assign_colours`.
+const CONTRAST_COLOURS: [AnsiColors; 6] = [
+    AnsiColors::BrightRed,
+    AnsiColors::BrightGreen,
+    AnsiColors::BrightYellow,
+    AnsiColors::BrightBlue,
+    AnsiColors::BrightMagenta,
+    AnsiColors::BrightCyan,
+];
+
+/// Sum the bytes comprising the given public key.
+fn public_key_sum(public_key: &[u8; 32]) -> u64 {
+    // A return type of `u64` is used to avoid the overflow which will
+    // likely occur if returning `u8`.
+    public_key.iter().map(|x| *x as u64).sum()
+}
+
 /// Pick a colour based on the sum of the base16 digits comprising
 /// the given public key.
 pub fn public_key_to_colour(public_key: &[u8; 32]) -> AnsiColors {
-    // A return type of `u64` is used to avoid the overflow which will
-    // likely occur if returning `u8`.
-    let sum: u64 = public_key.iter().map(|x| *x as u64).sum();
+    pick_colour(public_key_sum(public_key) % 12)
+}
+
+/// Assign high-contrast colours to the given public keys, ordered from the
+/// most to the least frequent poster.
+///
+/// Each peer is preferably assigned the colour derived from their public key
+/// (as by `public_key_to_colour`). If that colour has been taken by a more
+/// frequent poster, the next free colour is assigned instead. Once all
+/// colours are taken, the remaining peers keep their preferred colour.
+pub fn assign_colours(public_keys: &[[u8; 32]]) -> HashMap<[u8; 32], AnsiColors> {
+    let len = CONTRAST_COLOURS.len();
+    let mut taken = [false; CONTRAST_COLOURS.len()];
+
+    public_keys
+        .iter()
+        .map(|public_key| {
+            let preferred = (public_key_sum(public_key) % len as u64) as usize;
+            let index = (0..len)
+                .map(|offset| (preferred + offset) % len)
+                .find(|index| !taken[*index])
+                .unwrap_or(preferred);
+            taken[index] = true;
+
+            (*public_key, CONTRAST_COLOURS[index])
+        })
+        .collect()
+}
+
+/// Determine whether the given text mentions the given nickname.
+///
+/// Matching is case-insensitive and only considers whole words, ignoring
+/// any punctuation which surrounds the nickname (e.g. "glyph:").
+pub fn is_mention(text: &str, nickname: &str) -> bool {
+    if nickname.is_empty() {
+        return false;
+    }
+
+    text.split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric() && c != '-' && c != '_'))
+        .any(|word| word.eq_ignore_ascii_case(nickname))
+}
+
+/// Score how well the given query matches the given candidate, where lower
+/// scores are better matches.
+///
+/// The characters of the query must appear in the candidate in order (but
+/// not necessarily adjacent), ignoring case. Matches beginning earlier in the
+/// candidate and with fewer characters between them score better. Returns
+/// `None` if the candidate does not match.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<usize> {
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    for (i, c) in query.to_lowercase().chars().enumerate() {
+        let offset = candidate[position..].iter().position(|&d| d == c)?;
+        // The position of the first match counts fully; subsequent gaps
+        // count double, so that contiguous matches are preferred.
+        score += if i == 0 { offset } else { offset * 2 };
+        position += offset + 1;
+    }
+
+    Some(score)
+}
+
+/// Format the given text as a reply to the post with the given hash.
+pub fn format_reply(hash: &Hash, text: &str) -> String {
+    format!("{}{}] {}", REPLY_PREFIX, hex::to(hash), text)
+}
+
+/// Parse a reply, returning the hash of the replied-to post and the text
+/// which follows the reply reference.
+pub fn parse_reply(text: &str) -> Option<(Hash, &str)> {
+    let rest = text.strip_prefix(REPLY_PREFIX)?;
+    let (hex_hash, text) = rest.split_once(']')?;
+    let hash = Hash::try_from(hex::from(hex_hash)?).ok()?;
+
+    Some((hash, text.trim_start()))
+}
+
+/// Return the first line of the given text, truncated to the given number of
+/// characters (with an ellipsis appended if truncated).
+pub fn snippet(text: &str, max_chars: usize) -> String {
+    let line = text.lines().next().unwrap_or_default();
+    if line.chars().count() > max_chars {
+        format!("{}…", line.chars().take(max_chars).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+/// Encode the given bytes as standard (padded) base64.
+pub fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+/// Split a line of input into whitespace-separated arguments.
+///
+/// Double quotes group words into a single argument (e.g. `"hello world"`)
+/// and a backslash escapes a following double quote, backslash or
+/// whitespace character. All other characters, including single quotes,
+/// are taken literally. An unterminated quote extends to the end of the
+/// line.
+pub fn parse_args(line: &str) -> Vec<String> {
+    split_args(line, usize::MAX).0
+}
+
+/// Split the first `n` arguments from a line of input (as `parse_args`),
+/// followed by the rest of the line as a single argument.
+///
+/// The rest of the line is free text (such as the text of a post or a
+/// topic), so its quotes, backslashes and spacing are kept as typed, unless
+/// it is quoted as a whole (e.g. `"hello world"`), in which case it is
+/// unquoted as a single argument.
+pub fn parse_args_then_text(line: &str, n: usize) -> Vec<String> {
+    let (mut args, rest) = split_args(line, n);
+    if !rest.is_empty() {
+        args.push(unquote(rest).unwrap_or_else(|| rest.to_string()));
+    }
+
+    args
+}
 
-    pick_colour(sum % 12)
+/// Return the given text parsed as a single argument if it is enclosed in
+/// double quotes which contain no other unescaped double quote.
+fn unquote(text: &str) -> Option<String> {
+    let inner = text.strip_prefix('"')?.strip_suffix('"')?;
+
+    let mut escaped = false;
+    for c in inner.chars() {
+        match c {
+            '"' if !escaped => return None,
+            '\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+    // The closing quote itself is escaped.
+    if escaped {
+        return None;
+    }
+
+    parse_args(text).pop()
+}
+
+/// Parse up to `n` arguments from the given line, returning them along with
+/// the unparsed rest of the line.
+fn split_args(line: &str, n: usize) -> (Vec<String>, &str) {
+    let mut args = Vec::new();
+    let mut arg: Option<String> = None;
+    let mut quoted = false;
+
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => match chars.peek() {
+                Some(&(_, next)) if next == '"' || next == '\\' || next.is_whitespace() => {
+                    arg.get_or_insert_with(String::new).push(next);
+                    chars.next();
+                }
+                _ => arg.get_or_insert_with(String::new).push(c),
+            },
+            '"' => {
+                quoted = !quoted;
+                // A pair of quotes denotes an argument, even if empty.
+                arg.get_or_insert_with(String::new);
+            }
+            c if c.is_whitespace() && !quoted => {
+                args.extend(arg.take());
+                if args.len() >= n {
+                    return (args, line[i..].trim());
+                }
+            }
+            c => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(arg);
+
+    (args, "")
+}
+
+/// Return the first URL (beginning with `http://` or `https://`) in the
+/// given text, without any trailing punctuation.
+pub fn find_url(text: &str) -> Option<&str> {
+    text.split_whitespace()
+        .find(|word| word.starts_with("http://") || word.starts_with("https://"))
+        .map(|url| url.trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '"', '\'']))
+}
+
+/// Open the given URL with the browser defined by the `BROWSER`
+/// environment variable, falling back to the default opener of the system.
+pub fn open_url(url: &str) -> io::Result<()> {
+    let opener = env::var("BROWSER").unwrap_or_else(|_| {
+        if cfg!(target_os = "macos") {
+            "open".to_string()
+        } else {
+            "xdg-open".to_string()
+        }
+    });
+    let mut child = Command::new(opener)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Reap the process once it exits.
+    thread::spawn(move || child.wait());
+
+    Ok(())
+}
+
+/// Remove all ANSI escape sequences (such as colours) from the given text.
+pub fn strip_ansi(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip a control sequence (`ESC [ ... FINAL`) or a single
+            // escaped character.
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        break;
+                    }
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+
+    stripped
+}
+
+/// Return the channels which fuzzily match the given query, sorted by name.
+///
+/// Channels containing the query (ignoring case) are preferred; if there are
+/// none, channels containing all characters of the query in order are
+/// returned instead (e.g. "cdev" matches "cabal-dev").
+pub fn match_channels<'a>(query: &str, channels: &'a [String]) -> Vec<&'a String> {
+    let query = query.to_lowercase();
+    let is_subsequence = |channel: &str| {
+        let mut chars = channel.chars();
+        query.chars().all(|q| chars.any(|c| c == q))
+    };
+
+    let mut matches: Vec<&String> = channels
+        .iter()
+        .filter(|channel| channel.to_lowercase().contains(&query))
+        .collect();
+    if matches.is_empty() {
+        matches = channels
+            .iter()
+            .filter(|channel| is_subsequence(&channel.to_lowercase()))
+            .collect();
+    }
+    matches.sort();
+
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reply_round_trips_format_reply() {
+        let hash = [7; 32];
+        let reply = format_reply(&hash, "hi there");
+
+        assert_eq!(parse_reply(&reply), Some((hash, "hi there")));
+    }
+
+    #[test]
+    fn parse_reply_rejects_malformed_references() {
+        let hex = "ab".repeat(32);
+        for text in [
+            "[re:abc] hi".to_string(),
+            "[re:é] x".to_string(),
+            "[re:] x".to_string(),
+            "[re:zz] x".to_string(),
+            format!("[re:{}] x", &hex[1..]),
+            format!("[re:{}a] x", hex),
+            format!("[re:{}é] x", &hex[2..]),
+            format!("[re:{}", hex),
+        ] {
+            assert_eq!(parse_reply(&text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_args_handles_quotes_and_escapes() {
+        assert_eq!(
+            parse_args(r#"/nick "cabin dweller" a\ b \"c\" it's"#),
+            vec!["/nick", "cabin dweller", "a b", "\"c\"", "it's"]
+        );
+        assert_eq!(parse_args(r#"/topic """#), vec!["/topic", ""]);
+    }
+
+    #[test]
+    fn parse_args_then_text_keeps_text_as_typed() {
+        assert_eq!(
+            parse_args_then_text(r#"/reply 2 she said "hi"  \o/ "#, 2),
+            vec!["/reply", "2", r#"she said "hi"  \o/"#]
+        );
+        assert_eq!(
+            parse_args_then_text(r#"/schedule "in 2h"   don't  "forget""#, 2),
+            vec!["/schedule", "in 2h", r#"don't  "forget""#]
+        );
+        assert_eq!(parse_args_then_text("/reply 2", 2), vec!["/reply", "2"]);
+        assert_eq!(parse_args_then_text("/reply 2   ", 2), vec!["/reply", "2"]);
+        assert_eq!(parse_args_then_text("/search", 1), vec!["/search"]);
+    }
+
+    #[test]
+    fn parse_args_then_text_unquotes_quoted_text() {
+        assert_eq!(
+            parse_args_then_text(r#"/topic "hello world""#, 1),
+            vec!["/topic", "hello world"]
+        );
+        assert_eq!(
+            parse_args_then_text(r#"/note alice "says \"hi\"  \o/""#, 2),
+            vec!["/note", "alice", r#"says "hi"  \o/"#]
+        );
+        assert_eq!(parse_args_then_text(r#"/topic """#, 1), vec!["/topic", ""]);
+
+        // Text which is not a single quoted argument is kept as typed.
+        for text in [r#""a" "b""#, r#""a"b""#, r#""a\""#, r#""a"#] {
+            assert_eq!(
+                parse_args_then_text(&format!("/topic {}", text), 1),
+                vec!["/topic", text]
+            );
+        }
+    }
+
+    #[test]
+    fn fuzzy_score_prefers_early_contiguous_matches() {
+        assert_eq!(fuzzy_score("dev", "dev"), Some(0));
+        assert_eq!(fuzzy_score("dev", "my-dev"), Some(3));
+        assert_eq!(fuzzy_score("dv", "dev"), Some(2));
+        assert_eq!(fuzzy_score("DEV", "Dev"), Some(0));
+        assert_eq!(fuzzy_score("ved", "dev"), None);
+        assert_eq!(fuzzy_score("devs", "dev"), None);
+
+        // Every candidate matches an empty query, and nothing but an empty
+        // query matches an empty candidate.
+        assert_eq!(fuzzy_score("", "dev"), Some(0));
+        assert_eq!(fuzzy_score("", ""), Some(0));
+        assert_eq!(fuzzy_score("d", ""), None);
+
+        // Characters are compared rather than bytes, ignoring case.
+        assert_eq!(fuzzy_score("ñ", "Español"), Some(4));
+        assert_eq!(fuzzy_score("ÉCOLE", "école"), Some(0));
+        assert_eq!(fuzzy_score("日本", "日本語"), Some(0));
+        assert_eq!(fuzzy_score("語", "日本語"), Some(2));
+    }
 }
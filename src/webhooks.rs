@@ -0,0 +1,111 @@
+//! Outgoing webhooks for channel events.
+//!
+//! Webhooks are configured per channel in the `[[webhooks]]` section of the
+//! configuration file. Whenever a new text post is received in the channel,
+//! a JSON payload describing the post is sent to the configured URL in a
+//! `POST` request.
+
+use serde::Deserialize;
+
+use cable::Channel;
+use cable_core::{CableManager, Store};
+
+/// The configuration of a single webhook.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookConfig {
+    /// The hex-encoded address of the cabal.
+    pub cabal: String,
+    /// The channel whose posts trigger the webhook.
+    pub channel: Channel,
+    /// The URL to which payloads are posted.
+    pub url: String,
+}
+
+/// Post a JSON payload to the configured URL for each text post published
+/// in the configured channel after the webhook is started.
+///
+/// The payload has the following fields: `cabal`, `channel`, `hash`,
+/// `public_key`, `author` (the nickname of the author, if known),
+/// `timestamp` and `text`.
+#[cfg(feature = "webhooks")]
+pub fn spawn<S: Store>(cable: CableManager<S>, config: WebhookConfig) -> Result<(), String> {
+    use std::time::Duration;
+
+    use async_std::{prelude::*, task};
+    use cable::{post::PostBody, ChannelOptions};
+    use log::{error, warn};
+    use serde_json::json;
+
+    use crate::{
+        hex,
+        time::{Clock, SystemClock},
+    };
+
+    let start = SystemClock.now().map_err(|err| err.to_string())?;
+    let opts = ChannelOptions {
+        channel: config.channel.clone(),
+        time_start: start,
+        time_end: 0,
+        limit: 4096,
+    };
+
+    let mut cable = cable;
+    task::spawn(async move {
+        let mut stream = match cable.open_channel(&opts).await {
+            Ok(stream) => stream,
+            Err(err) => {
+                error!("Failed to open webhook channel {}: {}", config.channel, err);
+                return;
+            }
+        };
+
+        while let Some(Ok(post)) = stream.next().await {
+            if post.header.timestamp < start {
+                continue;
+            }
+            let Ok(hash) = post.hash() else {
+                continue;
+            };
+
+            if let PostBody::Text { channel, text } = post.body {
+                let public_key = post.header.public_key;
+                let author = cable
+                    .store
+                    .get_peer_name_and_hash(&public_key)
+                    .await
+                    .map(|(name, _hash)| name);
+                let payload = json!({
+                    "cabal": config.cabal,
+                    "channel": channel,
+                    "hash": hex::to(&hash),
+                    "public_key": hex::to(&public_key),
+                    "author": author,
+                    "timestamp": post.header.timestamp,
+                    "text": text,
+                });
+
+                let url = config.url.clone();
+                let result = task::spawn_blocking(move || {
+                    ureq::post(&url)
+                        .timeout(Duration::from_secs(30))
+                        .send_json(payload)
+                })
+                .await;
+                if let Err(err) = result {
+                    warn!("Failed to deliver webhook to {}: {}", config.url, err);
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Post a JSON payload to the configured URL for each text post published
+/// in the configured channel after the webhook is started.
+#[cfg(not(feature = "webhooks"))]
+pub fn spawn<S: Store>(_cable: CableManager<S>, _config: WebhookConfig) -> Result<(), String> {
+    use crate::i18n::tr;
+
+    Err(tr("webhooks require cabin to be built with the `webhooks` feature").to_string())
+}
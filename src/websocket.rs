@@ -0,0 +1,258 @@
+//! A minimal WebSocket server transport.
+//!
+//! Accepted WebSocket connections are exposed as byte streams so that they
+//! can be passed to the cable manager just like TCP streams, allowing
+//! browser peers and TCP peers of the same cabal to exchange posts through a
+//! single node. Cable messages are carried in binary frames.
+
+use std::{
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use async_std::{net::TcpStream, prelude::*, task};
+use futures::{
+    channel::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    io::{AsyncRead, AsyncWrite},
+    Stream,
+};
+use log::debug;
+
+use crate::utils;
+
+/// The GUID appended to the client key to compute the accept key (RFC 6455).
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// The maximum accepted size of a single frame payload.
+const MAX_PAYLOAD: u64 = 16 * 1024 * 1024;
+
+/// The maximum accepted size of the opening handshake.
+const MAX_HANDSHAKE: usize = 8192;
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+
+/// The payloads of received data frames, along with any bytes not yet read.
+struct Inbound {
+    receiver: UnboundedReceiver<Vec<u8>>,
+    pending: Vec<u8>,
+}
+
+/// An accepted WebSocket connection.
+///
+/// Clones share the same connection.
+#[derive(Clone)]
+pub struct WebSocket {
+    inbound: Arc<Mutex<Inbound>>,
+    /// Encoded frames to be written to the connection.
+    outbound: UnboundedSender<Vec<u8>>,
+}
+
+/// Perform the opening handshake of a WebSocket connection on the given
+/// stream and start relaying frames.
+pub async fn accept(mut stream: TcpStream) -> io::Result<WebSocket> {
+    let request = read_handshake(&mut stream).await?;
+    let key = request
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _value)| name.trim().eq_ignore_ascii_case("sec-websocket-key"))
+        .map(|(_name, value)| value.trim().to_owned())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not a websocket request"))?;
+
+    let accept = utils::base64(
+        &sha1_smol::Sha1::from(format!("{}{}", key, GUID))
+            .digest()
+            .bytes(),
+    );
+    let response = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept
+    );
+    stream.write_all(response.as_bytes()).await?;
+
+    let (inbound_sender, receiver) = mpsc::unbounded();
+    let (outbound, mut outbound_receiver) = mpsc::unbounded::<Vec<u8>>();
+
+    // Write outgoing frames.
+    let mut writer = stream.clone();
+    task::spawn(async move {
+        while let Some(frame) = outbound_receiver.next().await {
+            if writer.write_all(&frame).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    // Read incoming frames, passing on the payloads of data frames.
+    let pong = outbound.clone();
+    task::spawn(async move {
+        loop {
+            match read_frame(&mut stream).await {
+                Ok((OPCODE_CONTINUATION | OPCODE_TEXT | OPCODE_BINARY, payload)) => {
+                    if inbound_sender.unbounded_send(payload).is_err() {
+                        break;
+                    }
+                }
+                Ok((OPCODE_PING, payload)) => {
+                    let _ = pong.unbounded_send(encode_frame(0xA, &payload));
+                }
+                Ok((OPCODE_CLOSE, _payload)) => {
+                    let _ = pong.unbounded_send(encode_frame(OPCODE_CLOSE, &[]));
+                    break;
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    debug!("WebSocket connection closed: {}", err);
+                    break;
+                }
+            }
+        }
+        pong.close_channel();
+    });
+
+    Ok(WebSocket {
+        inbound: Arc::new(Mutex::new(Inbound {
+            receiver,
+            pending: Vec::new(),
+        })),
+        outbound,
+    })
+}
+
+/// Read the opening handshake request, up to and including the blank line
+/// which terminates its headers.
+async fn read_handshake(stream: &mut TcpStream) -> io::Result<String> {
+    let mut request = Vec::new();
+    let mut byte = [0];
+    while !request.ends_with(b"\r\n\r\n") {
+        if request.len() > MAX_HANDSHAKE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "handshake too large",
+            ));
+        }
+        stream.read_exact(&mut byte).await?;
+        request.push(byte[0]);
+    }
+
+    Ok(String::from_utf8_lossy(&request).into_owned())
+}
+
+/// Read a single frame, returning its opcode and unmasked payload.
+async fn read_frame(stream: &mut TcpStream) -> io::Result<(u8, Vec<u8>)> {
+    let mut header = [0; 2];
+    stream.read_exact(&mut header).await?;
+    let opcode = header[0] & 0x0F;
+    let masked = header[1] & 0x80 != 0;
+
+    let len = match header[1] & 0x7F {
+        126 => {
+            let mut len = [0; 2];
+            stream.read_exact(&mut len).await?;
+            u16::from_be_bytes(len) as u64
+        }
+        127 => {
+            let mut len = [0; 8];
+            stream.read_exact(&mut len).await?;
+            u64::from_be_bytes(len)
+        }
+        len => len as u64,
+    };
+    if len > MAX_PAYLOAD {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "frame too large",
+        ));
+    }
+
+    let mut mask = [0; 4];
+    if masked {
+        stream.read_exact(&mut mask).await?;
+    }
+    let mut payload = vec![0; len as usize];
+    stream.read_exact(&mut payload).await?;
+    if masked {
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+    }
+
+    Ok((opcode, payload))
+}
+
+/// Encode a single unmasked (server-to-client) frame.
+fn encode_frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+
+    frame
+}
+
+impl AsyncRead for WebSocket {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut inbound = self.inbound.lock().unwrap();
+        while inbound.pending.is_empty() {
+            match Pin::new(&mut inbound.receiver).poll_next(cx) {
+                Poll::Ready(Some(payload)) => inbound.pending = payload,
+                // The connection has been closed.
+                Poll::Ready(None) => return Poll::Ready(Ok(0)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        let n = buf.len().min(inbound.pending.len());
+        buf[..n].copy_from_slice(&inbound.pending[..n]);
+        inbound.pending.drain(..n);
+
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl AsyncWrite for WebSocket {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self
+            .outbound
+            .unbounded_send(encode_frame(OPCODE_BINARY, buf))
+        {
+            Ok(()) => Poll::Ready(Ok(buf.len())),
+            Err(_) => Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let _ = self
+            .outbound
+            .unbounded_send(encode_frame(OPCODE_CLOSE, &[]));
+        self.outbound.close_channel();
+
+        Poll::Ready(Ok(()))
+    }
+}